@@ -0,0 +1,17 @@
+use std::process::Command;
+
+fn main() {
+    // the commit is only a hint for the bug reports, so building from a
+    // source tarball (without git) is fine.
+    let commit = Command::new("git")
+        .args(&["rev-parse", "--short=10", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| String::from_utf8(out.stdout).ok());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=WEBB_GIT_COMMIT={}", commit.trim());
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
@@ -1,20 +1,20 @@
 use core::fmt;
 
-use subxt::sp_core::sr25519::Pair as Sr25519Pair;
-use subxt::sp_core::Pair;
-use subxt::PairSigner;
+use subxt::sp_core::crypto::AccountId32;
 use uuid::Uuid;
 
 use crate::error::Error;
-use crate::keystore::{KeyPair, PublicFor};
-use crate::runtime::WebbRuntime;
+use crate::keystore::{AccountSigner, KeyPair, KeyScheme};
 
 pub struct Account {
     pub uuid: Uuid,
     pub alias: String,
-    pub address: PublicFor<Sr25519Pair>,
-    pub signer: PairSigner<WebbRuntime, Sr25519Pair>,
+    pub address: AccountId32,
+    pub signer: AccountSigner,
+    pub scheme: KeyScheme,
     pub seed: [u8; 32],
+    /// The `//hard/soft` path the pair was derived from the seed with.
+    pub derivation_path: Option<String>,
 }
 
 impl fmt::Debug for Account {
@@ -23,8 +23,10 @@ impl fmt::Debug for Account {
             .field("uuid", &self.uuid)
             .field("alias", &self.alias)
             .field("address", &self.address)
+            .field("scheme", &self.scheme)
             .field("signer", &"[....]")
             .field("entropy", &"[....]")
+            .field("derivation_path", &self.derivation_path)
             .finish()
     }
 }
@@ -42,8 +44,10 @@ impl Account {
             uuid,
             alias,
             seed,
-            address: keys.pair().public(),
-            signer: PairSigner::new(keys.pair().clone()),
+            address: keys.account_id(),
+            signer: keys.pair().signer(),
+            scheme: keys.scheme(),
+            derivation_path: None,
         };
         keys.clean();
         account
@@ -51,14 +55,16 @@ impl Account {
 }
 
 /// Generates new `KeyPair` and returns new [Account] with Paper backup phrase.
-pub fn generate(alias: String) -> (Account, String) {
-    let keys = KeyPair::new(None);
+pub fn generate(alias: String, scheme: KeyScheme) -> (Account, String) {
+    let keys = KeyPair::generate(scheme, None);
     let account = Account {
         alias,
         uuid: Uuid::new_v4(),
-        address: keys.pair().public(),
-        signer: PairSigner::new(keys.pair().clone()),
+        address: keys.account_id(),
+        signer: keys.pair().signer(),
+        scheme: keys.scheme(),
         seed: keys.seed(),
+        derivation_path: None,
     };
     let paper_key =
         keys.backup().expect("new generated accound have paper key");
@@ -66,15 +72,22 @@ pub fn generate(alias: String) -> (Account, String) {
     (account, paper_key)
 }
 
-/// Restores the [Account] using the Paper backup phrase.
-pub fn restore(alias: String, paper_key: &str) -> Result<Account, Error> {
-    let keys = KeyPair::restore(paper_key, None)?;
+/// Restores the [Account] using the Paper backup phrase, optionally followed
+/// by a `//hard/soft` derivation path.
+pub fn restore(
+    alias: String,
+    scheme: KeyScheme,
+    paper_key: &str,
+) -> Result<Account, Error> {
+    let keys = KeyPair::restore_with_scheme(scheme, paper_key, None)?;
     let account = Account {
         alias,
         uuid: Uuid::new_v4(),
-        address: keys.pair().public(),
-        signer: PairSigner::new(keys.pair().clone()),
+        address: keys.account_id(),
+        signer: keys.pair().signer(),
+        scheme: keys.scheme(),
         seed: keys.seed(),
+        derivation_path: keys.path().map(ToOwned::to_owned),
     };
     keys.clean();
     Ok(account)
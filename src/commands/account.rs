@@ -1,29 +1,64 @@
 use std::io::Write;
 
+use anyhow::Context;
 use async_trait::async_trait;
-use bip39::{Language, Mnemonic};
 use console::{style, Emoji};
 use dialoguer::theme::ColorfulTheme;
+use indicatif::{ProgressBar, ProgressStyle};
 use secrecy::SecretString;
 use structopt::StructOpt;
-use subxt::sp_core::crypto::{Ss58AddressFormat, Ss58Codec};
-use subxt::sp_runtime::traits::IdentifyAccount;
+use subxt::balances::TransferCall;
+use subxt::sp_core::crypto::{AccountId32, Ss58Codec};
+use subxt::system::*;
+use subxt::{Client, Signer};
+use webb_cli::keystore::{
+    parse_mnemonic, split_derivation_path, KeyPair, KeyScheme,
+};
+use webb_cli::runtime::WebbRuntime;
 
-use crate::context::ExecutionContext;
-use crate::ext::OptionPromptExt;
+use crate::context::{ExecutionContext, OutputFormat};
+use crate::ext::{self, OptionPromptExt};
+use crate::raw::AccountRaw;
+use crate::{transaction, utils};
 
 /// Modify or query the saved accounts.
 #[derive(StructOpt)]
 pub enum AccountCommand {
     /// List all accounts you own.
-    List,
+    List(ListAccounts),
     /// Imports an Account using the Mnemonic phrase
     /// or as we call it a `PaperKey`.
     Import(ImportAccount),
+    /// Shows the account of a mnemonic phrase, without saving it.
+    Inspect(InspectAccount),
     /// Generates a new account and save it.
     Generate(GenerateAccount),
+    /// Export the mnemonic phrase or the seed of an account.
+    Export(ExportAccount),
+    /// Rename an account.
+    Rename(RenameAccount),
+    /// Set the default account, same as `webb default`.
+    SetDefault(SetDefaultAccount),
     /// Remove/Forget an account.
     Forget(ForgetAccount),
+    /// Change the password used to encrypt the saved accounts and notes.
+    ChangePassword(ChangePassword),
+    /// Check that an account exists on-chain, and optionally activate it.
+    Touch(TouchAccount),
+    /// Show the on-chain balance of an account.
+    Balance(AccountBalance),
+}
+
+/// List all of the saved accounts, with the default one first.
+#[derive(StructOpt)]
+pub struct ListAccounts {
+    /// Fetch the accounts nonce from the chain, and mark the accounts
+    /// that never did any transaction as unused.
+    #[structopt(long)]
+    with_activity: bool,
+    /// Fetch the accounts free balance from the chain.
+    #[structopt(long)]
+    on_chain: bool,
 }
 
 /// To Restore an existing account.
@@ -41,9 +76,45 @@ pub struct ImportAccount {
     /// the paper key or the mnemonic seed phrase
     /// that got generated with this account.
     ///
-    /// could be also provided using the environment variable.
+    /// could be also provided using the environment variable, and it
+    /// could end with a `//hard/soft` derivation path, like the ones
+    /// of Polkadot.js.
     #[structopt(short, long, env = "WEBB_MNEMONIC")]
     mnemonic: Option<String>,
+    /// also save the mnemonic phrase (encrypted), so it could be exported
+    /// later using `webb account export`.
+    ///
+    /// by default only the derived seed is saved, which can't be turned
+    /// back into a mnemonic phrase.
+    #[structopt(long)]
+    store_phrase: bool,
+    /// the signature scheme of the account, `sr25519` or `ed25519`.
+    ///
+    /// the same phrase gives a different account under each scheme.
+    #[structopt(long, default_value = "sr25519")]
+    scheme: KeyScheme,
+}
+
+/// Shows the address and the public key a mnemonic phrase gives, like the
+/// `inspect` of Polkadot.js, so you could check it before importing it.
+///
+/// nothing gets saved.
+#[derive(StructOpt)]
+pub struct InspectAccount {
+    /// the mnemonic phrase, optionally followed by a `//hard/soft`
+    /// derivation path.
+    ///
+    /// passing it as an argument requires `--unsafe`, since it ends up in
+    /// your shell history, use the environment variable or the prompt
+    /// instead.
+    #[structopt(env = "WEBB_MNEMONIC", hide_env_values = true)]
+    mnemonic: Option<String>,
+    /// ask for the password the phrase was generated with, if any.
+    #[structopt(long)]
+    with_password: bool,
+    /// the signature scheme of the account, `sr25519` or `ed25519`.
+    #[structopt(long, default_value = "sr25519")]
+    scheme: KeyScheme,
 }
 
 /// For Generate a new account.
@@ -55,6 +126,56 @@ pub struct GenerateAccount {
     /// an easy to remember account name.
     #[structopt(short, long)]
     alias: Option<String>,
+    /// also save the mnemonic phrase (encrypted), so it could be exported
+    /// later using `webb account export`.
+    ///
+    /// by default only the derived seed is saved, which can't be turned
+    /// back into a mnemonic phrase.
+    #[structopt(long)]
+    store_phrase: bool,
+    /// the signature scheme of the account, `sr25519` or `ed25519`.
+    #[structopt(long, default_value = "sr25519")]
+    scheme: KeyScheme,
+}
+
+/// Export the backup of one of the saved accounts.
+///
+/// this prints the mnemonic phrase if it was saved using `--store-phrase`,
+/// otherwise the only available backup is the raw seed.
+///
+/// the exported secrets are never copied to the `--transcript` file, and
+/// they are only printed with `--unsafe` or once confirmed.
+#[derive(StructOpt)]
+pub struct ExportAccount {
+    /// the alias or the address of the account, defaults to the default
+    /// account.
+    #[structopt(short, long)]
+    account: Option<String>,
+    /// print the raw seed (hex) if the account has no saved mnemonic.
+    #[structopt(long)]
+    raw_seed: bool,
+}
+
+/// Sets the default account to be used for all operations.
+#[derive(StructOpt)]
+pub struct SetDefaultAccount {
+    /// the alias or the address of the account.
+    alias_or_address: String,
+}
+
+/// Renames one of the saved accounts.
+///
+/// the account keeps being the default account if it was.
+#[derive(StructOpt)]
+pub struct RenameAccount {
+    /// the alias or the address of the account to rename.
+    ///
+    /// leave empty to prompt with the saved accounts.
+    #[structopt(short, long)]
+    account: Option<String>,
+    /// the new account alias.
+    #[structopt(short, long)]
+    new_alias: Option<String>,
 }
 
 /// Removes the account from the local store.
@@ -64,64 +185,305 @@ pub struct GenerateAccount {
 /// to import an account see:
 ///
 ///     $ webb account import --help
+///
+/// this requires `--unsafe`, since without the mnemonic seed phrase
+/// the account is lost forever.
 #[derive(StructOpt)]
-pub struct ForgetAccount {}
+pub struct ForgetAccount {
+    /// the alias or the address of the account to forget.
+    ///
+    /// leave empty to prompt with the saved accounts.
+    #[structopt(short, long)]
+    account: Option<String>,
+    /// Don't ask for confirmation.
+    #[structopt(short, long)]
+    yes: bool,
+}
+
+/// Changes the password used to encrypt the saved accounts and notes.
+///
+/// every saved seed, mnemonic phrase and note gets re-encrypted using the
+/// new password, all at once, so it is never left half done. nothing
+/// changes if any of them was saved using another password, fix those first
+/// using `webb mixer fix-encryption`.
+///
+/// the accounts themselves don't change, their mnemonic phrases keep
+/// restoring the same accounts.
+#[derive(StructOpt)]
+pub struct ChangePassword {}
+
+/// Checks whether an account exists on-chain.
+///
+/// an account that never received the existential deposit doesn't exist
+/// on-chain, and some operations (i.e a withdraw to a brand new recipient)
+/// fail for it. with `--fund`, the default account transfers the existential
+/// deposit (or `--amount`) to it, to activate it.
+///
+/// Note: `--fund` moves real funds from your default account.
+#[derive(StructOpt)]
+pub struct TouchAccount {
+    /// the alias of one of the saved accounts, or any address.
+    account: String,
+    /// activate the account, if it doesn't exist, by transferring funds
+    /// to it from the default account.
+    #[structopt(long)]
+    fund: bool,
+    /// the amount to transfer in base units, defaults to the chain
+    /// existential deposit.
+    #[structopt(long, requires = "fund")]
+    amount: Option<u128>,
+    /// Don't ask for confirmation.
+    #[structopt(short, long)]
+    yes: bool,
+}
+
+/// Shows the free, reserved and frozen balances of an account.
+///
+/// an account that doesn't exist on-chain yet has a zero balance.
+#[derive(StructOpt)]
+pub struct AccountBalance {
+    /// the alias of one of the saved accounts, or any address.
+    ///
+    /// defaults to the default account.
+    #[structopt(short, long)]
+    alias_or_address: Option<String>,
+}
 
 #[async_trait]
 impl super::CommandExec for AccountCommand {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
         use AccountCommand::*;
         match self {
-            List => {
-                let mut accounts = context.accounts().to_owned();
-                let mut term = console::Term::stdout();
-                if accounts.is_empty() {
-                    write!(term, "{} ", style("uh oh").red())?;
-                    writeln!(term, "there is no accounts saved")?;
-                    writeln!(term, "try generating or importing them.")?;
-                    writeln!(term)?;
-                    writeln!(term, "$ webb account help")?;
-                    return Ok(());
-                }
-                // put the default account first.
-                accounts.sort_by(|a, b| b.is_default.cmp(&a.is_default));
-
-                for account in accounts {
-                    writeln!(term, "{}", account)?;
-                }
-                Ok(())
-            },
+            List(cmd) => cmd.exec(context).await,
             Import(cmd) => cmd.exec(context).await,
+            Inspect(cmd) => cmd.exec(context).await,
             Generate(cmd) => cmd.exec(context).await,
+            Export(cmd) => cmd.exec(context).await,
+            Rename(cmd) => cmd.exec(context).await,
+            SetDefault(cmd) => cmd.exec(context).await,
             Forget(cmd) => cmd.exec(context).await,
+            ChangePassword(cmd) => cmd.exec(context).await,
+            Touch(cmd) => cmd.exec(context).await,
+            Balance(cmd) => cmd.exec(context).await,
         }
     }
 }
 
+#[async_trait]
+impl super::CommandExec for ListAccounts {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut accounts = context.accounts().to_owned();
+        let mut term = context.term();
+        if accounts.is_empty() {
+            write!(term, "{} ", style("uh oh").red())?;
+            writeln!(term, "there is no accounts saved")?;
+            writeln!(term, "try generating or importing them.")?;
+            writeln!(term)?;
+            writeln!(term, "$ webb account help")?;
+            return Ok(());
+        }
+        // put the default account first.
+        accounts.sort_by(|a, b| b.is_default.cmp(&a.is_default));
+        let count = accounts.len();
+        let default_alias = accounts
+            .iter()
+            .find(|a| a.is_default)
+            .map(|a| a.alias.clone());
+        let fetched = if self.with_activity || self.on_chain {
+            Some(fetch_on_chain(context, &accounts).await)
+        } else {
+            None
+        };
+        let (client, states) = match fetched {
+            Some((client, states)) => (client, states),
+            None => (None, vec![None; accounts.len()]),
+        };
+        // after connecting, so the addresses use the chain format if we did.
+        let shown: Vec<_> = accounts
+            .iter()
+            .map(|a| context.display_account(a))
+            .collect();
+        // only needed to format the balances, if we could fetch them.
+        let props = match client {
+            Some(ref client) if self.on_chain => {
+                Some(context.system_properties(Some(client)))
+            },
+            _ => None,
+        };
+        let format_free = |state: &Option<OnChain>| {
+            let props = props.as_ref()?;
+            state.map(|s| {
+                utils::format_balance(
+                    s.free,
+                    props.token_decimals,
+                    &props.token_symbol,
+                )
+            })
+        };
+        match context.output() {
+            OutputFormat::Human => {
+                for (account, state) in shown.iter().zip(states) {
+                    write!(term, "{}", account)?;
+                    if self.with_activity {
+                        let activity = match state {
+                            Some(OnChain { nonce: 0, .. }) => {
+                                style(String::from("unused")).dim()
+                            },
+                            Some(OnChain { nonce, .. }) => {
+                                style(format!("nonce: {}", nonce)).green()
+                            },
+                            None => style(String::from("—")).dim(),
+                        };
+                        write!(term, " ({})", activity)?;
+                    }
+                    if self.on_chain {
+                        let free = match format_free(&state) {
+                            Some(free) => style(free).green(),
+                            None => style(String::from("unknown")).dim(),
+                        };
+                        write!(term, " {}", free)?;
+                    }
+                    writeln!(term)?;
+                }
+                writeln!(term)?;
+                let noun = if count == 1 { "account" } else { "accounts" };
+                let summary = match default_alias {
+                    Some(alias) => {
+                        format!("{} {}, default: {}", count, noun, alias)
+                    },
+                    None => format!("{} {}, no default", count, noun),
+                };
+                writeln!(term, "{}", style(summary).dim())?;
+            },
+            OutputFormat::Json => {
+                let values: Vec<_> = shown
+                    .iter()
+                    .zip(states)
+                    .map(|(account, state)| {
+                        let mut v = serde_json::json!({
+                            "alias": account.alias,
+                            "address": account.address,
+                            "is_default": account.is_default,
+                        });
+                        if self.with_activity {
+                            v["nonce"] =
+                                serde_json::json!(state.map(|s| s.nonce));
+                        }
+                        if self.on_chain {
+                            v["free"] = serde_json::json!(
+                                state.map(|s| s.free.to_string())
+                            );
+                        }
+                        v
+                    })
+                    .collect();
+                let value = serde_json::json!({
+                    "count": count,
+                    "default": default_alias,
+                    "accounts": values,
+                });
+                writeln!(term, "{}", serde_json::to_string_pretty(&value)?)?;
+            },
+        }
+        Ok(())
+    }
+}
+
+/// The on-chain state of a saved account, shown by `account list`.
+#[derive(Debug, Clone, Copy)]
+struct OnChain {
+    nonce: u32,
+    free: u128,
+}
+
+#[async_trait]
+impl super::CommandExec for SetDefaultAccount {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        context.set_default_account(&self.alias_or_address)?;
+        writeln!(term, "default: {}", self.alias_or_address)?;
+        Ok(())
+    }
+}
+
+/// Fetches the accounts on-chain state concurrently, returns `None` for the
+/// accounts we failed to query (i.e we are offline), along with the client
+/// if we could connect.
+async fn fetch_on_chain(
+    context: &ExecutionContext,
+    accounts: &[AccountRaw],
+) -> (Option<Client<WebbRuntime>>, Vec<Option<OnChain>>) {
+    let client = match context.client().await {
+        Ok(client) => client,
+        Err(e) => {
+            context.warn(format!(
+                "failed to connect to the node, the accounts on-chain state \
+                 is unknown: {}",
+                e
+            ));
+            return (None, vec![None; accounts.len()]);
+        },
+    };
+    let queries = accounts.iter().map(|account| {
+        let client = &client;
+        async move {
+            let account_id = AccountId32::from_ss58check(&account.address)
+                .map_err(|e| anyhow::anyhow!("bad address: {:?}", e))?;
+            let info = client.account(&account_id, None).await?;
+            anyhow::Result::<_>::Ok(OnChain {
+                nonce: info.nonce,
+                free: info.data.free,
+            })
+        }
+    });
+    let states = utils::fetch_concurrently(context.rpc_concurrency(), queries)
+        .await
+        .into_iter()
+        .map(|result| {
+            result
+                .map_err(|e| log::warn!("failed to fetch the account: {}", e))
+                .ok()
+        })
+        .collect();
+    (Some(client), states)
+}
+
 #[async_trait]
 impl super::CommandExec for ImportAccount {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         let theme = ColorfulTheme::default();
-        let alias = self.alias.unwrap_or_prompt("Account Alias", &theme)?;
+        let alias =
+            self.alias
+                .unwrap_or_prompt(context, "Account Alias", &theme)?;
         writeln!(term, "Importing account with {}", style(&alias).blue())?;
 
-        let paper_key = if let Some(paper_key) = self.mnemonic {
-            Mnemonic::from_phrase(&paper_key, Language::English)?
+        // the phrase could end with a `//hard/soft` derivation path.
+        let (paper_key, derivation_path) = if let Some(input) = self.mnemonic {
+            let (phrase, path) = split_derivation_path(&input);
+            let path = path.map(ToOwned::to_owned);
+            (parse_mnemonic(phrase)?, path)
         } else {
-            crate::utils::ask_for_phrase("Enter PaperKey (Mnemonic Seed): ")?
+            let phrase = crate::utils::ask_for_phrase(
+                "Enter PaperKey (Mnemonic Seed): ",
+            )?;
+            (phrase, None)
         };
         if !context.has_secret() {
             let password = Option::<SecretString>::None
                 .unwrap_or_prompt_password_with_confirmation(
-                    "Password", &theme,
+                    context, "Password", &theme,
                 )?;
             context.set_secret(password);
         }
-        let address = context.import_account(alias.clone(), paper_key)?;
-        let account = address
-            .into_account()
-            .to_ss58check_with_version(Ss58AddressFormat::SubstrateAccount);
+        let address = context.import_account(
+            alias.clone(),
+            self.scheme,
+            paper_key,
+            derivation_path.as_deref(),
+            self.store_phrase,
+        )?;
+        let account = context.display_address(&address.to_string());
         writeln!(term, "{} Account Imported!", Emoji("🎉", "※"))?;
         writeln!(
             term,
@@ -136,29 +498,101 @@ impl super::CommandExec for ImportAccount {
     }
 }
 
+#[async_trait]
+impl super::CommandExec for InspectAccount {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        let from_env = std::env::var("WEBB_MNEMONIC").ok();
+        let phrase = match self.mnemonic {
+            Some(phrase) => {
+                if from_env.as_ref() != Some(&phrase) && !context.is_unsafe() {
+                    anyhow::bail!(
+                        "the mnemonic phrase ends up in your shell history, \
+                         use `WEBB_MNEMONIC` or `--unsafe`"
+                    );
+                }
+                phrase
+            },
+            None => {
+                ext::ensure_can_prompt(context, "the mnemonic phrase")?;
+                crate::utils::ask_for_phrase(
+                    "Enter PaperKey (Mnemonic Seed): ",
+                )?
+                .into_phrase()
+            },
+        };
+        let password = if self.with_password {
+            let password: String = Option::<String>::None
+                .unwrap_or_prompt_password(
+                    context,
+                    "Phrase Password",
+                    &theme,
+                )?;
+            Some(password)
+        } else {
+            None
+        };
+        let keys = KeyPair::restore_with_scheme(
+            self.scheme,
+            &phrase,
+            password.as_deref(),
+        )?;
+        let address = context.display_address(&keys.account_id().to_string());
+        let public_key = hex::encode(keys.account_id());
+        let path = keys.path().map(ToOwned::to_owned);
+        keys.clean();
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "address": address,
+                "public_key": format!("0x{}", public_key),
+                "scheme": self.scheme.to_string(),
+                "derivation_path": path,
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        writeln!(term, "{}: {}", style("Address").bold(), address)?;
+        writeln!(term, "{}: 0x{}", style("Public Key").bold(), public_key)?;
+        writeln!(term, "{}: {}", style("Scheme").bold(), self.scheme)?;
+        if let Some(path) = path {
+            writeln!(term, "{}: {}", style("Derivation Path").bold(), path)?;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl super::CommandExec for GenerateAccount {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         let theme = ColorfulTheme::default();
-        let alias = self.alias.unwrap_or_prompt("Account Alias", &theme)?;
+        let alias =
+            self.alias
+                .unwrap_or_prompt(context, "Account Alias", &theme)?;
         writeln!(term, "Generating new account with {}", style(&alias).blue())?;
 
         if !context.has_secret() {
             let password = Option::<SecretString>::None
                 .unwrap_or_prompt_password_with_confirmation(
-                    "Password", &theme,
+                    context, "Password", &theme,
                 )?;
             context.set_secret(password);
         }
-        let (address, seed) = context.generate_account(alias.clone())?;
+        let (address, seed) = context.generate_account(
+            alias.clone(),
+            self.scheme,
+            self.store_phrase,
+        )?;
         writeln!(term, "{} Account Generated!", Emoji("🎉", "※"))?;
         writeln!(term)?;
         writeln!(
             term,
             "{}: {}",
             style(&alias).blue(),
-            style(address).dim().green()
+            style(context.display_address(&address.to_string()))
+                .dim()
+                .green()
         )?;
         writeln!(term)?;
         writeln!(
@@ -180,9 +614,415 @@ impl super::CommandExec for GenerateAccount {
     }
 }
 
+#[async_trait]
+impl super::CommandExec for ExportAccount {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let term = context.term();
+        let theme = ColorfulTheme::default();
+        let account = match self.account {
+            Some(val) => context
+                .accounts()
+                .iter()
+                .find(|a| a.matches(&val))
+                .cloned()
+                .with_context(|| format!("there is no account {}", val))?,
+            None => context.default_account()?.clone(),
+        };
+        let confirmed = context.is_unsafe() || {
+            ext::ensure_can_prompt(context, "`--unsafe`")?;
+            dialoguer::Confirm::with_theme(&theme)
+                .with_prompt(format!(
+                    "The backup gives full control of {}, export it?",
+                    account.alias
+                ))
+                .default(false)
+                .interact_on(&term)?
+        };
+        if !confirmed {
+            return Ok(());
+        }
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(context, "Password", &theme)?;
+            context.set_secret(password);
+        }
+        // the secrets are written to the terminal directly, and not through
+        // our `Term` writer, so they never end up in the transcript.
+        if let Some(phrase) = context.stored_phrase(&account.uuid)? {
+            term.write_line(&format!("{}", context.display_account(&account)))?;
+            term.write_line("Mnemonic seed:")?;
+            term.write_line(&format!("{}", style(phrase).bright().bold()))?;
+            return Ok(());
+        }
+        let seed = context.stored_seed(&account.uuid)?;
+        term.write_line(&format!(
+            "{} {} has no saved mnemonic phrase.",
+            Emoji("⚠️ ", "!!"),
+            account.alias
+        ))?;
+        if self.raw_seed {
+            term.write_line(
+                "Raw seed (hex), the only backup of this account:",
+            )?;
+            term.write_line(&format!(
+                "{}",
+                style(format!("0x{}", hex::encode(&seed.bytes)))
+                    .bright()
+                    .bold()
+            ))?;
+        } else {
+            term.write_line("the raw seed is the only available backup:")?;
+            term.write_line("    $ webb account export --raw-seed")?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for RenameAccount {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        let account = if let Some(val) = self.account {
+            val
+        } else {
+            let accounts = context.accounts();
+            if accounts.is_empty() {
+                write!(term, "{} ", style("uh oh").red())?;
+                writeln!(term, "there is no accounts saved")?;
+                return Ok(());
+            }
+            let items: Vec<_> =
+                accounts.iter().map(|a| format!("{}", a)).collect();
+            ext::ensure_can_prompt(context, "the account to rename")?;
+            let i = dialoguer::Select::with_theme(&theme)
+                .with_prompt("Select the account to rename")
+                .items(&items)
+                .default(0)
+                .interact_on(&term)?;
+            accounts[i].address.clone()
+        };
+        let new_alias =
+            self.new_alias
+                .unwrap_or_prompt(context, "New Alias", &theme)?;
+        let renamed = context.rename_account(&account, new_alias.clone())?;
+        if renamed {
+            writeln!(
+                term,
+                "{} Account renamed to {}",
+                Emoji("✅", "√"),
+                style(new_alias).blue()
+            )?;
+        } else {
+            write!(term, "{} ", style("uh oh").red())?;
+            writeln!(
+                term,
+                "there is no account with alias/address {}",
+                account
+            )?;
+            writeln!(term, "$ webb account list")?;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl super::CommandExec for ForgetAccount {
-    async fn exec(self, _context: &mut ExecutionContext) -> anyhow::Result<()> {
-        todo!("forget account")
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        if !context.is_unsafe() {
+            anyhow::bail!(
+                "forgetting an account can't be undone! make sure you have \
+                 its mnemonic seed phrase, then try again with `--unsafe`"
+            );
+        }
+        let accounts = context.accounts().to_owned();
+        if accounts.is_empty() {
+            write!(term, "{} ", style("uh oh").red())?;
+            writeln!(term, "there is no accounts saved")?;
+            return Ok(());
+        }
+        let account = match self.account {
+            Some(val) => accounts
+                .into_iter()
+                .find(|a| a.matches(&val))
+                .with_context(|| format!("there is no account {}", val))?,
+            None => {
+                let items: Vec<_> =
+                    accounts.iter().map(|a| format!("{}", a)).collect();
+                ext::ensure_can_prompt(context, "the account to forget")?;
+                let i = dialoguer::Select::with_theme(&theme)
+                    .with_prompt("Select the account to forget")
+                    .items(&items)
+                    .interact_on(&term)?;
+                accounts[i].clone()
+            },
+        };
+        let confirmed = self.yes || {
+            ext::ensure_can_prompt(context, "`--yes`")?;
+            dialoguer::Confirm::with_theme(&theme)
+                .with_prompt(format!("Forget the account {}?", account.alias))
+                .default(false)
+                .interact_on(&term)?
+        };
+        if !confirmed {
+            return Ok(());
+        }
+        let promoted = context.forget_account(&account.uuid)?;
+        writeln!(
+            term,
+            "{} Account {} forgotten.",
+            Emoji("🗑️ ", "√"),
+            style(&account.alias).blue()
+        )?;
+        if let Some(promoted) = promoted {
+            writeln!(
+                term,
+                "{} is now the default account.",
+                style(promoted.alias).blue()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for TouchAccount {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        let (address, account_id) = resolve_account(context, &self.account)?;
+        let pb = ProgressBar::new_spinner();
+        pb.enable_steady_tick(60);
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
+                .template("{prefix:.bold.dim} {spinner} {wide_msg}"),
+        );
+        let client = super::connect_with_feedback(context, &pb).await?;
+        pb.set_message("Checking the account...");
+        let info = client.account(&account_id, None).await?;
+        let existential_deposit: u128 = client
+            .metadata()
+            .module("Balances")?
+            .constant("ExistentialDeposit")?
+            .value()?;
+        pb.finish_and_clear();
+        let props = context.system_properties(Some(&client));
+        let format = |v| {
+            utils::format_balance(v, props.token_decimals, &props.token_symbol)
+        };
+        if info.providers > 0 || info.nonce > 0 {
+            writeln!(
+                term,
+                "{} {} exists on-chain, with a free balance of {}",
+                Emoji("✅", "√"),
+                style(&address).blue(),
+                style(format(info.data.free)).green()
+            )?;
+            return Ok(());
+        }
+        writeln!(
+            term,
+            "{} {} doesn't exist on-chain yet.",
+            Emoji("⚠️ ", "!"),
+            style(&address).blue(),
+        )?;
+        writeln!(
+            term,
+            "it needs to receive at least the existential deposit of {}.",
+            style(format(existential_deposit)).green()
+        )?;
+        if !self.fund {
+            writeln!(term, "to activate it from your default account:")?;
+            writeln!(term, "    $ webb account touch {} --fund", self.account)?;
+            return Ok(());
+        }
+        let amount = self.amount.unwrap_or(existential_deposit);
+        if amount < existential_deposit {
+            anyhow::bail!(
+                "{} is below the existential deposit of {}",
+                format(amount),
+                format(existential_deposit)
+            );
+        }
+        let confirmed = self.yes || {
+            ext::ensure_can_prompt(context, "`--yes`")?;
+            dialoguer::Confirm::with_theme(&theme)
+                .with_prompt(format!(
+                    "Transfer {} from your default account to {}?",
+                    format(amount),
+                    address
+                ))
+                .default(false)
+                .interact_on(&term)?
+        };
+        if !confirmed {
+            return Ok(());
+        }
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(
+                    context,
+                    "Default Account Password",
+                    &theme,
+                )?;
+            context.set_secret(password);
+        }
+        let signer = context
+            .signer()
+            .context("incorrect default account password!")?;
+        let funder = client.account(signer.account_id(), None).await?;
+        if funder.data.free < amount {
+            anyhow::bail!(
+                "the default account free balance {} is not enough to \
+                 transfer {}",
+                format(funder.data.free),
+                format(amount)
+            );
+        }
+        pb.reset();
+        pb.enable_steady_tick(60);
+        pb.set_message("Transferring...");
+        let rpc_client = context.rpc_client().await?;
+        transaction::sign_and_watch(
+            &client,
+            &rpc_client,
+            TransferCall {
+                to: &account_id,
+                amount,
+            },
+            &signer,
+            None,
+        )
+        .await?;
+        pb.finish_and_clear();
+        writeln!(
+            term,
+            "{} Transferred {} to {}, it is active now.",
+            Emoji("🎉", "※"),
+            style(format(amount)).green(),
+            style(&address).blue()
+        )?;
+        Ok(())
     }
 }
+
+#[async_trait]
+impl super::CommandExec for ChangePassword {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        let old = match context.secret() {
+            Some(secret) => secret.clone(),
+            None => Option::<SecretString>::None.unwrap_or_prompt_password(
+                context,
+                "Current Password",
+                &theme,
+            )?,
+        };
+        let new = Option::<SecretString>::None
+            .unwrap_or_prompt_password_with_confirmation(
+                context,
+                "New Password",
+                &theme,
+            )?;
+        context.set_secret(old.clone());
+        let unreadable: Vec<_> = context
+            .notes()
+            .iter()
+            .filter(|note| context.decrypt_note(note.uuid.clone()).is_err())
+            .cloned()
+            .collect();
+        let count = match context.change_password(old, new) {
+            Ok(count) => count,
+            Err(e) => {
+                // unless the password is wrong, anything saved using
+                // another password must be fixed first.
+                if unreadable.len() < context.notes().len() {
+                    for note in unreadable {
+                        context.warn(format!(
+                            "the note {} was saved using another password, \
+                             fix it using `webb mixer fix-encryption --uuid \
+                             {}`",
+                            note.alias, note.uuid
+                        ));
+                    }
+                }
+                return Err(e);
+            },
+        };
+        writeln!(
+            term,
+            "{} Password changed, {} secrets re-encrypted.",
+            Emoji("🔐", "√"),
+            count
+        )?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for AccountBalance {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let alias_or_address = match self.alias_or_address {
+            Some(v) => v,
+            None => context.default_account()?.address.clone(),
+        };
+        let (address, account_id) =
+            resolve_account(context, &alias_or_address)?;
+        let pb = ProgressBar::new_spinner();
+        pb.enable_steady_tick(60);
+        let client = super::connect_with_feedback(context, &pb).await?;
+        pb.set_message("Getting the balance...");
+        // a missing account has the default (zero) balance.
+        let data = client.account(&account_id, None).await?.data;
+        pb.finish_and_clear();
+        let frozen = data.misc_frozen.max(data.fee_frozen);
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "address": address,
+                "free": data.free.to_string(),
+                "reserved": data.reserved.to_string(),
+                "frozen": frozen.to_string(),
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        let props = context.system_properties(Some(&client));
+        let format = |v| {
+            utils::format_balance(v, props.token_decimals, &props.token_symbol)
+        };
+        writeln!(term, "Account: {}", style(&address).blue())?;
+        writeln!(term, "Free: {}", style(format(data.free)).green().bold())?;
+        writeln!(term, "Reserved: {}", format(data.reserved))?;
+        writeln!(term, "Frozen: {}", format(frozen))?;
+        Ok(())
+    }
+}
+
+/// Resolves `alias_or_address` to a saved account address, or to the
+/// address itself, along with its account id.
+fn resolve_account(
+    context: &ExecutionContext,
+    alias_or_address: &str,
+) -> anyhow::Result<(String, AccountId32)> {
+    let address = context
+        .accounts()
+        .iter()
+        .find(|a| a.matches(alias_or_address))
+        .map(|a| a.address.clone())
+        .unwrap_or_else(|| alias_or_address.to_owned());
+    let account_id = AccountId32::from_ss58check_with_version(&address)
+        .map(|(id, _)| id)
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "{} is neither a saved account nor a valid address: {:?}",
+                alias_or_address,
+                e
+            )
+        })?;
+    Ok((address, account_id))
+}
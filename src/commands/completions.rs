@@ -0,0 +1,35 @@
+use std::io::Write;
+
+use structopt::clap::{App, Shell};
+use structopt::StructOpt;
+
+/// Generates the shell completion script of the CLI.
+///
+/// the script is written to stdout, for example with bash:
+///
+///     $ webb completions bash > /etc/bash_completion.d/webb
+#[derive(StructOpt)]
+pub struct CompletionsCommand {
+    /// The shell to generate the script for.
+    #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+    shell: Shell,
+}
+
+impl CompletionsCommand {
+    /// Writes the completion script of the `app` to `out`.
+    ///
+    /// this doesn't need the datastore nor the node, so it runs before
+    /// opening any of them.
+    pub fn generate<W: Write>(
+        &self,
+        mut app: App,
+        out: &mut W,
+    ) -> std::io::Result<()> {
+        // clap panics if it fails to write (i.e stdout is closed by `head`),
+        // so the script is generated in memory first.
+        let mut script = Vec::new();
+        app.gen_completions_to("webb", self.shell, &mut script);
+        out.write_all(&script)?;
+        out.flush()
+    }
+}
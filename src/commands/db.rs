@@ -0,0 +1,215 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use console::style;
+use dialoguer::theme::ColorfulTheme;
+use prost::Message;
+use secrecy::SecretString;
+use structopt::StructOpt;
+
+use crate::context::{ExecutionContext, OutputFormat};
+use crate::ext::OptionPromptExt;
+use crate::raw::Backup;
+use crate::utils;
+
+/// Manage the local datastore.
+#[derive(StructOpt)]
+pub enum DbCommand {
+    /// Exports all the accounts and notes into a backup file.
+    Export(ExportBackup),
+    /// Imports the accounts and notes of a backup file.
+    Import(ImportBackup),
+    /// Checks that all the accounts and notes records are readable.
+    Verify(VerifyDatastore),
+}
+
+/// Exports all the accounts and notes into a single backup file.
+///
+/// the seeds and the notes secrets stay encrypted with your password,
+/// so the backup is as safe as your password is strong.
+#[derive(StructOpt)]
+pub struct ExportBackup {
+    /// the backup file, it must not exist already.
+    #[structopt(long = "out", parse(from_os_str))]
+    out: PathBuf,
+}
+
+/// Imports the accounts and notes of a backup file, the ones you already
+/// have are skipped.
+///
+/// the backup must be exported using the same password.
+#[derive(StructOpt)]
+pub struct ImportBackup {
+    /// the backup file, written by `webb db export`.
+    #[structopt(long = "in", parse(from_os_str))]
+    input: PathBuf,
+}
+
+/// Checks that all the accounts and notes records are readable, exits
+/// with an error if any of them is not.
+///
+/// the seeds and the notes secrets are only checked if the password is
+/// provided, they are never printed.
+#[derive(StructOpt)]
+pub struct VerifyDatastore {}
+
+#[async_trait]
+impl super::CommandExec for DbCommand {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        match self {
+            DbCommand::Export(cmd) => cmd.exec(context).await,
+            DbCommand::Import(cmd) => cmd.exec(context).await,
+            DbCommand::Verify(cmd) => cmd.exec(context).await,
+        }
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for ExportBackup {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let backup = context.export_backup()?;
+        let mut buf = Vec::new();
+        backup.encode(&mut buf)?;
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        // the backup holds all of the (encrypted) secrets.
+        let mut file = utils::open_private(options, &self.out)
+            .with_context(|| format!("creating {}", self.out.display()))?;
+        file.write_all(&buf)?;
+        file.sync_all()?;
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "path": self.out,
+                "accounts": backup.accounts.len(),
+                "notes": backup.notes.len(),
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        writeln!(
+            term,
+            "Exported {} accounts and {} notes to {}",
+            style(backup.accounts.len()).green(),
+            style(backup.notes.len()).green(),
+            self.out.display()
+        )?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for ImportBackup {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        let buf = std::fs::read(&self.input)
+            .with_context(|| format!("reading {}", self.input.display()))?;
+        let backup = Backup::decode(buf.as_slice())
+            .context("the file is not a webb backup")?;
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(context, "Password", &theme)?;
+            context.set_secret(password);
+        }
+        let imported = context.import_backup(backup)?;
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "accounts": imported.accounts,
+                "notes": imported.notes,
+                "skipped": imported.skipped,
+                "renamed": imported
+                    .renamed
+                    .iter()
+                    .map(|(alias, new_alias)| {
+                        serde_json::json!({"alias": alias, "new_alias": new_alias})
+                    })
+                    .collect::<Vec<_>>(),
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        writeln!(
+            term,
+            "Imported {} accounts and {} notes",
+            style(imported.accounts).green(),
+            style(imported.notes).green()
+        )?;
+        if imported.skipped > 0 {
+            writeln!(
+                term,
+                "skipped {} that you already have.",
+                imported.skipped
+            )?;
+        }
+        for (alias, new_alias) in &imported.renamed {
+            writeln!(
+                term,
+                "there is already a note named {}, imported it as {}.",
+                alias,
+                style(new_alias).blue()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for VerifyDatastore {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let checks = context.verify_records()?;
+        let unreadable: Vec<_> =
+            checks.iter().filter(|c| c.problem.is_some()).collect();
+        let readable = checks.len() - unreadable.len();
+        if context.output() == OutputFormat::Json {
+            let problems: Vec<_> = unreadable
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "record": c.record,
+                        "error": c.problem,
+                    })
+                })
+                .collect();
+            let value = serde_json::json!({
+                "total": checks.len(),
+                "readable": readable,
+                "unreadable": unreadable.len(),
+                "secrets_checked": context.has_secret(),
+                "problems": problems,
+            });
+            writeln!(term, "{}", value)?;
+        } else {
+            for check in &unreadable {
+                write!(term, "{} ", style("unreadable").red())?;
+                writeln!(
+                    term,
+                    "{}: {}",
+                    check.record,
+                    check.problem.as_deref().unwrap_or_default()
+                )?;
+            }
+            writeln!(term, "Total Records: {}", checks.len())?;
+            writeln!(term, "Readable: {}", style(readable).green())?;
+            writeln!(term, "Unreadable: {}", style(unreadable.len()).red())?;
+            if !context.has_secret() {
+                writeln!(
+                    term,
+                    "the secrets were not checked, provide the password to \
+                     check them too."
+                )?;
+            }
+        }
+        if !unreadable.is_empty() {
+            anyhow::bail!(
+                "found {} unreadable records in the datastore",
+                unreadable.len()
+            );
+        }
+        Ok(())
+    }
+}
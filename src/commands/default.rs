@@ -5,7 +5,8 @@ use async_trait::async_trait;
 use dialoguer::theme::ColorfulTheme;
 use structopt::StructOpt;
 
-use crate::context::ExecutionContext;
+use crate::context::{ExecutionContext, OutputFormat};
+use crate::ext;
 
 /// Set the default account to be used for all operations.
 #[derive(StructOpt)]
@@ -17,12 +18,36 @@ pub struct DefaultCommand {
     /// to list all accounts you own try `webb account list`.
     #[structopt(short, long)]
     alias_or_address: Option<String>,
+    /// Only print the current default account, without changing it.
+    #[structopt(long, conflicts_with = "alias-or-address")]
+    show: bool,
 }
 
 #[async_trait]
 impl super::CommandExec for DefaultCommand {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
+        if self.show {
+            let default = context
+                .default_account()
+                .ok()
+                .map(|account| context.display_account(account));
+            if context.output() == OutputFormat::Json {
+                let value = default.map(|account| {
+                    serde_json::json!({
+                        "alias": account.alias,
+                        "address": account.address,
+                    })
+                });
+                writeln!(term, "{}", value.unwrap_or_default())?;
+            } else if let Some(account) = default {
+                writeln!(term, "{}", account)?;
+            } else {
+                writeln!(term, "there is no default account set.")?;
+                writeln!(term, "    $ webb default -a <ALIAS_OR_ADDRESS>")?;
+            }
+            return Ok(());
+        }
         let handler = if let Some(val) = self.alias_or_address {
             Result::<_, anyhow::Error>::Ok(val)
         } else {
@@ -37,18 +62,15 @@ impl super::CommandExec for DefaultCommand {
             if non_default_accounts.is_empty() {
                 bail!("you don't have any accounts saved.");
             }
+            ext::ensure_can_prompt(context, "the account alias or address")?;
             let i = dialoguer::Select::with_theme(&ColorfulTheme::default())
                 .with_prompt("Select one of these accounts")
                 .items(&non_default_accounts)
                 .interact_on(&term)?;
             Ok(non_default_accounts[i].clone())
         }?;
-        let changed = context.set_default_account(&handler)?;
-        if changed {
-            writeln!(term, "default: {}", handler)?;
-        } else {
-            bail!("no account with alias nor address equal to: {}", handler);
-        }
+        context.set_default_account(&handler)?;
+        writeln!(term, "default: {}", handler)?;
         Ok(())
     }
 }
@@ -0,0 +1,90 @@
+use std::io::Write;
+
+use async_trait::async_trait;
+use console::style;
+use dialoguer::theme::ColorfulTheme;
+use secrecy::SecretString;
+use structopt::StructOpt;
+
+use crate::context::{ExecutionContext, OutputFormat};
+use crate::ext::OptionPromptExt;
+use crate::raw::SeedScheme;
+
+/// Query the local secure keystore.
+#[derive(StructOpt)]
+pub enum KeystoreCommand {
+    /// Shows how an account seed is stored, without revealing it.
+    Inspect(InspectSeed),
+}
+
+/// Shows the stored seed scheme and length of an account.
+///
+/// the seed bytes are never printed, but the password is still required
+/// to decrypt the seed.
+#[derive(StructOpt)]
+pub struct InspectSeed {
+    /// the alias or the address of the account.
+    account: String,
+}
+
+#[async_trait]
+impl super::CommandExec for KeystoreCommand {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        match self {
+            KeystoreCommand::Inspect(cmd) => cmd.exec(context).await,
+        }
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for InspectSeed {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = ColorfulTheme::default();
+        let account = context
+            .accounts()
+            .iter()
+            .find(|a| a.matches(&self.account))
+            .cloned();
+        let account = match account {
+            Some(account) => account,
+            None => {
+                write!(term, "{} ", style("uh oh").red())?;
+                writeln!(term, "there is no account {}", self.account)?;
+                writeln!(term, "$ webb account list")?;
+                return Ok(());
+            },
+        };
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(context, "Password", &theme)?;
+            context.set_secret(password);
+        }
+        let seed = context.stored_seed(&account.uuid)?;
+        let scheme = match SeedScheme::from_i32(seed.scheme) {
+            Some(scheme) => scheme.to_string(),
+            None => format!("unknown (#{})", seed.scheme),
+        };
+        let account = context.display_account(&account);
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "alias": account.alias,
+                "address": account.address,
+                "version": seed.version,
+                "scheme": scheme,
+                "derivation_path": account.derivation_path,
+                "seed_length": seed.bytes.len(),
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        writeln!(term, "{}", account)?;
+        writeln!(term, "Format Version: {}", seed.version)?;
+        writeln!(term, "Scheme: {}", style(scheme).green())?;
+        if !account.derivation_path.is_empty() {
+            writeln!(term, "Derivation Path: {}", account.derivation_path)?;
+        }
+        writeln!(term, "Seed Length: {} bytes", seed.bytes.len())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use structopt::clap::{App, AppSettings, ErrorKind};
+use structopt::StructOpt;
+
+/// Generates the man pages of the CLI, one for every command.
+///
+/// meant for packagers, so it is hidden from the help.
+#[derive(StructOpt)]
+pub struct ManCommand {
+    /// Write the pages of all the commands to this directory, as
+    /// `webb-<command>.1` files, instead of printing one to stdout.
+    #[structopt(long, value_name = "DIR", parse(from_os_str))]
+    out_dir: Option<PathBuf>,
+    /// The command to print the page of, like `mixer deposit`, defaults to
+    /// the top-level page.
+    #[structopt(conflicts_with = "out-dir")]
+    command: Vec<String>,
+}
+
+impl ManCommand {
+    /// Renders the pages of the `app` command tree.
+    ///
+    /// like the completions, this doesn't need the datastore nor the node.
+    pub fn generate<F>(&self, app: F) -> anyhow::Result<()>
+    where
+        F: Fn() -> App<'static, 'static>,
+    {
+        match self.out_dir {
+            Some(ref dir) => {
+                fs::create_dir_all(dir).with_context(|| {
+                    format!("failed to create {}", dir.display())
+                })?;
+                let mut written = 0;
+                for path in command_paths(&app(), Vec::new()) {
+                    let page = render_page(&app, &path)?;
+                    let file = dir.join(format!("{}.1", page_name(&path)));
+                    write_page(&file, &page)?;
+                    written += 1;
+                }
+                eprintln!("{} man pages written to {}", written, dir.display());
+            },
+            None => {
+                let page = render_page(&app, &self.command)?;
+                std::io::stdout().write_all(page.as_bytes())?;
+            },
+        }
+        Ok(())
+    }
+}
+
+/// All the visible command paths under `app`, starting with the `prefix`
+/// itself.
+fn command_paths(app: &App, prefix: Vec<String>) -> Vec<Vec<String>> {
+    let mut paths = vec![prefix.clone()];
+    // clap 2 has no public accessor for the subcommands, the completions
+    // generator walks them the same way.
+    for sub in &app.p.subcommands {
+        let name = sub.get_name();
+        if name == "help" || sub.p.is_set(AppSettings::Hidden) {
+            continue;
+        }
+        let mut path = prefix.clone();
+        path.push(name.to_owned());
+        paths.extend(command_paths(sub, path));
+    }
+    paths
+}
+
+fn page_name(path: &[String]) -> String {
+    std::iter::once("webb")
+        .chain(path.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Renders the man page of the command at `path`, using its `--help`.
+fn render_page<F>(app: F, path: &[String]) -> anyhow::Result<String>
+where
+    F: Fn() -> App<'static, 'static>,
+{
+    let args = std::iter::once("webb")
+        .chain(path.iter().map(String::as_str))
+        .chain(std::iter::once("--help"));
+    let help = match app().set_term_width(80).get_matches_from_safe(args) {
+        Err(e) if e.kind == ErrorKind::HelpDisplayed => e.message,
+        Err(e) => anyhow::bail!("unknown command `{}`: {}", path.join(" "), e),
+        Ok(_) => anyhow::bail!("no help for `{}`", path.join(" ")),
+    };
+    let name = page_name(path);
+    // the first help line is the name and version, then the summary.
+    let summary = help.lines().nth(1).unwrap_or_default();
+    let mut page = String::new();
+    page.push_str(&format!(
+        ".TH {} 1 \"\" \"webb {}\"\n",
+        escape(&name.to_uppercase()),
+        escape(env!("CARGO_PKG_VERSION"))
+    ));
+    page.push_str(".SH NAME\n");
+    page.push_str(&format!("{} \\- {}\n", escape(&name), escape(summary)));
+    page.push_str(".SH DESCRIPTION\n.nf\n");
+    for line in help.lines().skip(1) {
+        page.push_str(&escape_line(line));
+        page.push('\n');
+    }
+    page.push_str(".fi\n");
+    if !path.is_empty() {
+        let parent = page_name(&path[..path.len() - 1]);
+        page.push_str(".SH SEE ALSO\n");
+        page.push_str(&format!("{}(1)\n", escape(&parent)));
+    }
+    Ok(page)
+}
+
+/// Escapes the roff special characters of `text`.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+/// Escapes a whole line, so it can't be taken as a roff request.
+fn escape_line(line: &str) -> String {
+    let line = escape(line);
+    if line.starts_with('.') || line.starts_with('\'') {
+        format!("\\&{}", line)
+    } else {
+        line
+    }
+}
+
+fn write_page(path: &Path, page: &str) -> anyhow::Result<()> {
+    fs::write(path, page)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
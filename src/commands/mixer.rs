@@ -1,4 +1,6 @@
+use std::fmt;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::Context;
@@ -8,31 +10,55 @@ use indicatif::{ProgressBar, ProgressStyle};
 use jsonrpsee_types::jsonrpc::Params;
 use secrecy::SecretString;
 use structopt::StructOpt;
-use subxt::sp_core::crypto::AccountId32;
+use subxt::sp_core::crypto::{AccountId32, Ss58Codec};
+use subxt::sp_core::{sr25519, Pair};
 use subxt::system::*;
-use subxt::{RpcClient, Signer};
-use webb_cli::mixer::{Mixer, Note, TokenSymbol};
+use subxt::{Client, PairSigner, RpcClient, Signer};
+use webb_cli::keystore::AccountSigner;
+use webb_cli::mixer::{self, Mixer, Note, TokenSymbol};
 use webb_cli::pallet::merkle::*;
 use webb_cli::pallet::mixer::*;
 use webb_cli::pallet::ScalarData;
 use webb_cli::runtime::WebbRuntime;
 
-use crate::context::{ExecutionContext, SystemProperties};
-use crate::ext::OptionPromptExt;
+use crate::context::{
+    AmbiguousAlias, ExecutionContext, NoteMetadata, OutputFormat,
+};
+use crate::ext::{self, OptionPromptExt};
+use crate::raw::{unused_totals, NoteFilter, NoteRaw, UsedFilter};
+use crate::relayer::{RelayerClient, WithdrawRequest, WithdrawStatus};
+use crate::{paper, transaction, utils};
 
 /// Webb Crypto Mixer.
 #[derive(StructOpt)]
 pub enum MixerCommand {
     /// List all of your saved Notes.
-    ListNotes,
+    ListNotes(ListNotes),
+    /// List the Mixer Groups available on the chain.
+    ListMixers,
+    /// Show the deposits count and the total value locked of each Mixer
+    /// Group, the more deposits the bigger its anonymity set.
+    Balance,
     /// Imports a previously generated Note.
     ImportNote(ImportNote),
+    /// Decode a Note and show what it is for, without importing it.
+    NoteInfo(NoteInfo),
     /// Generates a new Note and save it.
     GenerateNote(GenerateNote),
     /// Remove/Forget a Note.
     ForgetNote(ForgetNote),
+    /// Change the alias of a saved Note.
+    RenameNote(RenameNote),
+    /// Re-encrypt a Note that was saved using another password.
+    FixEncryption(FixEncryption),
+    /// Print (or copy) a saved Note, to use it somewhere else.
+    ExportNote(ExportNote),
+    /// Save a printable paper backup of a Note.
+    Paper(PaperNote),
     /// Deposit crypto assets to the mixer.
     Deposit(DepositAsset),
+    /// Estimate the total cost of a deposit without doing it.
+    SimulateDeposit(SimulateDeposit),
     /// Withdraw a previously deposited asset from the mixer.
     Withdraw(WithdrawAsset),
 }
@@ -41,34 +67,276 @@ pub enum MixerCommand {
 impl super::CommandExec for MixerCommand {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
         match self {
-            MixerCommand::ListNotes => {
-                let mut term = console::Term::stdout();
-                let mut notes = context.notes().to_owned();
-                if notes.is_empty() {
-                    writeln!(term)?;
-                    writeln!(term, "there is no Notes saved")?;
-                    writeln!(term, "try generating or importing them.")?;
-                    writeln!(term)?;
-                    writeln!(term, "$ webb mixer help")?;
-                    return Ok(());
-                }
-                // put the unused account first.
-                notes.sort_by(|a, b| b.used.cmp(&a.used));
-
-                for note in notes {
-                    writeln!(term, "{}", note)?;
-                }
-                Ok(())
-            },
+            MixerCommand::ListNotes(cmd) => cmd.exec(context).await,
             MixerCommand::ImportNote(cmd) => cmd.exec(context).await,
+            MixerCommand::NoteInfo(cmd) => cmd.exec(context).await,
             MixerCommand::GenerateNote(cmd) => cmd.exec(context).await,
             MixerCommand::ForgetNote(cmd) => cmd.exec(context).await,
+            MixerCommand::RenameNote(cmd) => cmd.exec(context).await,
+            MixerCommand::FixEncryption(cmd) => cmd.exec(context).await,
+            MixerCommand::ExportNote(cmd) => cmd.exec(context).await,
+            MixerCommand::ListMixers => list_mixers(context).await,
+            MixerCommand::Balance => mixers_balance(context).await,
+            MixerCommand::Paper(cmd) => cmd.exec(context).await,
             MixerCommand::Deposit(cmd) => cmd.exec(context).await,
+            MixerCommand::SimulateDeposit(cmd) => cmd.exec(context).await,
             MixerCommand::Withdraw(cmd) => cmd.exec(context).await,
         }
     }
 }
 
+/// List the saved Notes, optionally only the ones matching the filters.
+#[derive(StructOpt)]
+pub struct ListNotes {
+    /// Only show the `used`, `unused` or `all` notes.
+    #[structopt(long, default_value = "all")]
+    filter: UsedFilter,
+    /// Only show the notes of this token symbol.
+    #[structopt(long)]
+    token: Option<String>,
+    /// Only show the notes of this deposit size, in base units.
+    #[structopt(long)]
+    size: Option<u128>,
+}
+
+impl ListNotes {
+    fn filter(&self) -> NoteFilter {
+        NoteFilter {
+            used: self.filter,
+            token: self.token.clone(),
+            size: self.size,
+        }
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for ListNotes {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let filter = self.filter();
+        let mut notes = filter.apply(context.notes().to_owned());
+        let (totals, unknown) = unused_totals(&notes);
+        let human_totals: Vec<_> = totals
+            .iter()
+            .map(|(symbol, decimals, total)| {
+                utils::format_balance(*total, *decimals as u8, symbol)
+            })
+            .collect();
+        // an unreadable note can't be matched against a filter.
+        let unreadable = if filter.is_empty() {
+            context.unreadable_notes()
+        } else {
+            Default::default()
+        };
+        if context.output() == OutputFormat::Json {
+            let values: Vec<_> = notes
+                .iter()
+                .map(|note| {
+                    serde_json::json!({
+                        "alias": note.alias,
+                        "uuid": note.uuid,
+                        "used": note.used,
+                        "amount": note.amount().map(|v| v.to_string()),
+                        "token_symbol": note.token_symbol,
+                        "mixer_id": note.mixer_id,
+                    })
+                })
+                .chain(unreadable.iter().map(|(uuid, reason)| {
+                    serde_json::json!({
+                        "uuid": uuid,
+                        "error": reason,
+                    })
+                }))
+                .collect();
+            let totals: serde_json::Map<_, _> = totals
+                .iter()
+                .zip(&human_totals)
+                .map(|((symbol, _, total), human)| {
+                    let total = serde_json::json!({
+                        "amount": total.to_string(),
+                        "human": human,
+                    });
+                    (symbol.clone(), total)
+                })
+                .collect();
+            let value = serde_json::json!({
+                "notes": values,
+                "totals": totals,
+                "unknown_amounts": unknown,
+            });
+            let json = serde_json::to_string_pretty(&value)?;
+            writeln!(term, "{}", json)?;
+            return Ok(());
+        }
+        if notes.is_empty() && !filter.is_empty() {
+            writeln!(term, "no Notes match the filters.")?;
+            return Ok(());
+        }
+        if notes.is_empty() && unreadable.is_empty() {
+            writeln!(term)?;
+            writeln!(term, "there is no Notes saved")?;
+            writeln!(term, "try generating or importing them.")?;
+            writeln!(term)?;
+            writeln!(term, "$ webb mixer help")?;
+            return Ok(());
+        }
+        // put the unused account first.
+        notes.sort_by(|a, b| b.used.cmp(&a.used));
+
+        // a note we can't understand (i.e saved by a newer version)
+        // should never hide the other notes.
+        let warn = Emoji("⚠️ ", "!");
+        for note in notes {
+            match TokenSymbol::from_str(&note.token_symbol) {
+                Ok(_) => writeln!(term, "{}", note)?,
+                Err(e) => writeln!(
+                    term,
+                    "{} {}: unparseable note: {}",
+                    warn, note.alias, e
+                )?,
+            }
+        }
+        for (uuid, reason) in unreadable {
+            writeln!(term, "{} {}: unparseable note: {}", warn, uuid, reason)?;
+        }
+        // used notes are listed, but they are not worth anything anymore.
+        if !human_totals.is_empty() || unknown > 0 {
+            let mut summary = if human_totals.is_empty() {
+                String::from("Total unused: unknown")
+            } else {
+                format!("Total unused: {}", human_totals.join(", "))
+            };
+            if unknown > 0 {
+                summary.push_str(&format!(
+                    " ({} {} with an unknown amount)",
+                    unknown,
+                    if unknown == 1 { "note" } else { "notes" }
+                ));
+            }
+            writeln!(term)?;
+            writeln!(term, "{}", style(summary).dim())?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the mixer groups on the chain, with their deposit sizes.
+async fn list_mixers(context: &ExecutionContext) -> anyhow::Result<()> {
+    let mut term = context.term();
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(60);
+    let client = super::connect_to_mixer(context, &pb).await?;
+    pb.set_message("Getting Mixer Groups ..");
+    let mixer_groups = context.fetch_mixers().await?;
+    pb.finish_and_clear();
+    let props = context.system_properties(Some(&client));
+    // only the native currency (#0) symbol is known.
+    let symbol = |currency_id: u64| match currency_id {
+        0 => props.token_symbol.clone(),
+        id => format!("(currency #{})", id),
+    };
+    if context.output() == OutputFormat::Json {
+        let values: Vec<_> = mixer_groups
+            .iter()
+            .map(|(id, info)| {
+                serde_json::json!({
+                    "id": id,
+                    "deposit_size": info.fixed_deposit_size.to_string(),
+                    "currency_id": info.currency_id,
+                    "token_symbol": symbol(info.currency_id),
+                })
+            })
+            .collect();
+        writeln!(term, "{}", serde_json::to_string_pretty(&values)?)?;
+        return Ok(());
+    }
+    if mixer_groups.is_empty() {
+        write!(term, "{} ", style("uh oh").red())?;
+        writeln!(term, "there is no mixers available on this chain.")?;
+        return Ok(());
+    }
+    for (id, info) in &mixer_groups {
+        let size = utils::format_balance(
+            info.fixed_deposit_size,
+            props.token_decimals,
+            &symbol(info.currency_id),
+        );
+        writeln!(term, "- #{} Mixer Group of {}", id, style(size).green())?;
+    }
+    Ok(())
+}
+
+/// Prints the deposits count of each mixer group, and the value they lock.
+async fn mixers_balance(context: &ExecutionContext) -> anyhow::Result<()> {
+    let mut term = context.term();
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(60);
+    let client = super::connect_to_mixer(context, &pb).await?;
+    pb.set_message("Getting Mixer Groups ..");
+    let mixer_groups = context.fetch_mixers().await?;
+    pb.set_message("Counting the deposits ..");
+    let counts = utils::fetch_concurrently(
+        context.rpc_concurrency(),
+        mixer_groups
+            .iter()
+            .map(|(id, _)| fetch_deposits_count(&client, *id)),
+    )
+    .await
+    .into_iter()
+    .collect::<anyhow::Result<Vec<_>>>()?;
+    pb.finish_and_clear();
+    let props = context.system_properties(Some(&client));
+    // only the native currency (#0) symbol is known.
+    let symbol = |currency_id: u64| match currency_id {
+        0 => props.token_symbol.clone(),
+        id => format!("(currency #{})", id),
+    };
+    let locked = |count: u32, size: u128| size.saturating_mul(count.into());
+    if context.output() == OutputFormat::Json {
+        let values: Vec<_> = mixer_groups
+            .iter()
+            .zip(&counts)
+            .map(|((id, info), count)| {
+                let size = info.fixed_deposit_size;
+                serde_json::json!({
+                    "id": id,
+                    "deposits": count,
+                    "deposit_size": size.to_string(),
+                    "locked": locked(*count, size).to_string(),
+                    "currency_id": info.currency_id,
+                    "token_symbol": symbol(info.currency_id),
+                })
+            })
+            .collect();
+        writeln!(term, "{}", serde_json::to_string_pretty(&values)?)?;
+        return Ok(());
+    }
+    if mixer_groups.is_empty() {
+        write!(term, "{} ", style("uh oh").red())?;
+        writeln!(term, "there is no mixers available on this chain.")?;
+        return Ok(());
+    }
+    for ((id, info), count) in mixer_groups.iter().zip(counts) {
+        let format = |v| {
+            utils::format_balance(
+                v,
+                props.token_decimals,
+                &symbol(info.currency_id),
+            )
+        };
+        let size = info.fixed_deposit_size;
+        writeln!(
+            term,
+            "- #{} Mixer Group of {}: {} deposits, {} locked",
+            id,
+            style(format(size)).green(),
+            count,
+            style(format(locked(count, size))).green()
+        )?;
+    }
+    Ok(())
+}
+
 /// Import a previously generated Note to your local secure store.
 ///
 /// The Note could be generated previously from the Webb UI.
@@ -78,21 +346,103 @@ pub struct ImportNote {
     #[structopt(short, long)]
     alias: Option<String>,
     /// Note string.
-    #[structopt(env = "WEBB_NOTE")]
+    #[structopt(env = "WEBB_NOTE", conflicts_with = "file")]
+    note: Option<String>,
+    /// Import all the notes in this file, one note per line.
+    ///
+    /// the notes get the `alias` with an incrementing suffix
+    /// (i.e `alias-1`, `alias-2`, ...).
+    #[structopt(short, long, value_name = "PATH", parse(from_os_str))]
+    file: Option<PathBuf>,
+}
+
+/// Decodes a Note and shows its fields, the note secrets are never
+/// printed.
+///
+/// this works offline, and nothing gets saved.
+#[derive(StructOpt)]
+pub struct NoteInfo {
+    /// Note string, read from the stdin if missing.
     note: Option<String>,
 }
 
+#[async_trait]
+impl super::CommandExec for NoteInfo {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let val = match self.note {
+            Some(val) => val,
+            None => {
+                let mut line = String::new();
+                std::io::stdin()
+                    .read_line(&mut line)
+                    .context("reading the note from the stdin")?;
+                line
+            },
+        };
+        let note = Note::from_str(val.trim())?;
+        let problem = note.validate().err().map(|e| e.to_string());
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "prefix": note.prefix,
+                "version": note.version.to_string(),
+                "token_symbol": note.token_symbol.to_string(),
+                "mixer_id": note.mixer_id,
+                "block_number": note.block_number,
+                "index": note.index,
+                "amount": note.amount,
+                "human_amount": note.human_amount(),
+                "valid": problem.is_none(),
+                "error": problem,
+            });
+            writeln!(term, "{}", serde_json::to_string_pretty(&value)?)?;
+            return Ok(());
+        }
+        let unknown = || style(String::from("unknown")).dim();
+        writeln!(term, "Prefix: {}", note.prefix)?;
+        writeln!(term, "Version: {}", note.version)?;
+        writeln!(term, "Token: {}", note.token_symbol)?;
+        writeln!(term, "Mixer Group: #{}", note.mixer_id)?;
+        match note.block_number {
+            Some(bn) => writeln!(term, "Block Number: #{}", bn)?,
+            None => writeln!(term, "Block Number: {}", unknown())?,
+        }
+        match note.index {
+            Some(index) => writeln!(term, "Leaf Index: {}", index)?,
+            None => writeln!(term, "Leaf Index: {}", unknown())?,
+        }
+        match note.human_amount() {
+            Some(amount) => {
+                writeln!(term, "Amount: {}", style(amount).green())?
+            },
+            None => writeln!(term, "Amount: {}", unknown())?,
+        }
+        match problem {
+            None => writeln!(term, "Valid: {}", style("yes").green())?,
+            Some(e) => writeln!(term, "Valid: {} ({})", style("no").red(), e)?,
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl super::CommandExec for ImportNote {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         let theme = dialoguer::theme::ColorfulTheme::default();
-        let alias = self.alias.unwrap_or_prompt("Note Alias", &theme)?;
+        let alias =
+            self.alias.unwrap_or_prompt(context, "Note Alias", &theme)?;
+        if let Some(path) = self.file {
+            return import_notes_file(context, alias, &path);
+        }
+        let alias =
+            free_alias(context, alias, &theme, |alias| vec![alias.to_owned()])?;
         let note = if let Some(val) = self.note {
             Note::from_str(&val)?
         } else {
             loop {
-                let v = Option::<Note>::None.unwrap_or_prompt("Note", &theme);
+                let v = Option::<Note>::None
+                    .unwrap_or_prompt(context, "Note", &theme);
                 match v {
                     Ok(note) => break note,
                     Err(e) => {
@@ -105,6 +455,7 @@ impl super::CommandExec for ImportNote {
         if !context.has_secret() {
             let password = Option::<SecretString>::None
                 .unwrap_or_prompt_password(
+                    context,
                     "Default Account Password",
                     &theme,
                 )?;
@@ -128,6 +479,142 @@ impl super::CommandExec for ImportNote {
     }
 }
 
+/// Makes sure none of the `aliases` of the notes about to be saved under
+/// `alias` is taken already, since the notes are looked up by their alias,
+/// asking for another one if it is.
+fn free_alias(
+    context: &ExecutionContext,
+    mut alias: String,
+    theme: &dyn dialoguer::theme::Theme,
+    aliases: impl Fn(&str) -> Vec<String>,
+) -> anyhow::Result<String> {
+    loop {
+        let taken = aliases(&alias)
+            .into_iter()
+            .find(|candidate| context.has_note_alias(candidate));
+        let taken = match taken {
+            Some(taken) => taken,
+            None => return Ok(alias),
+        };
+        if ext::ensure_can_prompt(context, "a free note alias").is_err() {
+            anyhow::bail!(
+                "there is already a note named {}, choose another alias",
+                taken
+            );
+        }
+        let mut term = context.term();
+        write!(term, "{} ", style("uh oh").red())?;
+        writeln!(term, "there is already a note named {}", taken)?;
+        alias = dialoguer::Input::with_theme(theme)
+            .with_prompt("Note Alias")
+            .default(context.free_note_alias(&alias))
+            .interact_on(&term)?;
+    }
+}
+
+/// The note selected with `--alias` or `--uuid`, asking which one to use if
+/// more than one note has the alias, `None` if neither is given.
+pub fn selected_note(
+    context: &ExecutionContext,
+    term: &console::Term,
+    theme: &dyn dialoguer::theme::Theme,
+    alias: Option<&str>,
+    uuid: Option<&str>,
+) -> anyhow::Result<Option<NoteRaw>> {
+    match context.find_note(alias, uuid) {
+        Err(e) => match e.downcast::<AmbiguousAlias>() {
+            Ok(ambiguous) => {
+                pick_ambiguous(context, term, theme, ambiguous).map(Some)
+            },
+            Err(e) => Err(e),
+        },
+        found => found,
+    }
+}
+
+/// Asks which of the notes sharing an alias to use, or fails telling to use
+/// `--uuid` when we can't prompt.
+fn pick_ambiguous(
+    context: &ExecutionContext,
+    term: &console::Term,
+    theme: &dyn dialoguer::theme::Theme,
+    ambiguous: AmbiguousAlias,
+) -> anyhow::Result<NoteRaw> {
+    if ext::ensure_can_prompt(context, "`--uuid`").is_err() {
+        return Err(ambiguous.into());
+    }
+    let items: Vec<_> = ambiguous
+        .notes
+        .iter()
+        .map(|n| format!("{} [{}]", n, n.uuid))
+        .collect();
+    let i = dialoguer::Select::with_theme(theme)
+        .with_prompt(format!(
+            "{} notes are named {}, select one",
+            ambiguous.notes.len(),
+            ambiguous.alias
+        ))
+        .items(&items)
+        .interact_on(term)?;
+    Ok(ambiguous.notes[i].clone())
+}
+
+/// Imports every note in the file at `path` (one per line), a line that
+/// fails to import is reported with its line number, and doesn't stop
+/// the others from being imported.
+fn import_notes_file(
+    context: &mut ExecutionContext,
+    alias: String,
+    path: &Path,
+) -> anyhow::Result<()> {
+    let mut term = context.term();
+    let theme = dialoguer::theme::ColorfulTheme::default();
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("reading notes from {}", path.display()))?;
+    let count = contents.lines().filter(|l| !l.trim().is_empty()).count();
+    let alias = free_alias(context, alias, &theme, |alias| {
+        (1..=count).map(|i| format!("{}-{}", alias, i)).collect()
+    })?;
+    if !context.has_secret() {
+        let password = Option::<SecretString>::None.unwrap_or_prompt_password(
+            context,
+            "Default Account Password",
+            &theme,
+        )?;
+        context.set_secret(password);
+    }
+    context
+        .signer()
+        .context("incorrect default account password!")?;
+    let mut imported = 0;
+    let mut failed = Vec::new();
+    let lines = contents.lines().enumerate().map(|(i, l)| (i + 1, l.trim()));
+    for (line, val) in lines.filter(|(_, l)| !l.is_empty()) {
+        let note_alias = format!("{}-{}", alias, imported + 1);
+        let result = Note::from_str(val)
+            .map_err(anyhow::Error::from)
+            .and_then(|note| context.import_note(note_alias.clone(), note));
+        match result {
+            Ok(mixer_group_id) => {
+                imported += 1;
+                writeln!(
+                    term,
+                    "Note Imported with alias {} for #{} Mixer Group",
+                    style(note_alias).green(),
+                    mixer_group_id
+                )?;
+            },
+            Err(e) => failed.push((line, e)),
+        }
+    }
+    for (line, e) in &failed {
+        writeln!(term, "{} line {}: {}", style("failed").red(), line, e)?;
+    }
+    writeln!(term)?;
+    writeln!(term, "{} imported, {} failed.", imported, failed.len())?;
+    Ok(())
+}
+
 /// Generate a new Note and save it for later.
 ///
 /// The Generated Note will be saved securely in your local store
@@ -145,55 +632,113 @@ pub struct GenerateNote {
     /// leave empty to prompt with the available mixer groups.
     #[structopt(short, long)]
     group: Option<u32>,
+    /// Generate the Note without connecting to a node, using the given
+    /// `--group`, `--size`, `--decimals` and `--chain`.
+    ///
+    /// useful on an air-gapped machine, the Note is the same as one
+    /// generated online for the same mixer group.
+    #[structopt(long, requires_all = &["group", "size", "decimals", "chain"])]
+    offline: bool,
+    /// The mixer group deposit size in base units (offline only).
+    #[structopt(long, requires = "offline")]
+    size: Option<u128>,
+    /// The token decimals of the chain (offline only).
+    #[structopt(long, requires = "offline")]
+    decimals: Option<u8>,
+    /// The name of the chain the Note is for (offline only).
+    #[structopt(long, requires = "offline")]
+    chain: Option<String>,
+    /// The mixer group currency id (offline only), defaults to 0.
+    #[structopt(long, requires = "offline")]
+    currency_id: Option<u64>,
+    /// The mixer group token symbol (offline only), defaults to EDG.
+    #[structopt(long, requires = "offline")]
+    token_symbol: Option<TokenSymbol>,
+    /// Generate this many Notes for the same mixer group, saved with the
+    /// aliases `<alias>-1`, `<alias>-2` and so on.
+    #[structopt(long, value_name = "N", default_value = "1")]
+    count: usize,
+    /// Also append the generated Notes to this file, one per line.
+    ///
+    /// the file holds the Notes secrets in plaintext, so this requires
+    /// `--unsafe`, and the file is only readable by you.
+    #[structopt(long, value_name = "PATH", parse(from_os_str))]
+    output_file: Option<PathBuf>,
+}
+
+impl GenerateNote {
+    /// The mixer group and its metadata, as given on the command line.
+    fn offline_metadata(&self) -> anyhow::Result<(u32, NoteMetadata)> {
+        let (group, amount, decimals, chain) =
+            match (self.group, self.size, self.decimals, &self.chain) {
+                (Some(g), Some(s), Some(d), Some(c)) => (g, s, d, c.clone()),
+                _ => anyhow::bail!(
+                    "--offline needs --group, --size, --decimals and --chain"
+                ),
+            };
+        anyhow::ensure!(amount > 0, "the mixer deposit size can't be zero");
+        // a u128 has at most 39 digits.
+        anyhow::ensure!(
+            decimals <= 38,
+            "the token decimals must be at most 38, got {}",
+            decimals
+        );
+        anyhow::ensure!(!chain.trim().is_empty(), "the chain name is empty");
+        let metadata = NoteMetadata {
+            amount,
+            decimals,
+            currency_id: self.currency_id.unwrap_or_default(),
+            chain,
+        };
+        Ok((group, metadata))
+    }
 }
 
 #[async_trait]
 impl super::CommandExec for GenerateNote {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        type MixerTreeIds = MixerTreeIdsStore<WebbRuntime>;
-
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         let theme = dialoguer::theme::ColorfulTheme::default();
-        let alias = self.alias.unwrap_or_prompt("Note Alias", &theme)?;
-        let pb = ProgressBar::new_spinner();
-        pb.enable_steady_tick(60);
+        anyhow::ensure!(self.count > 0, "--count must be at least 1");
+        if let Some(ref path) = self.output_file {
+            if !context.is_unsafe() {
+                anyhow::bail!(
+                    "the output file holds the Notes secrets, \
+                     use `--unsafe` to save them to it"
+                );
+            }
+            context.warn(format!(
+                "{} will contain the Notes secrets in plaintext",
+                path.display()
+            ));
+        }
+        let offline = if self.offline {
+            Some(self.offline_metadata()?)
+        } else {
+            None
+        };
+        let alias =
+            self.alias.unwrap_or_prompt(context, "Note Alias", &theme)?;
         let pb_style = ProgressStyle::default_spinner()
             .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
             .template("{prefix:.bold.dim} {spinner} {wide_msg}");
-        pb.set_style(pb_style.clone());
-        pb.set_prefix("[1/3]");
-        pb.set_message("Connecting ..");
-        let client = context.client().await?;
-        pb.set_prefix("[2/3]");
-        pb.set_message("Getting Mixer Groups ..");
-        let mixer_group_ids = client
-            .fetch_or_default(&MixerTreeIds::default(), None)
-            .await?;
-        pb.finish_and_clear();
-        let mixer_group_id = if let Some(val) = self.group {
-            if mixer_group_ids.contains(&val) {
-                val
-            } else {
-                writeln!(term, "Available groups: {:?}", mixer_group_ids)?;
-                anyhow::bail!("Invalid Mixer group!");
-            }
-        } else {
-            let f = |i| format!("Group #{} with 1,000{} EDG", i, "0".repeat(i));
-            let items: Vec<_> = mixer_group_ids
-                .iter()
-                .cloned()
-                .map(|v| v as usize)
-                .map(f)
-                .collect();
-            let i = dialoguer::Select::with_theme(&theme)
-                .with_prompt("Select Mixer Group")
-                .items(&items)
-                .interact_on(&term)?;
-            mixer_group_ids[i]
+        let (mixer_group_id, token_symbol, metadata) = match offline {
+            Some((id, metadata)) => {
+                (id, self.token_symbol.unwrap_or(TokenSymbol::Edg), metadata)
+            },
+            None => {
+                match select_mixer_group(context, self.group, &pb_style).await?
+                {
+                    Some((id, metadata)) => (id, TokenSymbol::Edg, metadata),
+                    None => return Ok(()),
+                }
+            },
         };
+        context.set_last_mixer(Some(mixer_group_id))?;
         if !context.has_secret() {
             let password = Option::<SecretString>::None
                 .unwrap_or_prompt_password(
+                    context,
                     "Default Account Password",
                     &theme,
                 )?;
@@ -202,24 +747,66 @@ impl super::CommandExec for GenerateNote {
         context
             .signer()
             .context("incorrect default account password!")?;
+        let count = self.count;
+        let note_aliases = |alias: &str| -> Vec<String> {
+            if count == 1 {
+                vec![alias.to_owned()]
+            } else {
+                (1..=count).map(|i| format!("{}-{}", alias, i)).collect()
+            }
+        };
+        let alias = free_alias(context, alias, &theme, note_aliases)?;
+        let aliases = note_aliases(&alias);
         let pb = ProgressBar::new_spinner();
         pb.enable_steady_tick(60);
         pb.set_style(pb_style);
         pb.set_prefix("[3/3]");
-        pb.set_message("Generating Note..");
-        context.generate_note(
-            alias.clone(),
-            mixer_group_id,
-            TokenSymbol::Edg,
-        )?;
+        let mut generated = Vec::with_capacity(aliases.len());
+        for (i, alias) in aliases.iter().enumerate() {
+            if aliases.len() == 1 {
+                pb.set_message("Generating Note..");
+            } else {
+                pb.set_message(&format!(
+                    "Generating Note {}/{}..",
+                    i + 1,
+                    aliases.len()
+                ));
+            }
+            let note = context.generate_note(
+                alias.clone(),
+                mixer_group_id,
+                token_symbol,
+                metadata.clone(),
+            )?;
+            generated.push(note.to_string());
+        }
         pb.finish_with_message("Done!");
         pb.finish_and_clear();
-        writeln!(
-            term,
-            "Note Generated with alias {} for #{} Mixer Group",
-            style(alias).green(),
-            mixer_group_id
-        )?;
+        if let Some(ref path) = self.output_file {
+            let mut lines = generated.join("\n");
+            lines.push('\n');
+            append_private(path, lines.as_bytes()).with_context(|| {
+                format!("failed to write {}", path.display())
+            })?;
+        }
+        if let [alias] = aliases.as_slice() {
+            writeln!(
+                term,
+                "Note Generated with alias {} for #{} Mixer Group",
+                style(alias).green(),
+                mixer_group_id
+            )?;
+        } else {
+            writeln!(
+                term,
+                "{} Notes Generated for #{} Mixer Group:",
+                aliases.len(),
+                mixer_group_id
+            )?;
+            for alias in &aliases {
+                writeln!(term, "  - {}", style(alias).green())?;
+            }
+        }
         writeln!(term)?;
         writeln!(term, "Next, Do a dopist using this note.")?;
         writeln!(term, "    $ webb mixer deposit")?;
@@ -227,20 +814,484 @@ impl super::CommandExec for GenerateNote {
     }
 }
 
+/// Fetches the mixer groups from the chain, and picks the `group` or
+/// prompts for one.
+///
+/// returns `None` if there is no mixer groups on the chain.
+async fn select_mixer_group(
+    context: &mut ExecutionContext,
+    group: Option<u32>,
+    pb_style: &ProgressStyle,
+) -> anyhow::Result<Option<(u32, NoteMetadata)>> {
+    let mut term = context.term();
+    let theme = dialoguer::theme::ColorfulTheme::default();
+    let pb = ProgressBar::new_spinner();
+    pb.enable_steady_tick(60);
+    pb.set_style(pb_style.clone());
+    pb.set_prefix("[1/3]");
+    let client = super::connect_to_mixer(context, &pb).await?;
+    pb.set_prefix("[2/3]");
+    pb.set_message("Getting Mixer Groups ..");
+    let mixer_groups = context.fetch_mixers().await?;
+    let props = context.system_properties(Some(&client));
+    let rpc_client = context.rpc_client().await?;
+    let chain: String =
+        rpc_client.request("system_chain", Params::None).await?;
+    pb.finish_and_clear();
+    if mixer_groups.is_empty() {
+        write!(term, "{} ", style("uh oh").red())?;
+        writeln!(term, "there is no mixers available on this chain.")?;
+        writeln!(term, "make sure you are connected to the right node:")?;
+        writeln!(term, "    $ webb --node-url <NODE_URL> mixer generate-note")?;
+        return Ok(None);
+    }
+    let token_symbol = TokenSymbol::Edg;
+    let (mixer_group_id, mixer_info) = if let Some(val) = group {
+        if let Some(group) = mixer_groups.iter().find(|(id, _)| *id == val) {
+            group
+        } else {
+            let ids: Vec<_> = mixer_groups.iter().map(|(id, _)| id).collect();
+            writeln!(term, "Available groups: {:?}", ids)?;
+            anyhow::bail!("Invalid Mixer group!");
+        }
+    } else {
+        let items: Vec<_> = mixer_groups
+            .iter()
+            .map(|(id, info)| {
+                let size = utils::format_balance(
+                    info.fixed_deposit_size,
+                    props.token_decimals,
+                    &token_symbol.to_string(),
+                );
+                format!("Group #{} with {}", id, size)
+            })
+            .collect();
+        // pre-select the last used mixer group, if it still exists.
+        let last_mixer = context.last_mixer()?;
+        let last = last_mixer.and_then(|last| {
+            mixer_groups.iter().position(|(id, _)| *id == last)
+        });
+        if let (Some(id), None) = (last_mixer, last) {
+            context.warn(format!(
+                "the last used #{} Mixer Group no longer exists",
+                id
+            ));
+            context.set_last_mixer(None)?;
+        }
+        ext::ensure_can_prompt(context, "the mixer group")?;
+        let i = dialoguer::Select::with_theme(&theme)
+            .with_prompt("Select Mixer Group")
+            .items(&items)
+            .default(last.unwrap_or_default())
+            .interact_on(&term)?;
+        &mixer_groups[i]
+    };
+    let metadata = NoteMetadata {
+        amount: mixer_info.fixed_deposit_size,
+        decimals: props.token_decimals,
+        currency_id: mixer_info.currency_id,
+        chain,
+    };
+    Ok(Some((*mixer_group_id, metadata)))
+}
+
+/// Changes the alias of a saved Note.
+///
+/// only the Note metadata changes, so this doesn't need the password.
+#[derive(StructOpt)]
+pub struct RenameNote {
+    /// The current Note alias.
+    #[structopt(short, long)]
+    alias: Option<String>,
+    /// The Note uuid, useful if more than one Note has the same alias.
+    ///
+    /// it wins over the `--alias`, if both are given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// The new Note alias.
+    #[structopt(short, long)]
+    new_alias: Option<String>,
+}
+
+#[async_trait]
+impl super::CommandExec for RenameNote {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        let notes = context.notes().to_owned();
+        if notes.is_empty() {
+            writeln!(term)?;
+            writeln!(term, "there is no Notes saved")?;
+            return Ok(());
+        }
+        let selected = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?;
+        let note = match selected {
+            Some(note) => note,
+            None => {
+                let items: Vec<_> =
+                    notes.iter().map(|n| format!("{}", n)).collect();
+                ext::ensure_can_prompt(context, "the note alias")?;
+                let i = dialoguer::Select::with_theme(&theme)
+                    .with_prompt("Select the note to rename")
+                    .items(&items)
+                    .interact_on(&term)?;
+                notes[i].clone()
+            },
+        };
+        let new_alias =
+            self.new_alias
+                .unwrap_or_prompt(context, "New Alias", &theme)?;
+        context.rename_note(&note.uuid, new_alias.clone())?;
+        writeln!(
+            term,
+            "{} Note {} renamed to {}",
+            Emoji("✅", "√"),
+            note.alias,
+            style(new_alias).blue()
+        )?;
+        Ok(())
+    }
+}
+
+/// Re-encrypts a Note secret with the current password.
+///
+/// a Note saved while using another password (i.e restored from an older
+/// backup) can't be read with the current password, given the password
+/// it was saved with, this re-encrypts just that Note.
+#[derive(StructOpt)]
+pub struct FixEncryption {
+    /// The Note alias to fix.
+    #[structopt(required_unless = "uuid")]
+    alias: Option<String>,
+    /// The Note uuid, it wins over the alias if both are given.
+    #[structopt(long)]
+    uuid: Option<String>,
+}
+
+#[async_trait]
+impl super::CommandExec for FixEncryption {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        let note = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?
+        .context("note not found")?;
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(
+                    context,
+                    "Current Password",
+                    &theme,
+                )?;
+            context.set_secret(password);
+        }
+        if context.decrypt_note(note.uuid.clone()).is_ok() {
+            writeln!(
+                term,
+                "{} Note {} is already readable with the current password.",
+                Emoji("✅", "√"),
+                note.alias
+            )?;
+            return Ok(());
+        }
+        let old = Option::<SecretString>::None.unwrap_or_prompt_password(
+            context,
+            "The Password this Note was saved with",
+            &theme,
+        )?;
+        context.fix_note_encryption(&note.uuid, old)?;
+        // make sure we can read it back, before telling the user so.
+        context
+            .decrypt_note(note.uuid.clone())
+            .context("the re-encrypted note is unreadable")?;
+        writeln!(
+            term,
+            "{} Note {} is now encrypted with the current password.",
+            Emoji("🔐", "√"),
+            note.alias
+        )?;
+        Ok(())
+    }
+}
+
 /// Forget/Remove the Note from your local store.
 /// This can be safely done on already used Notes.
 ///
 /// The Notes that are ready to be removed will be marked with `*`.
+///
+/// Forgetting an unused Note requires `--unsafe`, since you will lose
+/// the assets deposited using it.
 #[derive(StructOpt)]
-pub struct ForgetNote {}
+pub struct ForgetNote {
+    /// The Note alias to forget.
+    #[structopt(short, long)]
+    alias: Option<String>,
+    /// The Note uuid to forget, it wins over the `--alias` if both are
+    /// given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// Don't ask for confirmation.
+    #[structopt(short, long)]
+    yes: bool,
+}
 
 #[async_trait]
 impl super::CommandExec for ForgetNote {
-    async fn exec(self, _context: &mut ExecutionContext) -> anyhow::Result<()> {
-        todo!("Forget Note")
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        let notes = context.notes().to_owned();
+        if notes.is_empty() {
+            writeln!(term)?;
+            writeln!(term, "there is no Notes saved")?;
+            return Ok(());
+        }
+        let selected = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?;
+        let note = match selected {
+            Some(note) => note,
+            None => {
+                let items: Vec<_> =
+                    notes.iter().map(|n| format!("{}", n)).collect();
+                ext::ensure_can_prompt(context, "the note alias")?;
+                let i = dialoguer::Select::with_theme(&theme)
+                    .with_prompt("Select the note to forget")
+                    .items(&items)
+                    .interact_on(&term)?;
+                notes[i].clone()
+            },
+        };
+        if !note.used && !context.is_unsafe() {
+            write!(term, "{} ", style("uh oh").red())?;
+            writeln!(term, "the note {} is not used yet!", note.alias)?;
+            writeln!(term, "forgetting it will lose any deposited assets.")?;
+            writeln!(term, "if you are sure, try again with `--unsafe`.")?;
+            return Ok(());
+        }
+        let confirmed = self.yes || {
+            ext::ensure_can_prompt(context, "`--yes`")?;
+            dialoguer::Confirm::with_theme(&theme)
+                .with_prompt(format!("Forget the note {}?", note.alias))
+                .default(false)
+                .interact_on(&term)?
+        };
+        if !confirmed {
+            return Ok(());
+        }
+        context.forget_note(note.uuid)?;
+        writeln!(
+            term,
+            "{} Note {} forgotten, {} notes remaining.",
+            Emoji("🗑️ ", "√"),
+            style(note.alias).blue(),
+            context.notes().len()
+        )?;
+        Ok(())
+    }
+}
+
+/// Export a saved Note, so it could be used in the Webb UI
+/// or shared with a relayer.
+///
+/// The Note holds the secrets of your deposit, anyone who has it
+/// can withdraw your assets! so it requires `--unsafe` or a confirmation,
+/// and it is never copied to the `--transcript` file.
+#[derive(StructOpt)]
+pub struct ExportNote {
+    /// The Note alias to export.
+    #[structopt(short, long)]
+    alias: Option<String>,
+    /// The Note uuid to export, it wins over the `--alias` if both are
+    /// given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// Render the Note as a QR code.
+    #[structopt(long)]
+    qr: bool,
+    /// Copy the Note to the clipboard instead of printing it.
+    ///
+    /// this uses the terminal clipboard escape sequence (OSC 52), which is
+    /// supported by most terminals, even over ssh.
+    #[structopt(long)]
+    clipboard: bool,
+}
+
+#[async_trait]
+impl super::CommandExec for ExportNote {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        // the Note is only ever written to the terminal directly, and not
+        // through our `Term` writer, so it never ends up in the transcript.
+        let term = console::Term::stdout();
+        let notes = context.notes().to_owned();
+        if notes.is_empty() {
+            term.write_line("there is no Notes saved")?;
+            return Ok(());
+        }
+        let selected = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?;
+        let note = if let Some(note) = selected {
+            note
+        } else {
+            let items: Vec<_> =
+                notes.iter().map(|n| format!("{}", n)).collect();
+            ext::ensure_can_prompt(context, "the note alias")?;
+            let i = dialoguer::Select::with_theme(&theme)
+                .with_prompt("Select the note to export")
+                .items(&items)
+                .interact_on(&term)?;
+            notes[i].clone()
+        };
+        let confirmed = context.is_unsafe() || {
+            ext::ensure_can_prompt(context, "`--unsafe`")?;
+            dialoguer::Confirm::with_theme(&theme)
+                .with_prompt(
+                    "The Note lets anyone withdraw your deposit, export it?",
+                )
+                .default(false)
+                .interact_on(&term)?
+        };
+        if !confirmed {
+            return Ok(());
+        }
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(
+                    context,
+                    "Default Account Password",
+                    &theme,
+                )?;
+            context.set_secret(password);
+        }
+        let secret_note = context.decrypt_note(note.uuid)?.to_string();
+        if self.qr {
+            let code = paper::note_qr_code(&secret_note)?;
+            term.write_line(&paper::render_terminal(&code))?;
+        }
+        if self.clipboard {
+            let osc52 =
+                format!("\x1b]52;c;{}\x07", base64::encode(&secret_note));
+            (&term).write_all(osc52.as_bytes())?;
+            term.write_line(&format!(
+                "{} Note {} copied to the clipboard.",
+                Emoji("📋", "√"),
+                note.alias
+            ))?;
+        } else if !self.qr {
+            term.write_line(&secret_note)?;
+        }
+        Ok(())
     }
 }
 
+/// Save a printable paper backup of a Note.
+///
+/// The backup is a self-contained HTML page, with the Note QR code, the
+/// Note itself, its parameters and how to recover it. It works offline,
+/// so print it from a trusted machine and delete the file afterwards.
+///
+/// The page holds the secrets of your deposit, so this requires
+/// `--unsafe`.
+#[derive(StructOpt)]
+pub struct PaperNote {
+    /// The Note alias to back up.
+    #[structopt(required_unless = "uuid")]
+    alias: Option<String>,
+    /// The Note uuid, it wins over the alias if both are given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// Where to save the HTML page.
+    #[structopt(short, long, value_name = "FILE", parse(from_os_str))]
+    out: PathBuf,
+}
+
+#[async_trait]
+impl super::CommandExec for PaperNote {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        if !context.is_unsafe() {
+            anyhow::bail!(
+                "the paper backup holds the Note secrets, \
+                 use `--unsafe` to save it"
+            );
+        }
+        let mut term = context.term();
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        let note = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?
+        .context("note not found")?;
+        if !context.has_secret() {
+            let password = Option::<SecretString>::None
+                .unwrap_or_prompt_password(
+                    context,
+                    "Default Account Password",
+                    &theme,
+                )?;
+            context.set_secret(password);
+        }
+        let secret_note = context.decrypt_note(note.uuid.clone())?.to_string();
+        let html = paper::render_html(&note, &secret_note)?;
+        write_private(&self.out, html.as_bytes()).with_context(|| {
+            format!("failed to write {}", self.out.display())
+        })?;
+        writeln!(
+            term,
+            "{} Paper backup of {} saved to {}",
+            Emoji("📄", "√"),
+            note.alias,
+            self.out.display()
+        )?;
+        Ok(())
+    }
+}
+
+/// Writes `contents` to the file at `path`, readable only by the owner.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    write_private_with(options, path, contents)
+}
+
+/// Appends `contents` to the file at `path`, readable only by the owner.
+fn append_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.append(true).create(true);
+    write_private_with(options, path, contents)
+}
+
+fn write_private_with(
+    options: std::fs::OpenOptions,
+    path: &Path,
+    contents: &[u8],
+) -> std::io::Result<()> {
+    utils::open_private(options, path)?.write_all(contents)
+}
+
 /// Deposit an asset to the Mixer.
 ///
 /// After generating a Note, you can do a deposit to the mixer
@@ -250,12 +1301,41 @@ pub struct DepositAsset {
     /// The Note alias that will be used to do the deposit.
     #[structopt(short, long)]
     alias: Option<String>,
+    /// The uuid of the Note, instead of its alias which other notes could
+    /// share, it wins over the `--alias` if both are given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// Consider the deposit done once its block has this many finalized
+    /// confirmations, instead of waiting for the `Finalized` status.
+    ///
+    /// useful for nodes that don't reliably emit the `Finalized` status.
+    #[structopt(long, value_name = "N")]
+    confirmations: Option<u32>,
+    /// Deposit even if the Note was generated for another chain.
+    #[structopt(long)]
+    force: bool,
+    /// Do all the checks and estimate the fee, but don't submit the
+    /// deposit, the Note stays unused.
+    #[structopt(long)]
+    dry_run: bool,
+    /// Deposit from this saved account (its alias or address) instead of
+    /// the default one.
+    #[structopt(long, value_name = "ALIAS_OR_ADDRESS")]
+    from: Option<String>,
+    /// Ask for a confirmation before depositing to a Mixer Group with
+    /// less deposits than this, since a deposit among only a few others
+    /// is easy to link to its withdraw.
+    #[structopt(long, value_name = "N", default_value = "10")]
+    min_anonymity_set: u32,
+    /// Don't ask for confirmation.
+    #[structopt(short, long)]
+    yes: bool,
 }
 
 #[async_trait]
 impl super::CommandExec for DepositAsset {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         let theme = dialoguer::theme::ColorfulTheme::default();
         let notes: Vec<_> =
             context.notes().iter().filter(|n| !n.used).collect();
@@ -267,41 +1347,70 @@ impl super::CommandExec for DepositAsset {
             writeln!(term, "$ webb mixer help")?;
             return Ok(());
         }
-        let note = if let Some(val) = self.alias {
-            notes
-                .into_iter()
-                .cloned()
-                .find(|n| n.alias == val)
-                .context("note not found")
+        let selected = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?;
+        let note = if let Some(note) = selected {
+            anyhow::ensure!(
+                !note.used,
+                "the note {} is already used, generate a new one",
+                note.alias
+            );
+            note
         } else {
             let items: Vec<_> =
                 notes.iter().map(|n| format!("{}", n)).collect();
             let notes = notes.to_owned();
+            // pre-select the last used note, if it is still unused.
+            let last_note = context.last_note()?;
+            let last = last_note
+                .as_ref()
+                .and_then(|last| notes.iter().position(|n| &n.uuid == last));
+            if last_note.is_some() && last.is_none() {
+                context.set_last_note(None)?;
+            }
+            ext::ensure_can_prompt(context, "the note alias")?;
             let i = dialoguer::Select::with_theme(&theme)
                 .with_prompt("Select one of these notes")
                 .items(&items)
+                .default(last.unwrap_or_default())
                 .interact_on(&term)?;
-            Ok(notes[i].clone())
-        }?;
+            notes[i].clone()
+        };
+        context.set_last_note(Some(&note.uuid))?;
 
         if !context.has_secret() {
             let password = Option::<SecretString>::None
                 .unwrap_or_prompt_password(
+                    context,
                     "Default Account Password",
                     &theme,
                 )?;
             context.set_secret(password);
         }
-        let signer = context
-            .signer()
-            .context("incorrect default account password!")?;
+        let signer = match self.from {
+            Some(ref account) => context.signer_for(account)?,
+            None => context
+                .signer()
+                .context("incorrect default account password!")?,
+        };
         let secret_note = context.decrypt_note(note.uuid.clone())?;
+        mixer::ensure_mixer_note(&secret_note).with_context(|| {
+            format!(
+                "{} is a {} note, only mixer notes can be deposited",
+                note.alias, secret_note.prefix
+            )
+        })?;
         let pb = ProgressBar::new_spinner();
         let pb_style = ProgressStyle::default_spinner()
             .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
             .template("{prefix:.bold.dim} {spinner} {wide_msg}");
         pb.enable_steady_tick(60);
-        pb.set_style(pb_style);
+        pb.set_style(pb_style.clone());
         pb.set_prefix("[1/4]");
         pb.set_message("Creating Mixer..");
         let mut mixer = Mixer::new(secret_note.mixer_id);
@@ -309,39 +1418,231 @@ impl super::CommandExec for DepositAsset {
         pb.set_message("Adding Note to the Mixer ...");
         let leaf = mixer.save_note(secret_note);
         pb.set_prefix("[3/4]");
-        pb.set_message("Connecting to the network...");
-        let client = context.client().await?;
+        let client = super::connect_to_mixer(context, &pb).await?;
+        let info = context.fetch_mixer(note.mixer_id).await?;
+        let props = context.system_properties(Some(&client));
+        if note.amount().is_some() {
+            let amount = note.amount_with_decimals(props.token_decimals.into());
+            if amount != Some(info.fixed_deposit_size) {
+                anyhow::bail!(
+                    "note amount {} does not match #{} Mixer Group size {}",
+                    note.human_amount().unwrap_or_default(),
+                    note.mixer_id,
+                    utils::format_balance(
+                        info.fixed_deposit_size,
+                        props.token_decimals,
+                        &note.token_symbol
+                    ),
+                );
+            }
+        } else {
+            context.warn(format!(
+                "the note {} amount is unknown, it could not be checked \
+                 against the #{} Mixer Group size",
+                note.alias, note.mixer_id
+            ));
+        }
+        if let Some(currency_id) = note.currency_id {
+            if info.currency_id != currency_id {
+                anyhow::bail!(
+                    "note currency #{} does not match #{} Mixer Group currency #{}",
+                    currency_id,
+                    note.mixer_id,
+                    info.currency_id,
+                );
+            }
+        }
+        // the same mixer group (and size) could exist on another chain, so
+        // make sure the note was generated for this one.
+        let rpc_client = context.rpc_client().await?;
+        if !note.chain.is_empty() {
+            let chain: String =
+                rpc_client.request("system_chain", Params::None).await?;
+            if chain != note.chain && self.force {
+                context.warn(format!(
+                    "the note {} was generated for the {} chain, but it is \
+                     deposited on the {} chain",
+                    note.alias, note.chain, chain
+                ));
+            } else if chain != note.chain {
+                anyhow::bail!(
+                    "the note {} was generated for the {} chain, but the \
+                     node is on the {} chain; use `--force` to deposit anyway",
+                    note.alias,
+                    note.chain,
+                    chain
+                );
+            }
+        }
+        pb.set_message("Counting the deposits..");
+        let deposits = fetch_deposits_count(&client, note.mixer_id).await?;
+        let pb = if deposits < self.min_anonymity_set {
+            let warning = format!(
+                "the #{} Mixer Group has only {} deposits, below the minimum \
+                 anonymity set of {}, so your deposit could be linked to its \
+                 withdraw",
+                note.mixer_id, deposits, self.min_anonymity_set
+            );
+            if self.yes || self.dry_run {
+                context.warn(warning);
+                pb
+            } else {
+                pb.finish_and_clear();
+                if ext::ensure_can_prompt(context, "`--yes`").is_err() {
+                    anyhow::bail!("{}; use `--yes` to deposit anyway", warning);
+                }
+                write!(term, "{} ", style("uh oh").red())?;
+                writeln!(term, "{}", warning)?;
+                let confirmed = dialoguer::Confirm::with_theme(&theme)
+                    .with_prompt("Deposit anyway?")
+                    .default(false)
+                    .interact_on(&term)?;
+                if !confirmed {
+                    return Ok(());
+                }
+                let pb = ProgressBar::new_spinner();
+                pb.enable_steady_tick(60);
+                pb.set_style(pb_style);
+                pb.set_prefix("[3/4]");
+                pb
+            }
+        } else {
+            pb
+        };
+        // checked before submitting, since a deposit we can't afford
+        // only fails on-chain, with a confusing error.
+        pb.set_message("Estimating the fee..");
+        let call = DepositCall::new(note.mixer_id, vec![leaf]);
+        let fee =
+            transaction::estimate_fee(&client, &rpc_client, call, &signer)
+                .await?;
+        let balance =
+            client.account(&signer.account_id(), None).await?.data.free;
+        let fmt = |v: u128| {
+            utils::format_balance(v, props.token_decimals, &props.token_symbol)
+        };
+        // only the native currency (#0) balance is known, so the deposits
+        // to the Mixer Groups of other currencies only need the fee from
+        // it, their size is checked on-chain.
+        let native = info.currency_id == 0;
+        let size = info.fixed_deposit_size;
+        let fmt_size = |v: u128| match info.currency_id {
+            0 => fmt(v),
+            id => utils::format_balance(
+                v,
+                props.token_decimals,
+                &format!("(currency #{})", id),
+            ),
+        };
+        let total = if native {
+            size.saturating_add(fee)
+        } else {
+            fee
+        };
+        let covered = balance >= total;
+        if !covered && !self.dry_run {
+            pb.finish_and_clear();
+            if !native {
+                anyhow::bail!(
+                    "insufficient balance for this deposit, it needs {} fee \
+                     but your free balance is {}",
+                    fmt(fee),
+                    fmt(balance)
+                );
+            }
+            anyhow::bail!(
+                "insufficient balance for this deposit, it needs {} ({} + \
+                 {} fee) but your free balance is {}",
+                fmt(total),
+                fmt(size),
+                fmt(fee),
+                fmt(balance)
+            );
+        }
+        if self.dry_run {
+            pb.finish_and_clear();
+            if context.output() == OutputFormat::Json {
+                let value = serde_json::json!({
+                    "note": note.alias,
+                    "mixer_id": note.mixer_id,
+                    "size": size.to_string(),
+                    "currency_id": info.currency_id,
+                    "fee": fee.to_string(),
+                    "total": total.to_string(),
+                    "free_balance": balance.to_string(),
+                    "covered": covered,
+                });
+                writeln!(term, "{}", value)?;
+                return Ok(());
+            }
+            writeln!(term, "Deposit Size: {}", style(fmt_size(size)).green())?;
+            writeln!(term, "Estimated Fee: {}", style(fmt(fee)).green())?;
+            if native {
+                writeln!(
+                    term,
+                    "Total Cost: {}",
+                    style(fmt(total)).green().bold()
+                )?;
+            } else {
+                writeln!(
+                    term,
+                    "Total Cost: {} + {}",
+                    style(fmt_size(size)).green().bold(),
+                    style(fmt(fee)).green().bold()
+                )?;
+            }
+            writeln!(term, "Your Current Free Balance: {}", fmt(balance))?;
+            if !covered {
+                write!(term, "{} ", style("uh oh").red())?;
+                writeln!(term, "insufficient balance for this deposit")?;
+            }
+            writeln!(term)?;
+            writeln!(term, "nothing was submitted, to do the deposit:")?;
+            writeln!(term, "    $ webb mixer deposit -a {}", note.alias)?;
+            return Ok(());
+        }
         pb.set_prefix("[4/4]");
         pb.set_message("Doing the deposit...");
-        let xt = client
-            .deposit_and_watch(&signer, note.mixer_id, vec![leaf])
+        let outcome = context
+            .deposit(note.mixer_id, leaf, &signer, self.confirmations)
             .await?;
-        context.mark_note_as_used(note.uuid)?;
+        context.mark_note_as_used(note.uuid.clone())?;
         pb.finish_and_clear();
-        let xt_block = xt.block;
-        let maybe_block = client.block(Some(xt_block)).await?;
-        let signed_block =
-            maybe_block.context("reading block from network!")?;
-        let number = signed_block.block.header.number;
-        let hash = signed_block.block.header.hash();
         let account_id = signer.account_id();
         let account = client.account(&account_id, None).await?;
-        let props = SystemProperties::from(client.properties());
-        let balance =
-            account.data.free / 10u128.pow(props.token_decimals as u32);
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "note": note.alias,
+                "mixer_id": note.mixer_id,
+                "xt_hash": outcome.xt_hash,
+                "block_hash": outcome.block_hash,
+                "block_number": outcome.block_number,
+                "free_balance": account.data.free.to_string(),
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        let props = context.system_properties(Some(&client));
+        let balance = utils::format_balance(
+            account.data.free,
+            props.token_decimals,
+            &props.token_symbol,
+        );
         writeln!(term, "{} Note Deposited Successfully!", Emoji("🎉", "※"))?;
+        if let Some(amount) = note.human_amount() {
+            writeln!(term, "Deposited: {}", style(amount).green())?;
+        }
         writeln!(
             term,
             "Block Number: #{} {}",
-            style(number).blue(),
-            style(hash).dim().green()
+            style(outcome.block_number).blue(),
+            style(outcome.block_hash).dim().green()
         )?;
         writeln!(term)?;
         writeln!(
             term,
-            "Your Current Free Balance: {} {}",
+            "Your Current Free Balance: {}",
             style(balance).green().bold(),
-            props.token_symbol,
         )?;
         writeln!(term)?;
         writeln!(term, "Next! to do a withdraw:")?;
@@ -351,6 +1652,135 @@ impl super::CommandExec for DepositAsset {
     }
 }
 
+/// Estimate the total cost of a deposit, without submitting anything.
+///
+/// The deposit size is read from the mixer group, and the fee is estimated
+/// by the connected node. Passing both `--size` and `--fee` skips the node
+/// entirely, which together with `--chain-properties-file` lets you run it
+/// fully offline (without checking your balance).
+#[derive(StructOpt)]
+pub struct SimulateDeposit {
+    /// the mixer group to simulate the deposit for.
+    #[structopt(short, long, conflicts_with = "size")]
+    group: Option<u32>,
+    /// the deposit size in base units, instead of reading it from the
+    /// mixer group.
+    #[structopt(long)]
+    size: Option<u128>,
+    /// the transaction fee in base units, instead of estimating it
+    /// using the connected node.
+    #[structopt(long)]
+    fee: Option<u128>,
+}
+
+#[async_trait]
+impl super::CommandExec for SimulateDeposit {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        // without a default account we can still estimate the cost.
+        let address = context.default_account().ok().map(|a| a.address.clone());
+        let (size, fee, balance, props) = match (self.size, self.fee) {
+            (Some(size), Some(fee)) => {
+                (size, fee, None, context.system_properties(None))
+            },
+            (size, fee) => {
+                let pb = ProgressBar::new_spinner();
+                pb.enable_steady_tick(60);
+                let client = super::connect_to_mixer(context, &pb).await?;
+                let group = match (size, self.group) {
+                    (_, Some(group)) => group,
+                    // only used to estimate the fee, which is the same
+                    // for any group.
+                    (Some(_), None) => 0,
+                    (None, None) => {
+                        anyhow::bail!("either --group or --size is required")
+                    },
+                };
+                let size = match size {
+                    Some(size) => size,
+                    None => {
+                        pb.set_message("Getting Mixer Group..");
+                        context.fetch_mixer(group).await?.fixed_deposit_size
+                    },
+                };
+                let fee = match fee {
+                    Some(fee) => fee,
+                    None => {
+                        pb.set_message("Estimating the fee..");
+                        // the fee doesn't depend on the signer, nor on the
+                        // deposited leaf, so we don't ask for the password.
+                        let (pair, _) = sr25519::Pair::generate();
+                        let signer = PairSigner::new(pair);
+                        let rpc_client = context.rpc_client().await?;
+                        transaction::estimate_fee(
+                            &client,
+                            &rpc_client,
+                            DepositCall::new(
+                                group,
+                                vec![ScalarData::default()],
+                            ),
+                            &signer,
+                        )
+                        .await?
+                    },
+                };
+                let balance = match address {
+                    Some(ref address) => {
+                        pb.set_message("Getting your balance..");
+                        let account_id =
+                            AccountId32::from_ss58check(address)
+                                .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+                        let account = client.account(&account_id, None).await?;
+                        Some(account.data.free)
+                    },
+                    None => None,
+                };
+                pb.finish_and_clear();
+                let props = context.system_properties(Some(&client));
+                (size, fee, balance, props)
+            },
+        };
+        let total = size.saturating_add(fee);
+        let after = balance.map(|b| b.checked_sub(total));
+        let fmt = |v: u128| {
+            utils::format_balance(v, props.token_decimals, &props.token_symbol)
+        };
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "account": address,
+                "size": size.to_string(),
+                "fee": fee.to_string(),
+                "total": total.to_string(),
+                "balance": balance.map(|b| b.to_string()),
+                "balance_after": after.flatten().map(|b| b.to_string()),
+                "feasible": after.map(|a| a.is_some()),
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        writeln!(term, "Deposit Size: {}", style(fmt(size)).green())?;
+        writeln!(term, "Estimated Fee: {}", style(fmt(fee)).green())?;
+        writeln!(term, "Total Cost: {}", style(fmt(total)).green().bold())?;
+        match (balance, after) {
+            (Some(balance), Some(after)) => {
+                writeln!(term, "Current Balance: {}", fmt(balance))?;
+                match after {
+                    Some(after) => {
+                        writeln!(term, "Balance After: {}", fmt(after))?
+                    },
+                    None => writeln!(
+                        term,
+                        "{} insufficient balance for this deposit",
+                        style("uh oh").red()
+                    )?,
+                }
+            },
+            _ => writeln!(term, "Current Balance: {}", style("unknown").dim())?,
+        }
+        Ok(())
+    }
+}
+
 /// Withdraw from the Mixer.
 ///
 /// After doing a deposit, you use the same Note used in the `Deposit`
@@ -362,15 +1792,53 @@ pub struct WithdrawAsset {
     /// this note must be used before in a deposit.
     #[structopt(short, long)]
     alias: Option<String>,
+    /// The Note uuid, it wins over the `--alias` if both are given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// The account address that will receive the withdrawn assets.
+    ///
+    /// defaults to the default account.
+    #[structopt(short, long, value_name = "ADDRESS")]
+    recipient: Option<String>,
+    /// The relayer account address that is allowed to submit this
+    /// withdraw.
+    ///
+    /// defaults to the default account.
+    #[structopt(long, value_name = "ADDRESS")]
+    relayer: Option<String>,
+    /// Withdraw through the relayer at this URL, which submits the
+    /// withdraw for a fee, instead of submitting it with the default
+    /// account.
+    ///
+    /// the `--relayer` address then defaults to the relayer account.
+    #[structopt(long, value_name = "URL", conflicts_with = "estimate-only")]
+    relayer_url: Option<String>,
+    /// The fee paid to the relayer, in base units.
+    ///
+    /// defaults to the minimum fee the relayer asks for.
+    #[structopt(long, value_name = "AMOUNT", requires = "relayer-url")]
+    fee: Option<u128>,
+    /// Print the public inputs of the generated zkProof before submitting
+    /// it, so it could be audited.
+    ///
+    /// combine it with `--output json` to get them in a structured form.
+    #[structopt(long)]
+    dump_public_inputs: bool,
+    /// Only report what the withdraw would give, and whether the note could
+    /// be withdrawn at all, without generating the zkProof nor submitting
+    /// it.
+    ///
+    /// combine it with `--output json` to get it in a structured form.
+    #[structopt(long, conflicts_with = "dump-public-inputs")]
+    estimate_only: bool,
 }
 
 #[async_trait]
 impl super::CommandExec for WithdrawAsset {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        type MixerTrees = MixerTreesStore<WebbRuntime>;
         type CachedRoots = CachedRootsStore<WebbRuntime>;
 
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         let theme = dialoguer::theme::ColorfulTheme::default();
         let notes: Vec<_> = context.notes().iter().filter(|n| n.used).collect();
         if notes.is_empty() {
@@ -381,26 +1849,36 @@ impl super::CommandExec for WithdrawAsset {
             writeln!(term, "$ webb mixer help")?;
             return Ok(());
         }
-        let note = if let Some(val) = self.alias {
-            notes
-                .into_iter()
-                .cloned()
-                .find(|n| n.alias == val)
-                .context("note not found")
+        let selected = selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?;
+        let note = if let Some(note) = selected {
+            anyhow::ensure!(
+                note.used,
+                "the note {} is not deposited yet, do a deposit first",
+                note.alias
+            );
+            note
         } else {
             let items: Vec<_> =
                 notes.iter().map(|n| format!("{}", n)).collect();
             let notes = notes.to_owned();
+            ext::ensure_can_prompt(context, "the note alias")?;
             let i = dialoguer::Select::with_theme(&theme)
                 .with_prompt("Select one of these notes")
                 .items(&items)
                 .interact_on(&term)?;
-            Ok(notes[i].clone())
-        }?;
+            notes[i].clone()
+        };
 
         if !context.has_secret() {
             let password = Option::<SecretString>::None
                 .unwrap_or_prompt_password(
+                    context,
                     "Default Account Password",
                     &theme,
                 )?;
@@ -409,31 +1887,56 @@ impl super::CommandExec for WithdrawAsset {
         let signer = context
             .signer()
             .context("incorrect default account password!")?;
+        let parse_address = |address: Option<String>| match address {
+            Some(address) => AccountId32::from_ss58check(&address)
+                .map_err(|e| anyhow::anyhow!("{:?}", e))
+                .with_context(|| format!("invalid address {}", address)),
+            None => Ok(signer.account_id().clone()),
+        };
+        let recipient = parse_address(self.recipient)?;
+        let relayer_client = self
+            .relayer_url
+            .as_deref()
+            .map(RelayerClient::new)
+            .transpose()?;
+        let relayer_info = match relayer_client {
+            Some(ref relayer_client) => Some(relayer_client.info().await?),
+            None => None,
+        };
+        let (relayer, fee) = match relayer_info {
+            Some(info) => {
+                let fee = match self.fee {
+                    Some(fee) => fee,
+                    None => info.fee.parse().with_context(|| {
+                        format!("invalid relayer fee {}", info.fee)
+                    })?,
+                };
+                (parse_address(self.relayer.or(Some(info.account)))?, fee)
+            },
+            None => (parse_address(self.relayer)?, 0),
+        };
         let secret_note = context.decrypt_note(note.uuid.clone())?;
+        if self.estimate_only {
+            let estimate = WithdrawEstimate {
+                alias: note.alias,
+                mixer_id: note.mixer_id,
+                recipient,
+            };
+            return estimate.report(context, secret_note, &signer).await;
+        }
         let pb = ProgressBar::new_spinner();
         let pb_style = ProgressStyle::default_spinner()
             .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
             .template("{prefix:.bold.dim} {spinner} {wide_msg}");
         pb.enable_steady_tick(60);
         pb.set_style(pb_style);
-        pb.set_prefix("[1/6]");
-        pb.set_message("Creating Mixer..");
-        let mut mixer = Mixer::new(secret_note.mixer_id);
-        pb.set_prefix("[2/6]");
-        pb.set_message("Adding Note to the Mixer ...");
-        let leaf = mixer.save_note(secret_note);
-        pb.set_prefix("[3/6]");
-        pb.set_message("Connecting to the network...");
-        let client = context.client().await?;
-        pb.set_prefix("[4/6]");
+        pb.set_prefix("[1/4]");
+        let client = super::connect_to_mixer(context, &pb).await?;
+        pb.set_prefix("[2/4]");
         pb.set_message(&format!("Getting Mixer #{} leaves", note.mixer_id));
-        client
-            .fetch(&MixerTrees::new(note.mixer_id), None)
-            .await?
-            .context("mixer info not found!")?;
+        context.fetch_mixer(note.mixer_id).await?;
         let rpc_client = context.rpc_client().await?;
         let leaves = fetch_tree_leaves(&rpc_client, note.mixer_id).await?;
-        mixer.add_leaves(leaves);
         let recent_hash = client.block_hash(None).await?;
         let recent = client
             .block(recent_hash)
@@ -446,29 +1949,111 @@ impl super::CommandExec for WithdrawAsset {
             )
             .await?
             .context("no cached roots on the block!")?;
-        let root = roots.first().cloned().context("recent roots are empty!")?;
-        pb.set_prefix("[5/6]");
+        pb.set_prefix("[3/4]");
         pb.set_message("Generating zkProof ..");
-        let zkproof = mixer.generate_proof(root, leaf);
-        pb.set_prefix("[6/6]");
-        pb.set_message("Doing the Withdraw! ...");
-        let xt = client
-            .withdraw_and_watch(
-                &signer,
-                WithdrawProof {
-                    mixer_id: note.mixer_id,
-                    proof_commitments: zkproof.proof_commitments,
-                    leaf_index_commitments: zkproof.leaf_index_commitments,
-                    proof_bytes: zkproof.proof_bytes,
-                    nullifier_hash: zkproof.nullifier_hash,
-                    comms: zkproof.comms,
-                    relayer: Some(AccountId32::new(zkproof.relayer.0)),
-                    recipient: Some(AccountId32::new(zkproof.recipient.0)),
-                    cached_root: root,
-                    cached_block: recent.block.header.number,
+        let recipient_bytes: &[u8; 32] = recipient.as_ref();
+        let relayer_bytes: &[u8; 32] = relayer.as_ref();
+        let zkproof = match mixer::generate_withdraw_proof(
+            secret_note,
+            leaves,
+            ScalarData(*recipient_bytes),
+            ScalarData(*relayer_bytes),
+        ) {
+            Ok(zkproof) => zkproof,
+            Err(webb_cli::error::Error::NoteNotInTree) => anyhow::bail!(
+                "note {} was never deposited to #{} Mixer Group",
+                note.alias,
+                note.mixer_id
+            ),
+            Err(e) => return Err(e.into()),
+        };
+        let root = zkproof.root;
+        if !roots.contains(&root) {
+            anyhow::bail!(
+                "#{} Mixer Group root is not cached yet, try again later",
+                note.mixer_id
+            );
+        }
+        // generating the proof takes a while, long enough for the node to
+        // drop an idle connection.
+        let client = context.reconnect().await?;
+        if self.dump_public_inputs {
+            let inputs = PublicInputs {
+                mixer_id: note.mixer_id,
+                cached_block: recent.block.header.number,
+                cached_root: root,
+                nullifier_hash: zkproof.nullifier_hash,
+                recipient: zkproof.recipient,
+                relayer: zkproof.relayer,
+            };
+            match context.output() {
+                OutputFormat::Human => {
+                    inputs.to_string().lines().for_each(|line| pb.println(line))
                 },
-            )
-            .await?;
+                OutputFormat::Json => {
+                    pb.println(serde_json::to_string_pretty(&inputs.to_json())?)
+                },
+            }
+        }
+        let proof = WithdrawProof {
+            mixer_id: note.mixer_id,
+            proof_commitments: zkproof.proof_commitments,
+            leaf_index_commitments: zkproof.leaf_index_commitments,
+            proof_bytes: zkproof.proof_bytes,
+            nullifier_hash: zkproof.nullifier_hash,
+            comms: zkproof.comms,
+            relayer: Some(relayer),
+            recipient: Some(recipient.clone()),
+            cached_root: root,
+            cached_block: recent.block.header.number,
+        };
+        pb.set_prefix("[4/4]");
+        if let Some(relayer_client) = relayer_client {
+            pb.set_message("Sending the Withdraw to the relayer ...");
+            let request = WithdrawRequest::new(&proof, fee);
+            let id = relayer_client.withdraw(&request).await?;
+            let status = relayer_client
+                .wait_finalized(&id, |status| {
+                    if let WithdrawStatus::Submitted { tx_hash } = status {
+                        pb.set_message(&format!(
+                            "Waiting for {} to be finalized ...",
+                            tx_hash
+                        ));
+                    }
+                })
+                .await?;
+            context.forget_note(note.uuid).context("remove old note")?;
+            pb.finish_and_clear();
+            let props = context.system_properties(Some(&client));
+            let fee = utils::format_balance(
+                fee,
+                props.token_decimals,
+                &props.token_symbol,
+            );
+            writeln!(
+                term,
+                "{} Note Withdrawn Successfully!",
+                Emoji("🎉", "※")
+            )?;
+            writeln!(term, "Recipient: {}", style(&recipient).green())?;
+            if let WithdrawStatus::Finalized {
+                block_hash,
+                block_number,
+                ..
+            } = status
+            {
+                writeln!(
+                    term,
+                    "Block Number: #{} {}",
+                    style(block_number).blue(),
+                    style(block_hash).dim().green()
+                )?;
+            }
+            writeln!(term, "Relayer Fee: {}", style(fee).yellow())?;
+            return Ok(());
+        }
+        pb.set_message("Doing the Withdraw! ...");
+        let xt = client.withdraw_and_watch(&signer, proof).await?;
         context.forget_note(note.uuid).context("remove old note")?;
         pb.finish_and_clear();
         let xt_block = xt.block;
@@ -479,10 +2064,16 @@ impl super::CommandExec for WithdrawAsset {
         let hash = signed_block.block.header.hash();
         let account_id = signer.account_id();
         let account = client.account(&account_id, None).await?;
-        let props = SystemProperties::from(client.properties());
-        let balance =
-            account.data.free / 10u128.pow(props.token_decimals as u32);
+        let props = context.system_properties(Some(&client));
+        let balance = utils::format_balance(
+            account.data.free,
+            props.token_decimals,
+            &props.token_symbol,
+        );
         writeln!(term, "{} Note Withdrawn Successfully!", Emoji("🎉", "※"))?;
+        if &recipient != account_id {
+            writeln!(term, "Recipient: {}", style(&recipient).green())?;
+        }
         writeln!(
             term,
             "Block Number: #{} {}",
@@ -492,14 +2083,223 @@ impl super::CommandExec for WithdrawAsset {
         writeln!(term)?;
         writeln!(
             term,
-            "Your Current Free Balance: {} {}",
+            "Your Current Free Balance: {}",
             style(balance).green().bold(),
-            props.token_symbol,
         )?;
         Ok(())
     }
 }
 
+/// What withdrawing a note gives, estimated without generating the
+/// zkProof, see `webb mixer withdraw --estimate-only`.
+struct WithdrawEstimate {
+    alias: String,
+    mixer_id: u32,
+    recipient: AccountId32,
+}
+
+impl WithdrawEstimate {
+    /// Checks the note could be withdrawn, estimates the fee paid by the
+    /// `signer` and prints the outcome.
+    ///
+    /// a note that can't be withdrawn is reported with the reason, it is
+    /// not an error.
+    async fn report(
+        self,
+        context: &ExecutionContext,
+        secret_note: Note,
+        signer: &AccountSigner,
+    ) -> anyhow::Result<()> {
+        type CachedRoots = CachedRootsStore<WebbRuntime>;
+        type UsedNullifiers = UsedNullifiersStore<WebbRuntime>;
+
+        let mut term = context.term();
+        let pb = ProgressBar::new_spinner();
+        pb.enable_steady_tick(60);
+        let client = super::connect_to_mixer(context, &pb).await?;
+        pb.set_message(&format!("Getting Mixer #{} leaves", self.mixer_id));
+        let info = context.fetch_mixer(self.mixer_id).await?;
+        let rpc_client = context.rpc_client().await?;
+        let leaves = fetch_tree_leaves(&rpc_client, self.mixer_id).await?;
+        let recent_hash = client.block_hash(None).await?;
+        let recent = client
+            .block(recent_hash)
+            .await?
+            .context("getting last block")?;
+        let roots = client
+            .fetch(
+                &CachedRoots::new(recent.block.header.number, self.mixer_id),
+                None,
+            )
+            .await?
+            .unwrap_or_default();
+        pb.set_message("Checking the note..");
+        let reason = match mixer::preview_withdraw(secret_note, leaves) {
+            Ok(preview) => {
+                let used = client
+                    .fetch(
+                        &UsedNullifiers::new(
+                            self.mixer_id,
+                            preview.nullifier_hash,
+                        ),
+                        None,
+                    )
+                    .await?
+                    .unwrap_or_default();
+                if used {
+                    Some(String::from("it was already withdrawn"))
+                } else if !roots.contains(&preview.root) {
+                    Some(format!(
+                        "#{} Mixer Group root is not cached yet, try again \
+                         later",
+                        self.mixer_id
+                    ))
+                } else {
+                    None
+                }
+            },
+            Err(webb_cli::error::Error::NoteNotInTree) => Some(format!(
+                "it was never deposited to #{} Mixer Group",
+                self.mixer_id
+            )),
+            Err(e) => return Err(e.into()),
+        };
+        pb.set_message("Estimating the fee..");
+        // the fee depends on the call length, and not on the proof values,
+        // so an empty proof is used, which doesn't count the proof bytes.
+        let placeholder = WithdrawProof {
+            mixer_id: self.mixer_id,
+            cached_block: recent.block.header.number,
+            cached_root: ScalarData::default(),
+            comms: Vec::new(),
+            nullifier_hash: ScalarData::default(),
+            proof_bytes: Vec::new(),
+            leaf_index_commitments: Vec::new(),
+            proof_commitments: Vec::new(),
+            recipient: Some(self.recipient.clone()),
+            relayer: Some(signer.account_id().clone()),
+        };
+        let fee = transaction::estimate_fee(
+            &client,
+            &rpc_client,
+            WithdrawCall::new(placeholder),
+            signer,
+        )
+        .await?;
+        pb.finish_and_clear();
+
+        let amount = info.fixed_deposit_size;
+        // the fee is paid by whoever submits the withdraw.
+        let self_submitted = &self.recipient == signer.account_id();
+        let net = if self_submitted {
+            amount.saturating_sub(fee)
+        } else {
+            amount
+        };
+        let props = context.system_properties(Some(&client));
+        let fmt = |v: u128| {
+            utils::format_balance(v, props.token_decimals, &props.token_symbol)
+        };
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "note": self.alias,
+                "mixer_id": self.mixer_id,
+                "recipient": self.recipient.to_ss58check(),
+                "amount": amount.to_string(),
+                "fee": fee.to_string(),
+                "net": net.to_string(),
+                "withdrawable": reason.is_none(),
+                "reason": reason,
+            });
+            writeln!(term, "{}", value)?;
+            return Ok(());
+        }
+        writeln!(term, "Withdraw Amount: {}", style(fmt(amount)).green())?;
+        writeln!(
+            term,
+            "Estimated Fee: {} (paid by the submitter, excluding the proof \
+             bytes)",
+            style(fmt(fee)).green()
+        )?;
+        writeln!(
+            term,
+            "Net to {}: {}",
+            style(&self.recipient).green(),
+            style(fmt(net)).green().bold()
+        )?;
+        writeln!(term)?;
+        match reason {
+            Some(reason) => writeln!(
+                term,
+                "{} {} is not withdrawable, because {}",
+                style("uh oh").red(),
+                self.alias,
+                reason
+            )?,
+            None => writeln!(
+                term,
+                "{} {} can be withdrawn",
+                Emoji("✅", "√"),
+                self.alias
+            )?,
+        }
+        Ok(())
+    }
+}
+
+/// The public inputs of a withdraw zkProof.
+///
+/// These are public by definition, and binds the withdraw parameters,
+/// so it is safe to print them.
+struct PublicInputs {
+    mixer_id: u32,
+    cached_block: u32,
+    cached_root: ScalarData,
+    nullifier_hash: ScalarData,
+    recipient: ScalarData,
+    relayer: ScalarData,
+}
+
+impl PublicInputs {
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "mixer_id": self.mixer_id,
+            "cached_block": self.cached_block,
+            "cached_root": hex_encode(&self.cached_root),
+            "nullifier_hash": hex_encode(&self.nullifier_hash),
+            "recipient": hex_encode(&self.recipient),
+            "relayer": hex_encode(&self.relayer),
+        })
+    }
+}
+
+impl fmt::Display for PublicInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Public Inputs:")?;
+        writeln!(f, "  Mixer Group: #{}", self.mixer_id)?;
+        writeln!(f, "  Cached Block: #{}", self.cached_block)?;
+        writeln!(f, "  Cached Root: {}", hex_encode(&self.cached_root))?;
+        writeln!(f, "  Nullifier Hash: {}", hex_encode(&self.nullifier_hash))?;
+        writeln!(f, "  Recipient: {}", hex_encode(&self.recipient))?;
+        write!(f, "  Relayer: {}", hex_encode(&self.relayer))
+    }
+}
+
+fn hex_encode(v: &ScalarData) -> String { format!("0x{}", hex::encode(v.0)) }
+
+/// The number of deposits to the `tree_id` mixer, i.e the leaves of its
+/// tree, read from the tree storage without fetching the leaves.
+async fn fetch_deposits_count(
+    client: &Client<WebbRuntime>,
+    tree_id: u32,
+) -> anyhow::Result<u32> {
+    let tree = client
+        .fetch(&TreesStore::<WebbRuntime>::new(tree_id), None)
+        .await?
+        .with_context(|| format!("there is no tree #{}", tree_id))?;
+    Ok(tree.leaf_count)
+}
+
 /// fetch all the tree leaves in batches.
 async fn fetch_tree_leaves(
     rpc_client: &RpcClient,
@@ -1,15 +1,30 @@
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
 
+use anyhow::Context;
 use async_trait::async_trait;
+use indicatif::ProgressBar;
 use secrecy::SecretString;
 use structopt::StructOpt;
+use subxt::{Call, Client, Metadata, MetadataError, Store};
+use webb_cli::pallet::merkle::{CachedRootsStore, TreesStore};
+use webb_cli::pallet::mixer::{
+    DepositCall, MixerTreeIdsStore, MixerTreesStore, WithdrawCall,
+};
+use webb_cli::runtime::WebbRuntime;
 
-use crate::context::ExecutionContext;
+use crate::context::{ExecutionContext, OutputFormat};
 use crate::utils;
 
 mod account;
+mod completions;
+mod db;
 mod default;
+mod keystore;
+mod man;
 mod mixer;
+mod relayer;
+mod report;
 mod show;
 
 /// A General trait used to organize all commands.
@@ -18,12 +33,85 @@ pub trait CommandExec {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()>;
 }
 
+/// Connects to the node, showing the progress on the `pb` spinner.
+///
+/// All the commands that need a connection to the node should use this
+/// helper, so the connection feedback and errors are the same everywhere.
+pub async fn connect_with_feedback(
+    context: &ExecutionContext,
+    pb: &ProgressBar,
+) -> anyhow::Result<Client<WebbRuntime>> {
+    pb.set_message("Connecting to the network...");
+    let client = context.client().await.with_context(|| {
+        format!(
+            "failed to connect to the node at {}, is it running?",
+            context.rpc_url()
+        )
+    })?;
+    log::debug!("connected to {}", context.rpc_url());
+    Ok(client)
+}
+
+/// Connects to the node like [connect_with_feedback], then makes sure it
+/// is a Webb node that has the mixer pallets we need.
+///
+/// Without this check, pointing the CLI to a generic substrate node ends
+/// with confusing metadata errors in the middle of the operation.
+pub async fn connect_to_mixer(
+    context: &ExecutionContext,
+    pb: &ProgressBar,
+) -> anyhow::Result<Client<WebbRuntime>> {
+    let client = connect_with_feedback(context, pb).await?;
+    pb.set_message("Checking the node capabilities...");
+    if let Err(e) = check_mixer_support(client.metadata()) {
+        log::debug!("missing mixer capability: {}", e);
+        anyhow::bail!(
+            "the connected node ({}) does not expose the Webb mixer pallets; \
+             check your --node-url",
+            context.rpc_url()
+        );
+    }
+    Ok(client)
+}
+
+/// Checks that the runtime `metadata` has all the mixer and merkle storage
+/// items and calls used by the CLI.
+fn check_mixer_support(metadata: &Metadata) -> Result<(), MetadataError> {
+    fn store<S: Store<WebbRuntime>>(
+        metadata: &Metadata,
+    ) -> Result<(), MetadataError> {
+        metadata.module(S::MODULE)?.storage(S::FIELD).map(|_| ())
+    }
+    fn call<C: Call<WebbRuntime>>(
+        metadata: &Metadata,
+    ) -> Result<(), MetadataError> {
+        metadata
+            .module_with_calls(C::MODULE)?
+            .call(C::FUNCTION, ())
+            .map(|_| ())
+    }
+    store::<MixerTreeIdsStore<WebbRuntime>>(metadata)?;
+    store::<MixerTreesStore<WebbRuntime>>(metadata)?;
+    store::<CachedRootsStore<WebbRuntime>>(metadata)?;
+    store::<TreesStore<WebbRuntime>>(metadata)?;
+    call::<DepositCall<WebbRuntime>>(metadata)?;
+    call::<WithdrawCall<WebbRuntime>>(metadata)?;
+    Ok(())
+}
+
 #[derive(StructOpt)]
 pub enum SubCommand {
     Show(show::ShowCommand),
     Default(default::DefaultCommand),
     Account(account::AccountCommand),
     Mixer(mixer::MixerCommand),
+    Relayer(relayer::RelayerCommand),
+    Keystore(keystore::KeystoreCommand),
+    Report(report::ReportCommand),
+    Db(db::DbCommand),
+    Completions(completions::CompletionsCommand),
+    #[structopt(setting = structopt::clap::AppSettings::Hidden)]
+    Man(man::ManCommand),
 }
 
 #[derive(StructOpt, Clone, Debug)]
@@ -33,17 +121,20 @@ pub struct PasswordOpts {
     #[structopt(
         global = true,
         long = "password-interactive",
-        conflicts_with_all = &["password", "password-filename"]
+        conflicts_with_all = &["password", "password-filename", "password-stdin"]
     )]
     pub password_interactive: bool,
 
     /// Password used by the secret datastore.
+    ///
+    /// requires `--unsafe`, same as the `WEBB_PASSWORD` env variable, which
+    /// is used when none of the password options is passed.
     #[structopt(
         global = true,
         long = "password",
         short,
         parse(try_from_str = utils::secret_string_from_str),
-        conflicts_with_all = &["password-interactive", "password-filename"]
+        conflicts_with_all = &["password-interactive", "password-filename", "password-stdin"]
     )]
     pub password: Option<SecretString>,
 
@@ -53,14 +144,30 @@ pub struct PasswordOpts {
         long = "password-filename",
         value_name = "PATH",
         parse(from_os_str),
-        conflicts_with_all = &["password-interactive", "password"]
+        conflicts_with_all = &["password-interactive", "password", "password-stdin"]
     )]
     pub password_filename: Option<PathBuf>,
+
+    /// Read the password used by the secret datastore from the standard
+    /// input, i.e `cat pw.txt | webb --password-stdin ...`.
+    ///
+    /// a single trailing newline is not part of the password.
+    #[structopt(
+        global = true,
+        long = "password-stdin",
+        conflicts_with_all = &["password-interactive", "password", "password-filename"]
+    )]
+    pub password_stdin: bool,
 }
 
 #[derive(StructOpt, Clone, Debug)]
 pub struct NodeOpts {
     /// Set the Node Url where we will connect to.
+    ///
+    /// it could be passed after any sub-command too, i.e `webb mixer
+    /// deposit --node-url <URL>`. the flag takes precedence over the
+    /// `WEBB_NODE_URL` env variable, which takes precedence over the
+    /// default.
     #[structopt(
         global = true,
         long = "node-url",
@@ -69,4 +176,69 @@ pub struct NodeOpts {
         parse(try_from_str = url::Url::parse)
     )]
     pub url: url::Url,
+
+    /// A JSON file with the chain properties (`ss58_format`,
+    /// `token_decimals` and `token_symbol`) to use instead of the ones
+    /// reported by the node.
+    #[structopt(
+        global = true,
+        long = "chain-properties-file",
+        value_name = "PATH",
+        parse(from_os_str)
+    )]
+    pub chain_properties_file: Option<PathBuf>,
+
+    /// The maximum number of RPC requests sent to the node at once, by the
+    /// commands that do many queries (i.e `account list --with-activity`).
+    ///
+    /// lower it if the node rate-limits or struggles with the load.
+    #[structopt(
+        global = true,
+        long = "rpc-concurrency",
+        value_name = "N",
+        default_value = "8"
+    )]
+    pub rpc_concurrency: NonZeroUsize,
+
+    /// The time limit, in seconds, of a single attempt to connect to the
+    /// node, the connection is retried a few times before giving up.
+    #[structopt(
+        global = true,
+        long = "connect-timeout",
+        value_name = "SECS",
+        default_value = "10"
+    )]
+    pub connect_timeout: u64,
+}
+
+#[derive(StructOpt, Clone, Debug)]
+pub struct OutputOpts {
+    /// The output format of the commands, either `human` or `json`.
+    #[structopt(
+        global = true,
+        long = "output",
+        default_value = "human",
+        possible_values = &["human", "json"],
+    )]
+    pub format: OutputFormat,
+
+    /// Shorthand for `--output json`.
+    ///
+    /// the interactive prompts are disabled too, so any missing argument
+    /// is an error instead.
+    #[structopt(global = true, long)]
+    pub json: bool,
+
+    /// The ss58 format (network prefix) to display the addresses with,
+    /// i.e `7` for Edgeware.
+    ///
+    /// defaults to the format of the node, if the command connects to it,
+    /// and to the generic substrate format otherwise.
+    #[structopt(
+        global = true,
+        long,
+        value_name = "N",
+        parse(try_from_str = utils::ss58_format_from_str)
+    )]
+    pub ss58_format: Option<u16>,
 }
@@ -0,0 +1,130 @@
+use std::io::Write;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use console::style;
+use indicatif::ProgressBar;
+use structopt::StructOpt;
+
+use crate::context::{ExecutionContext, OutputFormat};
+use crate::relayer::RelayerClient;
+use crate::{ext, utils};
+
+/// Talk to the Webb relayers, that submit the withdraws for a fee.
+#[derive(StructOpt)]
+pub enum RelayerCommand {
+    /// Asks a relayer for its fee to withdraw a note, without withdrawing.
+    Quote(QuoteFee),
+}
+
+/// Asks the relayer how much it charges to withdraw a note, and whether it
+/// relays the withdraws from the note Mixer Group.
+///
+/// nothing is submitted, so it is handy to compare the relayers fees
+/// before doing `webb mixer withdraw --relayer-url`.
+#[derive(StructOpt)]
+pub struct QuoteFee {
+    /// The Note alias to get a quote for.
+    alias: Option<String>,
+    /// The Note uuid, it wins over the alias if both are given.
+    #[structopt(long)]
+    uuid: Option<String>,
+    /// The relayer URL.
+    #[structopt(long, value_name = "URL", env = "WEBB_RELAYER_URL")]
+    relayer_url: String,
+}
+
+#[async_trait]
+impl super::CommandExec for RelayerCommand {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        match self {
+            RelayerCommand::Quote(cmd) => cmd.exec(context).await,
+        }
+    }
+}
+
+#[async_trait]
+impl super::CommandExec for QuoteFee {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let theme = dialoguer::theme::ColorfulTheme::default();
+        let relayer = RelayerClient::new(&self.relayer_url)?;
+        let selected = super::mixer::selected_note(
+            context,
+            &term,
+            &theme,
+            self.alias.as_deref(),
+            self.uuid.as_deref(),
+        )?;
+        // an unknown alias or uuid is already an error.
+        let note = match selected {
+            Some(note) => note,
+            None => {
+                let notes = context.notes();
+                anyhow::ensure!(!notes.is_empty(), "there is no saved notes!");
+                let items: Vec<_> =
+                    notes.iter().map(|n| format!("{}", n)).collect();
+                ext::ensure_can_prompt(context, "the note alias")?;
+                let i = dialoguer::Select::with_theme(&theme)
+                    .with_prompt("Select one of these notes")
+                    .items(&items)
+                    .interact_on(&term)?;
+                notes[i].clone()
+            },
+        };
+        let pb = ProgressBar::new_spinner();
+        pb.enable_steady_tick(60);
+        pb.set_message("Asking the relayer for a quote...");
+        let info = relayer.info().await?;
+        pb.finish_and_clear();
+        let fee: u128 = info
+            .fee
+            .parse()
+            .with_context(|| format!("invalid relayer fee {}", info.fee))?;
+        // the fee is in the chain base units, which may not be the note
+        // precision (i.e an imported note).
+        let props = context.system_properties(None);
+        let human_fee = utils::format_balance(
+            fee,
+            props.token_decimals,
+            &note.token_symbol,
+        );
+        let supported = info
+            .mixers
+            .as_ref()
+            .map(|mixers| mixers.contains(&note.mixer_id));
+        if context.output() == OutputFormat::Json {
+            let value = serde_json::json!({
+                "relayer": self.relayer_url,
+                "account": info.account,
+                "note": {
+                    "alias": note.alias,
+                    "uuid": note.uuid,
+                    "mixer_id": note.mixer_id,
+                },
+                "fee": {
+                    "amount": fee.to_string(),
+                    "human": human_fee,
+                },
+                "supported": supported,
+            });
+            writeln!(term, "{}", serde_json::to_string_pretty(&value)?)?;
+            return Ok(());
+        }
+        writeln!(term, "Relayer: {}", style(&self.relayer_url).blue())?;
+        writeln!(term, "Account: {}", style(&info.account).dim())?;
+        writeln!(
+            term,
+            "Fee: {} ({} base units)",
+            style(human_fee).yellow().bold(),
+            fee
+        )?;
+        let support = match supported {
+            Some(true) => style("supported").green(),
+            Some(false) => style("not supported").red(),
+            None => style("not advertised by the relayer").dim(),
+        };
+        writeln!(term, "Mixer Group #{}: {}", note.mixer_id, support)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use console::Emoji;
+use jsonrpsee_types::jsonrpc::Params;
+use sha2::Digest;
+use structopt::StructOpt;
+use subxt::sp_core::crypto::{AccountId32, Ss58Codec};
+
+use crate::context::ExecutionContext;
+
+/// Generate a diagnostic bundle to attach to a bug report.
+///
+/// the bundle is only saved locally, nothing is uploaded. it has the CLI
+/// version, the OS, the connected node chain and version and a summary of
+/// the saved accounts and notes, with every secret, seed, note and address
+/// stripped, and the aliases hashed.
+///
+/// have a look at it before sharing it anyway.
+#[derive(StructOpt)]
+pub struct ReportCommand {
+    /// Where to save the report.
+    #[structopt(short, long, value_name = "FILE", parse(from_os_str))]
+    out: PathBuf,
+}
+
+/// What we know about the connected node.
+#[derive(Debug, Default)]
+struct NodeInfo {
+    chain: Option<String>,
+    version: Option<String>,
+    mixer_support: Option<bool>,
+    error: Option<String>,
+}
+
+#[async_trait]
+impl super::CommandExec for ReportCommand {
+    async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
+        let mut term = context.term();
+        let node = node_info(context).await;
+        let report = build_report(context, &node);
+        let contents = redact(&serde_json::to_string_pretty(&report)?);
+        std::fs::write(&self.out, contents).with_context(|| {
+            format!("failed to write {}", self.out.display())
+        })?;
+        writeln!(
+            term,
+            "{} Report saved to {}, have a look at it before sharing it.",
+            Emoji("📦", "√"),
+            self.out.display()
+        )?;
+        Ok(())
+    }
+}
+
+/// Queries the node, recording the error instead of failing if we can't.
+async fn node_info(context: &ExecutionContext) -> NodeInfo {
+    let client = match context.client().await {
+        Ok(client) => client,
+        Err(e) => {
+            return NodeInfo {
+                error: Some(e.to_string()),
+                ..Default::default()
+            }
+        },
+    };
+    let mut info = NodeInfo {
+        mixer_support: Some(
+            super::check_mixer_support(client.metadata()).is_ok(),
+        ),
+        ..Default::default()
+    };
+    match context.rpc_client().await {
+        Ok(rpc_client) => {
+            info.chain =
+                rpc_client.request("system_chain", Params::None).await.ok();
+            info.version = rpc_client
+                .request("system_version", Params::None)
+                .await
+                .ok();
+        },
+        Err(e) => info.error = Some(e.to_string()),
+    }
+    info
+}
+
+/// Builds the report, only from the values that are safe to share.
+fn build_report(
+    context: &ExecutionContext,
+    node: &NodeInfo,
+) -> serde_json::Value {
+    let url = context.rpc_url();
+    let notes: Vec<_> = context
+        .notes()
+        .iter()
+        .map(|note| {
+            serde_json::json!({
+                "alias": anonymize(&note.alias),
+                "token_symbol": note.token_symbol,
+                "mixer_id": note.mixer_id,
+                "used": note.used,
+                "has_amount": !note.amount.is_empty(),
+                "chain": note.chain,
+            })
+        })
+        .collect();
+    let unreadable_notes: Vec<_> = context
+        .unreadable_notes()
+        .iter()
+        .map(|(uuid, reason)| {
+            serde_json::json!({ "id": anonymize(uuid), "reason": reason })
+        })
+        .collect();
+    serde_json::json!({
+        "cli": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+        },
+        "node": {
+            // the path, query and credentials could hold api keys.
+            "url": format!(
+                "{}://{}:{}",
+                url.scheme(),
+                url.host_str().unwrap_or_default(),
+                url.port_or_known_default().unwrap_or_default(),
+            ),
+            "chain": node.chain,
+            "version": node.version,
+            "mixer_support": node.mixer_support,
+            "error": node.error,
+        },
+        "store": {
+            "accounts": context.accounts().len(),
+            "has_default_account": context.default_account().is_ok(),
+            "notes": notes,
+            "unreadable_notes": unreadable_notes,
+        },
+    })
+}
+
+/// A short, stable, hash of `value`, to tell values apart without
+/// revealing them.
+fn anonymize(value: &str) -> String {
+    let hash = sha2::Sha256::digest(value.as_bytes());
+    hex::encode(&hash[..6])
+}
+
+/// Strips anything that looks like an address or a secret from `text`.
+///
+/// the report is built only from safe values in the first place, this is
+/// a last line of defense against one of them (i.e an error message)
+/// carrying an address, a note or a seed.
+fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut token = String::new();
+    for c in text.chars().chain(std::iter::once('\0')) {
+        if c.is_ascii_alphanumeric() {
+            token.push(c);
+            continue;
+        }
+        out.push_str(redact_token(&token));
+        token.clear();
+        if c != '\0' {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn redact_token(token: &str) -> &str {
+    let hex = token.strip_prefix("0x").unwrap_or(token);
+    if hex.len() >= 32 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        "<redacted>"
+    } else if token.len() >= 40 && AccountId32::from_ss58check(token).is_ok() {
+        "<redacted address>"
+    } else {
+        token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use webb_cli::keystore::KeyScheme;
+    use webb_cli::mixer::{Mixer, TokenSymbol};
+
+    use super::*;
+
+    #[test]
+    fn report_has_no_secrets() {
+        let mut context = ExecutionContext::temporary();
+        let (address, phrase) = context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, true)
+            .unwrap();
+        let account = context.default_account().unwrap().clone();
+        let seed = context.stored_seed(&account.uuid).unwrap();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        context
+            .import_note(String::from("my-note"), note.clone())
+            .unwrap();
+        let uuid = context.notes()[0].uuid.clone();
+        let node = NodeInfo {
+            error: Some(format!("bad account {}", address.to_ss58check())),
+            ..Default::default()
+        };
+        let report = build_report(&context, &node);
+        let text = redact(&serde_json::to_string_pretty(&report).unwrap());
+
+        assert!(text.contains(env!("CARGO_PKG_VERSION")));
+        assert!(!text.contains(&address.to_ss58check()));
+        assert!(!text.contains(&account.uuid));
+        assert!(!text.contains("alice"));
+        assert!(!text.contains(&hex::encode(&seed.bytes)));
+        // single words could be a report key (i.e `chain`), but never a
+        // run of them.
+        let words: Vec<_> = phrase.split_whitespace().collect();
+        for pair in words.windows(2) {
+            assert!(!text.contains(&pair.join(" ")));
+        }
+        let note = note.to_string();
+        assert!(!text.contains(note.rsplit('-').next().unwrap()));
+        assert!(!text.contains(&uuid));
+        assert!(!text.contains("my-note"));
+    }
+
+    #[test]
+    fn redacts_addresses_and_hex() {
+        let text = "to 5EL3BKivQqL6uSqb5VereNRkucyNHnfT1tJKC3z1CTfWC7in with \
+                    webb.mix-v1-EDG-0-0123456789abcdef0123456789abcdef!";
+        assert_eq!(
+            redact(text),
+            "to <redacted address> with webb.mix-v1-EDG-0-<redacted>!"
+        );
+        assert_eq!(redact("block #42 at 0xabcd"), "block #42 at 0xabcd");
+    }
+}
@@ -1,24 +1,42 @@
 use std::io::Write;
 
 use async_trait::async_trait;
+use console::style;
+use jsonrpsee_types::jsonrpc::Params;
 use structopt::StructOpt;
 
-use crate::context::ExecutionContext;
+use crate::context::{ExecutionContext, OutputFormat};
 
 /// Show the active account (if any)
 /// and other information about the CLI Configrations.
 #[derive(StructOpt)]
 pub enum ShowCommand {
-    /// Display the path to the Webb CLI.
+    /// Display the path to the Webb CLI active profile.
     Home,
     /// Shows the active Account.
     Account,
+    /// Shows the resolved configuration, handy to attach to bug reports.
+    ///
+    /// the chain properties are fetched from the node, if it is not
+    /// reachable they are reported as offline.
+    Config,
+    /// Shows the CLI version, and the node runtime version if it is
+    /// reachable, handy to attach to bug reports.
+    Version,
+}
+
+/// The parts of the node `state_getRuntimeVersion` we care about.
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersion {
+    spec_name: String,
+    spec_version: u32,
 }
 
 #[async_trait]
 impl super::CommandExec for ShowCommand {
     async fn exec(self, context: &mut ExecutionContext) -> anyhow::Result<()> {
-        let mut term = console::Term::stdout();
+        let mut term = context.term();
         match self {
             Self::Home => {
                 let home = context.home();
@@ -26,7 +44,21 @@ impl super::CommandExec for ShowCommand {
             },
             Self::Account => {
                 let accounts = context.accounts();
-                if let Some(account) = accounts.iter().find(|a| a.is_default) {
+                let default = accounts
+                    .iter()
+                    .find(|a| a.is_default)
+                    .map(|account| context.display_account(account));
+                if context.output() == OutputFormat::Json {
+                    let value = default.map(|account| {
+                        serde_json::json!({
+                            "alias": account.alias,
+                            "address": account.address,
+                            "is_default": account.is_default,
+                        })
+                    });
+                    let json = serde_json::to_string_pretty(&value)?;
+                    writeln!(term, "{}", json)?;
+                } else if let Some(account) = default {
                     writeln!(term, "{}", account)?;
                 } else {
                     writeln!(term, "you don't have any accounts.")?;
@@ -35,7 +67,123 @@ impl super::CommandExec for ShowCommand {
                     writeln!(term)?;
                 }
             },
+            Self::Config => {
+                // the local parts are reported even if the node is down.
+                let props = match context.client().await {
+                    Ok(client) => {
+                        Some(context.system_properties(Some(&client)))
+                    },
+                    Err(e) => {
+                        log::debug!("failed to connect to the node: {}", e);
+                        None
+                    },
+                };
+                let url = context.rpc_url();
+                let home = context.home();
+                if context.output() == OutputFormat::Json {
+                    let chain = props.map(|props| {
+                        serde_json::json!({
+                            "ss58_format": props.ss58_format,
+                            "token_decimals": props.token_decimals,
+                            "token_symbol": props.token_symbol,
+                        })
+                    });
+                    let value = serde_json::json!({
+                        "node_url": url.as_str(),
+                        "home": home,
+                        "has_secret": context.has_secret(),
+                        "accounts": context.accounts().len(),
+                        "notes": context.notes().len(),
+                        "chain": chain,
+                    });
+                    let json = serde_json::to_string_pretty(&value)?;
+                    writeln!(term, "{}", json)?;
+                    return Ok(());
+                }
+                let yes_no = |v: bool| if v { "yes" } else { "no" };
+                writeln!(term, "Node URL: {}", style(url).green())?;
+                writeln!(term, "Home: {}", home.display())?;
+                writeln!(
+                    term,
+                    "Password Loaded: {}",
+                    yes_no(context.has_secret())
+                )?;
+                writeln!(term, "Accounts: {}", context.accounts().len())?;
+                writeln!(term, "Notes: {}", context.notes().len())?;
+                match props {
+                    Some(props) => {
+                        writeln!(term, "SS58 Format: {}", props.ss58_format)?;
+                        writeln!(
+                            term,
+                            "Token Decimals: {}",
+                            props.token_decimals
+                        )?;
+                        writeln!(term, "Token Symbol: {}", props.token_symbol)?;
+                    },
+                    None => writeln!(
+                        term,
+                        "Chain Properties: {}",
+                        style("offline").dim()
+                    )?,
+                }
+            },
+            Self::Version => {
+                // the CLI version is reported even if the node is down.
+                let runtime = match runtime_version(context).await {
+                    Ok(version) => Some(version),
+                    Err(e) => {
+                        log::debug!("failed to get the runtime version: {}", e);
+                        None
+                    },
+                };
+                let version = env!("CARGO_PKG_VERSION");
+                let commit = option_env!("WEBB_GIT_COMMIT");
+                if context.output() == OutputFormat::Json {
+                    let runtime = runtime.map(|runtime| {
+                        serde_json::json!({
+                            "spec_name": runtime.spec_name,
+                            "spec_version": runtime.spec_version,
+                        })
+                    });
+                    let value = serde_json::json!({
+                        "version": version,
+                        "commit": commit,
+                        "runtime": runtime,
+                    });
+                    let json = serde_json::to_string_pretty(&value)?;
+                    writeln!(term, "{}", json)?;
+                    return Ok(());
+                }
+                match commit {
+                    Some(commit) => {
+                        writeln!(term, "Webb CLI: {} ({})", version, commit)?
+                    },
+                    None => writeln!(term, "Webb CLI: {}", version)?,
+                }
+                match runtime {
+                    Some(runtime) => writeln!(
+                        term,
+                        "Runtime: {} v{}",
+                        runtime.spec_name, runtime.spec_version
+                    )?,
+                    None => {
+                        writeln!(term, "Runtime: {}", style("offline").dim())?
+                    },
+                }
+            },
         };
         Ok(())
     }
 }
+
+async fn runtime_version(
+    context: &ExecutionContext,
+) -> anyhow::Result<RuntimeVersion> {
+    // connecting through the client first gets us its retries and timeout.
+    context.client().await?;
+    let rpc_client = context.rpc_client().await?;
+    let version = rpc_client
+        .request("state_getRuntimeVersion", Params::None)
+        .await?;
+    Ok(version)
+}
@@ -1,21 +1,45 @@
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use anyhow::{Context, Result};
 use bip39::Mnemonic;
-use directories_next::ProjectDirs;
 use jsonrpsee_ws_client::{WsClient, WsConfig};
+use prost::Message;
 use secrecy::SecretString;
-use subxt::sp_core::sr25519::Pair as Sr25519Pair;
-use subxt::sp_core::Pair;
-use subxt::{Client, PairSigner, RpcClient};
+use serde::Deserialize;
+use subxt::sp_core::crypto::AccountId32;
+use subxt::{Client, RpcClient, Signer};
 use webb_cli::account;
-use webb_cli::keystore::PublicFor;
+use webb_cli::keystore::{AccountSigner, KeyScheme, SchemePair};
 use webb_cli::mixer::{Mixer, Note, TokenSymbol};
-use webb_cli::runtime::WebbRuntime;
+use webb_cli::pallet::mixer::{
+    DepositCall, MixerInfo, MixerTreeIdsStore, MixerTreesStore,
+};
+use webb_cli::pallet::ScalarData;
+use webb_cli::runtime::{Hash, WebbRuntime};
 
-use crate::database::SledDatastore;
-use crate::raw::{AccountRaw, AccountsIds, NoteRaw, NotesIds};
+use crate::database::{Kdf, Keyspace, SledDatastore};
+use crate::raw::{
+    AccountRaw, AccountsIds, Backup, BackupAccount, BackupNote, NoteRaw,
+    NotesIds, SeedScheme, StoredSeed,
+};
+use crate::term::{self, Term};
+use crate::{transaction, utils};
+
+/// The node we connect to, unless told otherwise.
+const DEFAULT_RPC_URL: &str = "ws://127.0.0.1:9944";
+/// The default number of in-flight RPC requests.
+const DEFAULT_RPC_CONCURRENCY: usize = 8;
+/// How many times we try to connect to the node before giving up.
+const CONNECT_ATTEMPTS: u32 = 5;
+/// The wait before retrying to connect, doubled after each retry.
+const CONNECT_BACKOFF: Duration = Duration::from_millis(500);
+/// The default time limit of a single connection attempt.
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Commands Execution Context.
 ///
@@ -25,32 +49,108 @@ pub struct ExecutionContext {
     accounts: Vec<AccountRaw>,
     /// All Saved notes.
     notes: Vec<NoteRaw>,
+    /// The notes we failed to decode, as (uuid, reason).
+    unreadable_notes: Vec<(String, String)>,
     /// The Safe encrypted datastore.
     db: SledDatastore,
-    /// Home of Webb CLI.
-    dirs: ProjectDirs,
+    /// Home of Webb CLI, the directory of the active profile.
+    home: PathBuf,
     /// RPC Endpoint.
     rpc_url: url::Url,
+    /// The format used to print the commands output.
+    output: OutputFormat,
+    /// The transcript file where all of the commands output is copied to.
+    transcript: Option<Arc<Mutex<File>>>,
+    /// Chain properties that overrides the ones reported by the node.
+    chain_properties: Option<SystemProperties>,
+    /// Whether the unsafe operations are enabled (`--unsafe`).
+    unsafe_mode: bool,
+    /// Whether we are allowed to prompt the user for the missing arguments.
+    prompts: bool,
+    /// The maximum number of in-flight RPC requests (`--rpc-concurrency`).
+    rpc_concurrency: usize,
+    /// The time limit of a single connection attempt (`--connect-timeout`).
+    connect_timeout: Duration,
+    /// The wait before retrying to connect to the node.
+    connect_backoff: Duration,
+    /// The connected client, shared by all of the [ExecutionContext::client]
+    /// calls of the command.
+    client: async_std::sync::Mutex<Option<Client<WebbRuntime>>>,
+    /// The non-fatal issues found while running the command, printed all
+    /// together once it finishes.
+    warnings: Mutex<Vec<String>>,
+    /// The ss58 format used to display the addresses (`--ss58-format`),
+    /// they are stored in the generic substrate format.
+    ss58_format: Option<u16>,
+    /// The ss58 format reported by the node, once we are connected to it.
+    chain_ss58_format: Mutex<Option<u16>>,
 }
 
 impl ExecutionContext {
-    pub fn new(
-        db: SledDatastore,
-        dirs: ProjectDirs,
-        rpc_url: url::Url,
-    ) -> Result<Self> {
+    /// A context connecting to the local node, see
+    /// [ExecutionContext::set_rpc_url] to use another one.
+    pub fn new(db: SledDatastore, home: PathBuf) -> Result<Self> {
+        let migrated = db.migrate_flat_keys(is_encrypted_key)?;
+        if migrated > 0 {
+            log::info!(
+                "moved {} datastore entries to their namespace",
+                migrated
+            );
+        }
+        let migrated = migrate_keyspaces(&db)?;
+        if migrated > 0 {
+            log::info!(
+                "moved {} datastore entries to their keyspace",
+                migrated
+            );
+        }
         let accounts = Self::load_accounts(&db)?;
-        let notes = Self::load_notes(&db)?;
+        let (notes, unreadable_notes) = Self::load_notes(&db)?;
         let context = Self {
             accounts,
             notes,
+            unreadable_notes,
             db,
-            dirs,
-            rpc_url,
+            home,
+            rpc_url: url::Url::parse(DEFAULT_RPC_URL)?,
+            output: OutputFormat::default(),
+            transcript: None,
+            chain_properties: None,
+            unsafe_mode: false,
+            prompts: true,
+            rpc_concurrency: DEFAULT_RPC_CONCURRENCY,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            connect_backoff: CONNECT_BACKOFF,
+            client: async_std::sync::Mutex::default(),
+            warnings: Mutex::default(),
+            ss58_format: None,
+            chain_ss58_format: Mutex::default(),
         };
         Ok(context)
     }
 
+    /// A context backed by a temporary datastore, unlocked using the
+    /// `password` password.
+    #[cfg(test)]
+    pub fn temporary() -> Self {
+        Self::temporary_with(SledDatastore::temporary().unwrap())
+    }
+
+    /// Like [ExecutionContext::temporary], but backed by the `db`.
+    #[cfg(test)]
+    fn temporary_with(db: SledDatastore) -> Self {
+        let dirs = directories_next::ProjectDirs::from(
+            crate::PACKAGE_ID[0],
+            crate::PACKAGE_ID[1],
+            crate::PACKAGE_ID[2],
+        )
+        .unwrap();
+        let home = crate::database::profile_dir(&dirs, "test").unwrap();
+        let mut context = Self::new(db, home).unwrap();
+        context.set_secret(SecretString::new(String::from("password")));
+        context
+    }
+
     pub fn default_account(&self) -> Result<&AccountRaw> {
         self.accounts
             .iter()
@@ -58,79 +158,461 @@ impl ExecutionContext {
             .context("must have a default account")
     }
 
-    pub fn signer(&self) -> Result<PairSigner<WebbRuntime, Sr25519Pair>> {
+    pub fn signer(&self) -> Result<AccountSigner> {
         let default_account = self.default_account()?;
-        let mut seed_key = default_account.uuid.clone();
-        seed_key.push_str("_seed");
-        let seed = self
-            .db
-            .read(seed_key.as_bytes())?
-            .context("signer encrypted seed")?;
-        let pair = Sr25519Pair::from_seed_slice(&seed).map_err(|_| {
-            anyhow::anyhow!("failed to create keypair from seed")
-        })?;
-        let signer = PairSigner::new(pair);
-        Ok(signer)
+        self.build_signer(default_account)
+    }
+
+    /// A signer for the saved account with the `alias_or_address` (or its
+    /// uuid), without changing the default account.
+    pub fn signer_for(&self, alias_or_address: &str) -> Result<AccountSigner> {
+        let account = self
+            .accounts
+            .iter()
+            .find(|acc| {
+                acc.matches(alias_or_address) || acc.uuid == alias_or_address
+            })
+            .with_context(|| {
+                format!(
+                    "no account with alias nor address equal to: {}",
+                    alias_or_address
+                )
+            })?;
+        self.build_signer(account).with_context(|| {
+            format!("incorrect password for the account {}!", account.alias)
+        })
+    }
+
+    fn build_signer(&self, account: &AccountRaw) -> Result<AccountSigner> {
+        let seed = self.stored_seed(&account.uuid)?;
+        let scheme = SeedScheme::from_i32(seed.scheme)
+            .and_then(SeedScheme::key_scheme)
+            .with_context(|| {
+                format!("unsupported account seed scheme #{}", seed.scheme)
+            })?;
+        let mut pair = SchemePair::from_seed_slice(scheme, &seed.bytes)?;
+        if !account.derivation_path.is_empty() {
+            pair = pair.derive(&account.derivation_path)?;
+        }
+        Ok(pair.signer())
+    }
+
+    /// Reads the stored seed of the account `uuid`, migrating a legacy raw
+    /// seed to a [StoredSeed] on the fly.
+    pub fn stored_seed(&self, uuid: &str) -> Result<StoredSeed> {
+        let seed_key = Keyspace::Account.key(format!("{}_seed", uuid));
+        let buf = self.db.read(seed_key)?.context("account encrypted seed")?;
+        let (seed, legacy) = StoredSeed::decode_or_legacy(&buf)?;
+        if legacy {
+            log::info!("migrating the account {} seed", uuid);
+            self.write_seed(uuid, &seed)?;
+        }
+        Ok(seed)
+    }
+
+    fn write_seed(&self, uuid: &str, seed: &StoredSeed) -> Result<()> {
+        let seed_key = Keyspace::Account.key(format!("{}_seed", uuid));
+        let mut buf = Vec::new();
+        prost::Message::encode(seed, &mut buf)?;
+        self.db.write(seed_key, buf)?;
+        Ok(())
+    }
+
+    /// Reads the stored mnemonic phrase of the account `uuid`, if it was
+    /// saved using `--store-phrase`.
+    pub fn stored_phrase(&self, uuid: &str) -> Result<Option<String>> {
+        let phrase_key = Keyspace::Account.key(format!("{}_phrase", uuid));
+        match self.db.read(phrase_key)? {
+            Some(buf) => Ok(Some(std::str::from_utf8(&buf)?.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    fn write_phrase(&self, uuid: &str, phrase: &str) -> Result<()> {
+        let phrase_key = Keyspace::Account.key(format!("{}_phrase", uuid));
+        self.db.write(phrase_key, phrase.as_bytes())?;
+        Ok(())
     }
 
-    pub fn home(&self) -> PathBuf { self.dirs.data_dir().to_path_buf() }
+    pub fn home(&self) -> PathBuf { self.home.clone() }
 
     pub fn accounts(&self) -> &[AccountRaw] { self.accounts.as_slice() }
 
     pub fn notes(&self) -> &[NoteRaw] { self.notes.as_slice() }
 
+    /// The saved notes that we failed to decode, as (uuid, reason).
+    pub fn unreadable_notes(&self) -> &[(String, String)] {
+        self.unreadable_notes.as_slice()
+    }
+
+    pub fn rpc_url(&self) -> &url::Url { &self.rpc_url }
+
+    /// Changes the node we connect to, dropping the cached client (if any)
+    /// since it is connected to the old one.
+    pub fn set_rpc_url(&mut self, rpc_url: url::Url) {
+        if rpc_url != self.rpc_url {
+            self.client.get_mut().take();
+            self.rpc_url = rpc_url;
+        }
+    }
+
+    /// The client connected to the node, only the first call connects, the
+    /// next ones reuse the same connection.
     pub async fn client(&self) -> Result<Client<WebbRuntime>> {
-        let client = subxt::ClientBuilder::new()
-            .set_url(self.rpc_url.as_str())
-            .build()
-            .await?;
+        let mut cached = self.client.lock().await;
+        if let Some(client) = &*cached {
+            return Ok(client.clone());
+        }
+        let client = self.connect().await?;
+        *cached = Some(client.clone());
+        Ok(client)
+    }
+
+    /// Drops the cached client, and connects to the node again.
+    pub async fn reconnect(&self) -> Result<Client<WebbRuntime>> {
+        let mut cached = self.client.lock().await;
+        cached.take();
+        let client = self.connect().await?;
+        *cached = Some(client.clone());
         Ok(client)
     }
 
+    /// Connects to the node, retrying with a backoff if it fails, so a
+    /// flaky node doesn't fail the whole command.
+    async fn connect(&self) -> Result<Client<WebbRuntime>> {
+        let connect = |attempt| async move {
+            log::debug!("connecting to {} (#{})", self.rpc_url, attempt);
+            let building = subxt::ClientBuilder::new()
+                .set_url(self.rpc_url.as_str())
+                .build();
+            match async_std::future::timeout(self.connect_timeout, building)
+                .await
+            {
+                Ok(client) => Ok(client?),
+                Err(_) => anyhow::bail!(
+                    "timed out after {}s",
+                    self.connect_timeout.as_secs_f32()
+                ),
+            }
+        };
+        utils::retry_with_backoff(
+            CONNECT_ATTEMPTS,
+            self.connect_backoff,
+            connect,
+        )
+        .await
+        .with_context(|| {
+            format!(
+                "connecting to {} failed {} times",
+                self.rpc_url, CONNECT_ATTEMPTS
+            )
+        })
+        .map(|client| {
+            // a node that doesn't report its properties gets subxt's
+            // defaults, which say nothing about its address format.
+            let props = client.properties();
+            if props != &subxt::SystemProperties::default() {
+                if let Ok(mut format) = self.chain_ss58_format.lock() {
+                    *format = Some(u16::from(props.ss58_format));
+                }
+            }
+            client
+        })
+    }
+
+    /// All of the mixer groups on the chain, along with their info, in the
+    /// order the chain lists them.
+    pub async fn fetch_mixers(
+        &self,
+    ) -> Result<Vec<(u32, MixerInfo<WebbRuntime>)>> {
+        let client = self.client().await?;
+        let ids = client
+            .fetch_or_default(
+                &MixerTreeIdsStore::<WebbRuntime>::default(),
+                None,
+            )
+            .await?;
+        let queries = ids
+            .into_iter()
+            .map(|id| async move { Ok((id, self.fetch_mixer(id).await?)) });
+        utils::fetch_concurrently(self.rpc_concurrency, queries)
+            .await
+            .into_iter()
+            .collect()
+    }
+
+    /// Deposits the `leaf` to the `mixer_id` group, signed by the `signer`.
+    ///
+    /// it returns once the deposit block is finalized, see
+    /// [transaction::sign_and_watch] for the `confirmations`.
+    pub async fn deposit(
+        &self,
+        mixer_id: u32,
+        leaf: ScalarData,
+        signer: &(dyn Signer<WebbRuntime> + Send + Sync),
+        confirmations: Option<u32>,
+    ) -> Result<DepositOutcome> {
+        let client = self.client().await?;
+        let rpc_client = self.rpc_client().await?;
+        let tx = transaction::sign_and_watch(
+            &client,
+            &rpc_client,
+            DepositCall::new(mixer_id, vec![leaf]),
+            signer,
+            confirmations,
+        )
+        .await?;
+        let block_number = client
+            .header(Some(tx.block_hash))
+            .await?
+            .context("reading the deposit block from network!")?
+            .number;
+        Ok(DepositOutcome {
+            xt_hash: tx.xt_hash,
+            block_hash: tx.block_hash,
+            block_number,
+        })
+    }
+
+    /// The info of the `id` mixer group.
+    pub async fn fetch_mixer(&self, id: u32) -> Result<MixerInfo<WebbRuntime>> {
+        self.client()
+            .await?
+            .fetch(&MixerTreesStore::<WebbRuntime>::new(id), None)
+            .await?
+            .with_context(|| format!("#{} Mixer Group not found!", id))
+    }
+
     pub async fn rpc_client(&self) -> Result<RpcClient> {
         let mut config = WsConfig::with_url(self.rpc_url.as_str());
         config.max_notifs_per_subscription = 4096;
         Ok(RpcClient::WebSocket(Arc::new(WsClient::new(config).await?)))
     }
 
+    /// Loads the chain properties from the JSON file at `path`, to be used
+    /// instead of the ones reported by the node.
+    pub fn load_chain_properties(&mut self, path: &Path) -> Result<()> {
+        let contents = std::fs::read_to_string(path)
+            .context("reading the chain properties file")?;
+        let props = serde_json::from_str(&contents).with_context(|| {
+            format!(
+                "malformed chain properties file {}, expected an object with \
+                 `ss58_format`, `token_decimals` and `token_symbol`",
+                path.display()
+            )
+        })?;
+        self.chain_properties = Some(props);
+        Ok(())
+    }
+
+    /// The chain properties, either the ones loaded from the chain
+    /// properties file, or the ones reported by the node if we are
+    /// connected, otherwise the defaults.
+    pub fn system_properties(
+        &self,
+        client: Option<&Client<WebbRuntime>>,
+    ) -> SystemProperties {
+        match (&self.chain_properties, client) {
+            (Some(props), _) => props.clone(),
+            (None, Some(client)) => SystemProperties::from(client.properties()),
+            (None, None) => {
+                self.warn(
+                    "using the default chain properties, pass \
+                     --chain-properties-file to override them",
+                );
+                SystemProperties::default()
+            },
+        }
+    }
+
+    /// Records a non-fatal `warning` about the running command, so it gets
+    /// reported along with the others once the command finishes.
+    pub fn warn(&self, warning: impl Into<String>) {
+        let warning = warning.into();
+        log::warn!("{}", warning);
+        if let Ok(mut warnings) = self.warnings.lock() {
+            warnings.push(warning);
+        }
+    }
+
+    /// Takes all of the warnings recorded so far.
+    pub fn take_warnings(&self) -> Vec<String> {
+        self.warnings
+            .lock()
+            .map(|mut warnings| std::mem::take(&mut *warnings))
+            .unwrap_or_default()
+    }
+
     pub fn has_secret(&self) -> bool { self.db.has_secret() }
 
+    pub fn secret(&self) -> Option<&SecretString> { self.db.secret() }
+
+    pub fn is_unsafe(&self) -> bool { self.unsafe_mode }
+
+    pub fn set_unsafe(&mut self, unsafe_mode: bool) {
+        self.unsafe_mode = unsafe_mode;
+    }
+
+    /// Whether the commands may prompt the user for the missing arguments,
+    /// otherwise a missing argument is an error, see
+    /// [crate::ext::ensure_can_prompt].
+    pub fn can_prompt(&self) -> bool { self.prompts }
+
+    /// Enables or disables the interactive prompts, they are disabled with
+    /// `--non-interactive` or when the output is meant to be parsed by
+    /// scripts.
+    pub fn set_prompts(&mut self, prompts: bool) { self.prompts = prompts; }
+
+    /// The maximum number of RPC requests the commands should issue
+    /// concurrently, see [crate::utils::fetch_concurrently].
+    pub fn rpc_concurrency(&self) -> usize { self.rpc_concurrency }
+
+    pub fn set_rpc_concurrency(&mut self, limit: usize) {
+        self.rpc_concurrency = limit;
+    }
+
+    pub fn set_ss58_format(&mut self, format: Option<u16>) {
+        self.ss58_format = format;
+    }
+
+    /// The ss58 format the addresses are displayed with, the first one of
+    /// these wins: `--ss58-format`, the `--chain-properties-file` one and
+    /// the one of the connected node.
+    ///
+    /// `None` (the generic substrate format) if the node is not connected,
+    /// so this never blocks on connecting to it.
+    pub fn ss58_format(&self) -> Option<u16> {
+        self.ss58_format
+            .or_else(|| {
+                let props = self.chain_properties.as_ref()?;
+                Some(u16::from(props.ss58_format))
+            })
+            .or_else(|| *self.chain_ss58_format.lock().ok()?)
+    }
+
+    /// The stored `address` in the ss58 format used for display, see
+    /// [ExecutionContext::ss58_format].
+    pub fn display_address(&self, address: &str) -> String {
+        match self.ss58_format() {
+            Some(format) => utils::reformat_address(address, format)
+                .unwrap_or_else(|_| address.to_owned()),
+            None => address.to_owned(),
+        }
+    }
+
+    /// The `account` with its address in the ss58 format used for display.
+    pub fn display_account(&self, account: &AccountRaw) -> AccountRaw {
+        AccountRaw {
+            address: self.display_address(&account.address),
+            ..account.clone()
+        }
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+    }
+
+    pub fn output(&self) -> OutputFormat { self.output }
+
+    pub fn set_output(&mut self, output: OutputFormat) { self.output = output; }
+
+    /// A handle to the terminal output, all the commands should write their
+    /// output through it so it gets captured in the transcript.
+    pub fn term(&self) -> Term { Term::new(self.transcript.clone()) }
+
+    /// Starts copying the commands output to the transcript file at `path`,
+    /// starting with the (redacted) command line.
+    pub fn set_transcript(&mut self, path: &Path) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context("open the transcript file")?;
+        let args = term::redact_args(std::env::args());
+        writeln!(file, "$ {}", args.join(" "))?;
+        self.transcript = Some(Arc::new(Mutex::new(file)));
+        Ok(())
+    }
+
     pub fn set_secret(&mut self, secret: SecretString) {
         self.db.set_secret(secret)
     }
 
+    /// Makes the account with the `alias_or_address` the default one.
+    ///
+    /// only the previous and the new default accounts are written, and
+    /// nothing is touched if there is no such account.
     pub fn set_default_account(
         &mut self,
         alias_or_address: &str,
-    ) -> Result<bool> {
-        let mut changed = false;
-        // let's loop over all the accounts
-        for acc in &mut self.accounts {
-            // first we set any account as not default.
-            acc.is_default = false;
-            let alias_match = acc.alias == alias_or_address;
-            let address_match = acc.address == alias_or_address;
-            let matched = alias_match || address_match;
-            // we found it!
-            if matched && !changed {
-                // set it to default account
-                acc.is_default = true;
-                // and mark it as changed.
-                changed = true;
+    ) -> Result<()> {
+        let target = self
+            .accounts
+            .iter()
+            .position(|acc| acc.matches(alias_or_address))
+            .with_context(|| {
+                format!(
+                    "no account with alias nor address equal to: {}",
+                    alias_or_address
+                )
+            })?;
+        for (i, acc) in self.accounts.iter_mut().enumerate() {
+            let is_default = i == target;
+            if acc.is_default == is_default {
+                continue;
             }
-            // save any changes to the database.
+            acc.is_default = is_default;
             let mut buf = Vec::new();
             prost::Message::encode(acc, &mut buf)?;
-            self.db.write_plaintext(acc.uuid.as_bytes(), buf)?;
+            self.db
+                .write_plaintext(Keyspace::Account.key(&acc.uuid), buf)?;
         }
-        Ok(changed)
+        Ok(())
     }
 
+    /// Renames the account matching `alias_or_address` to `new_alias`.
+    ///
+    /// The account is keyed by its uuid, so it stays the default account if
+    /// it was, and its seed and anything referencing it remain valid.
+    pub fn rename_account(
+        &mut self,
+        alias_or_address: &str,
+        new_alias: String,
+    ) -> Result<bool> {
+        let maybe_account = self
+            .accounts
+            .iter()
+            .position(|acc| acc.matches(alias_or_address));
+        let i = match maybe_account {
+            Some(i) => i,
+            None => return Ok(false),
+        };
+        let taken = self
+            .accounts
+            .iter()
+            .enumerate()
+            .any(|(j, acc)| j != i && acc.alias == new_alias);
+        if taken {
+            anyhow::bail!("there is already an account named {}", new_alias);
+        }
+        let acc = &mut self.accounts[i];
+        acc.alias = new_alias;
+        let mut buf = Vec::new();
+        prost::Message::encode(acc, &mut buf)?;
+        self.db
+            .write_plaintext(Keyspace::Account.key(&acc.uuid), buf)?;
+        Ok(true)
+    }
+
+    /// Generates a new account, if `store_phrase` is set the mnemonic phrase
+    /// is also saved (encrypted) so it could be exported later.
     pub fn generate_account(
         &mut self,
         alias: String,
-    ) -> Result<(PublicFor<Sr25519Pair>, String)> {
-        let (account, paper_key) = account::generate(alias);
+        scheme: KeyScheme,
+        store_phrase: bool,
+    ) -> Result<(AccountId32, String)> {
+        let (account, paper_key) = account::generate(alias, scheme);
         let address = account.address;
         let uuid = account.uuid.to_string();
         let mut raw = AccountRaw {
@@ -138,6 +620,8 @@ impl ExecutionContext {
             address: address.to_string(),
             uuid: account.uuid.to_string(),
             is_default: false,
+            derivation_path: account.derivation_path.unwrap_or_default(),
+            scheme: SeedScheme::from(account.scheme) as i32,
         };
         // if we don't have any accounts
         if self.accounts.is_empty() {
@@ -147,32 +631,39 @@ impl ExecutionContext {
 
         let mut buf = Vec::new();
         prost::Message::encode(&raw, &mut buf)?;
-        self.db.write_plaintext(uuid.as_bytes(), buf)?;
-        let mut seed_key = uuid.clone();
-        seed_key.push_str("_seed");
-        self.db.write(seed_key.as_bytes(), &account.seed)?;
+        self.db.write_plaintext(Keyspace::Account.key(&uuid), buf)?;
+        let seed =
+            StoredSeed::new(account.scheme.into(), account.seed.to_vec());
+        self.write_seed(&uuid, &seed)?;
+        if store_phrase {
+            self.write_phrase(&uuid, &paper_key)?;
+        }
         // save the account to account ids.
-        let maybe_ids = self.db.read_plaintext(b"account_ids")?;
-        let v = match maybe_ids {
-            Some(b) => {
-                let mut v: AccountsIds = prost::Message::decode(b.as_ref())?;
-                v.ids.push(uuid);
-                v
-            },
-            None => AccountsIds { ids: vec![uuid] },
-        };
-        let mut buf = Vec::new();
-        prost::Message::encode(&v, &mut buf)?;
-        self.db.write_plaintext(b"account_ids", buf)?;
+        update_ids(&self.db, &Keyspace::Index.key("account_ids"), |ids| {
+            push_id(ids, uuid.clone())
+        })?;
+        self.accounts.push(raw);
         Ok((address, paper_key))
     }
 
+    /// Imports an account, if `store_phrase` is set the mnemonic phrase
+    /// is also saved (encrypted) so it could be exported later.
+    ///
+    /// the account is derived with the `derivation_path`, if any, which is
+    /// saved along with it.
     pub fn import_account(
         &mut self,
         alias: String,
+        scheme: KeyScheme,
         paper_key: Mnemonic,
-    ) -> Result<PublicFor<Sr25519Pair>> {
-        let account = account::restore(alias, paper_key.phrase())?;
+        derivation_path: Option<&str>,
+        store_phrase: bool,
+    ) -> Result<AccountId32> {
+        let input = match derivation_path {
+            Some(path) => format!("{}{}", paper_key.phrase(), path),
+            None => paper_key.phrase().to_owned(),
+        };
+        let account = account::restore(alias, scheme, &input)?;
         let address = account.address;
         let uuid = account.uuid.to_string();
         let mut raw = AccountRaw {
@@ -180,6 +671,8 @@ impl ExecutionContext {
             address: address.to_string(),
             uuid: account.uuid.to_string(),
             is_default: false,
+            derivation_path: account.derivation_path.unwrap_or_default(),
+            scheme: SeedScheme::from(account.scheme) as i32,
         };
         // if we don't have any accounts
         if self.accounts.is_empty() {
@@ -189,145 +682,754 @@ impl ExecutionContext {
 
         let mut buf = Vec::new();
         prost::Message::encode(&raw, &mut buf)?;
-        self.db.write_plaintext(uuid.as_bytes(), buf)?;
-        let mut seed_key = uuid.clone();
-        seed_key.push_str("_seed");
-        self.db.write(seed_key.as_bytes(), &account.seed)?;
+        self.db.write_plaintext(Keyspace::Account.key(&uuid), buf)?;
+        let seed =
+            StoredSeed::new(account.scheme.into(), account.seed.to_vec());
+        self.write_seed(&uuid, &seed)?;
+        if store_phrase {
+            self.write_phrase(&uuid, paper_key.phrase())?;
+        }
         // save the account to account ids.
-        let maybe_ids = self.db.read_plaintext(b"account_ids")?;
-        let v = match maybe_ids {
-            Some(b) => {
-                let mut v: AccountsIds = prost::Message::decode(b.as_ref())?;
-                v.ids.push(uuid);
-                v
-            },
-            None => AccountsIds { ids: vec![uuid] },
-        };
-        let mut buf = Vec::new();
-        prost::Message::encode(&v, &mut buf)?;
-        self.db.write_plaintext(b"account_ids", buf)?;
+        update_ids(&self.db, &Keyspace::Index.key("account_ids"), |ids| {
+            push_id(ids, uuid.clone())
+        })?;
+        self.accounts.push(raw);
         Ok(address)
     }
 
+    /// Generates a new note for the `mixer_id` group and saves it along with
+    /// the resolved mixer `metadata`, returning the generated note.
     pub fn generate_note(
         &mut self,
         alias: String,
         mixer_id: u32,
         token_symbol: TokenSymbol,
-    ) -> Result<()> {
+        metadata: NoteMetadata,
+    ) -> Result<Note> {
         let mut mixer = Mixer::new(mixer_id);
         let note = mixer.generate_note(token_symbol);
-        self.import_note(alias, note)?;
-        Ok(())
+        self.save_note(alias, note.clone(), Some(metadata))?;
+        Ok(note)
     }
 
+    /// Saves an imported note, after making sure it is a valid one.
     pub fn import_note(&mut self, alias: String, note: Note) -> Result<u32> {
+        note.validate().context("the imported note is invalid")?;
+        self.save_note(alias, note, None)
+    }
+
+    /// Saves the `note`, its `alias` must not be taken by another note.
+    fn save_note(
+        &mut self,
+        alias: String,
+        note: Note,
+        metadata: Option<NoteMetadata>,
+    ) -> Result<u32> {
+        if self.has_note_alias(&alias) {
+            anyhow::bail!("there is already a note named {}", alias);
+        }
         let uuid = uuid::Uuid::new_v4();
-        let raw = NoteRaw {
+        let mut raw = NoteRaw {
             alias,
             mixer_id: note.mixer_id,
             token_symbol: note.token_symbol.to_string(),
             uuid: uuid.to_string(),
             used: false,
+            ..Default::default()
         };
+        if let Some(metadata) = metadata {
+            raw.amount = metadata.amount.to_string();
+            raw.decimals = metadata.decimals.into();
+            raw.currency_id = Some(metadata.currency_id);
+            raw.chain = metadata.chain;
+        } else if let Some((amount, decimals)) = note.base_amount() {
+            // a v2 note carries its amount, so an imported one is shown
+            // (and checked at deposit) like a generated one.
+            raw.amount = amount.to_string();
+            raw.decimals = decimals.into();
+        }
         let mut buf = Vec::new();
         prost::Message::encode(&raw, &mut buf)?;
-        self.db.write_plaintext(uuid.to_string().as_bytes(), buf)?;
-        let mut secret_key = uuid.to_string();
-        secret_key.push_str("_secret");
+        self.db
+            .write_plaintext(Keyspace::Note.key(uuid.to_string()), buf)?;
+        let secret_key = Keyspace::Note.key(format!("{}_secret", uuid));
         let note_secret = note.to_string().into_bytes();
-        self.db.write(secret_key.as_bytes(), note_secret)?;
-        let maybe_ids = self.db.read_plaintext(b"notes_ids")?;
-        let v = match maybe_ids {
-            Some(b) => {
-                let mut v: NotesIds = prost::Message::decode(b.as_ref())?;
-                v.ids.push(uuid.to_string());
-                v
-            },
-            None => NotesIds {
-                ids: vec![uuid.to_string()],
+        self.db.write(secret_key, note_secret)?;
+        update_ids(&self.db, &Keyspace::Index.key("notes_ids"), |ids| {
+            push_id(ids, uuid.to_string())
+        })?;
+        let mixer_id = raw.mixer_id;
+        self.notes.push(raw);
+        Ok(mixer_id)
+    }
+
+    pub fn decrypt_note(&self, uuid: String) -> Result<Note> {
+        let key = Keyspace::Note.key(format!("{}_secret", uuid));
+        let buf = self.db.read(key)?.context("finding the encrypted note")?;
+        let note = std::str::from_utf8(&buf)?.parse()?;
+        Ok(note)
+    }
+
+    /// Finds the saved note with the `uuid`, or else the one with the
+    /// `alias`, `Ok(None)` if neither is given.
+    ///
+    /// fails if there is no such note, or with an [AmbiguousAlias] if more
+    /// than one note has the `alias`.
+    pub fn find_note(
+        &self,
+        alias: Option<&str>,
+        uuid: Option<&str>,
+    ) -> Result<Option<NoteRaw>> {
+        let note = match (uuid, alias) {
+            (Some(uuid), _) => self
+                .notes
+                .iter()
+                .find(|note| note.uuid == uuid)
+                .cloned()
+                .with_context(|| format!("no note with the uuid {}", uuid))?,
+            (None, Some(alias)) => {
+                note_with_alias(self.notes.iter().cloned(), alias)?
+                    .with_context(|| format!("no note named {}", alias))?
             },
+            (None, None) => return Ok(None),
         };
-        let mut buf = Vec::new();
-        prost::Message::encode(&v, &mut buf)?;
-        self.db.write_plaintext(b"notes_ids", buf)?;
-        Ok(raw.mixer_id)
+        Ok(Some(note))
     }
 
-    pub fn decrypt_note(&self, uuid: String) -> Result<Note> {
-        let mut key = uuid;
-        key.push_str("_secret");
-        let buf = self
+    /// Whether a saved note already has the `alias`.
+    pub fn has_note_alias(&self, alias: &str) -> bool {
+        self.notes.iter().any(|note| note.alias == alias)
+    }
+
+    /// The first `<alias>-N` that no saved note has, to suggest instead of
+    /// a taken `alias`.
+    pub fn free_note_alias(&self, alias: &str) -> String {
+        (2..)
+            .map(|i| format!("{}-{}", alias, i))
+            .find(|candidate| !self.has_note_alias(candidate))
+            .expect("there are less notes than numbers")
+    }
+
+    /// Changes the alias of the note `uuid`, only its metadata is touched,
+    /// the encrypted note secret stays as is.
+    pub fn rename_note(&mut self, uuid: &str, new_alias: String) -> Result<()> {
+        let taken = self
+            .notes
+            .iter()
+            .any(|note| note.uuid != uuid && note.alias == new_alias);
+        if taken {
+            anyhow::bail!("there is already a note named {}", new_alias);
+        }
+        let metadata = self
             .db
-            .read(key.as_bytes())?
-            .context("finding the encrypted note")?;
-        let note_str = String::from_utf8(buf.to_vec())?;
-        let note = note_str.parse()?;
-        Ok(note)
+            .read_plaintext(Keyspace::Note.key(uuid))?
+            .context("reading note metadata")?;
+        let mut note: NoteRaw = prost::Message::decode(metadata.as_ref())?;
+        note.alias = new_alias;
+
+        let mut buf = Vec::new();
+        prost::Message::encode(&note, &mut buf)?;
+        self.db.write_plaintext(Keyspace::Note.key(uuid), buf)?;
+        if let Some(cached) = self.notes.iter_mut().find(|n| n.uuid == uuid) {
+            *cached = note;
+        }
+        Ok(())
+    }
+
+    /// Changes the datastore password from `old` to `new`, re-encrypting
+    /// all of the saved seeds, phrases and notes.
+    ///
+    /// nothing changes unless every one of them is decrypted using the `old`
+    /// password. returns the number of re-encrypted entries.
+    pub fn change_password(
+        &mut self,
+        old: SecretString,
+        new: SecretString,
+    ) -> Result<usize> {
+        self.db.reencrypt(old, new)
+    }
+
+    /// Re-encrypts the secret of the note `uuid`, that was encrypted using
+    /// the `old` password, with the current password.
+    pub fn fix_note_encryption(
+        &self,
+        uuid: &str,
+        old: SecretString,
+    ) -> Result<()> {
+        let key = Keyspace::Note.key(format!("{}_secret", uuid));
+        let found = self.db.reencrypt_from(key, old)?;
+        if !found {
+            anyhow::bail!("the note {} has no saved secret", uuid);
+        }
+        Ok(())
     }
 
     pub fn mark_note_as_used(&mut self, uuid: String) -> Result<()> {
         let metadata = self
             .db
-            .read_plaintext(uuid.as_bytes())?
+            .read_plaintext(Keyspace::Note.key(&uuid))?
             .context("reading note metadata")?;
         let mut note: NoteRaw = prost::Message::decode(metadata.as_ref())?;
         note.used = true;
 
         let mut buf = Vec::new();
         prost::Message::encode(&note, &mut buf)?;
-        self.db.write_plaintext(uuid.as_bytes(), buf)?;
+        self.db.write_plaintext(Keyspace::Note.key(uuid), buf)?;
+        Ok(())
+    }
+
+    /// The last mixer group selected while generating a note, if any.
+    pub fn last_mixer(&self) -> Result<Option<u32>> {
+        let maybe_id =
+            self.db.read_plaintext(Keyspace::Config.key("last_mixer"))?;
+        let id = match maybe_id {
+            Some(v) => Some(String::from_utf8(v.to_vec())?.parse()?),
+            None => None,
+        };
+        Ok(id)
+    }
+
+    /// Remembers (or clears) the last selected mixer group.
+    pub fn set_last_mixer(&self, mixer_id: Option<u32>) -> Result<()> {
+        match mixer_id {
+            Some(id) => self.db.write_plaintext(
+                Keyspace::Config.key("last_mixer"),
+                id.to_string().as_bytes(),
+            )?,
+            None => self.db.remove(Keyspace::Config.key("last_mixer"))?,
+        };
         Ok(())
     }
 
-    pub fn forget_note(&self, uuid: String) -> Result<()> {
-        self.db.remove(uuid.as_bytes())?;
-        let mut key = uuid;
-        key.push_str("_secret");
-        self.db.remove(key.as_bytes())?;
+    /// The uuid of the last note selected for a deposit, if any.
+    pub fn last_note(&self) -> Result<Option<String>> {
+        let maybe_uuid =
+            self.db.read_plaintext(Keyspace::Config.key("last_note"))?;
+        let uuid = match maybe_uuid {
+            Some(v) => Some(String::from_utf8(v.to_vec())?),
+            None => None,
+        };
+        Ok(uuid)
+    }
+
+    /// Remembers (or clears) the last note selected for a deposit.
+    pub fn set_last_note(&self, uuid: Option<&str>) -> Result<()> {
+        match uuid {
+            Some(uuid) => self.db.write_plaintext(
+                Keyspace::Config.key("last_note"),
+                uuid.as_bytes(),
+            )?,
+            None => self.db.remove(Keyspace::Config.key("last_note"))?,
+        };
         Ok(())
     }
 
+    /// Removes the account metadata, its seed (and phrase, if stored) and
+    /// drops it from the accounts index.
+    ///
+    /// If it was the default account, another account gets promoted to be
+    /// the default one, and it is returned.
+    pub fn forget_account(&mut self, uuid: &str) -> Result<Option<AccountRaw>> {
+        update_ids(&self.db, &Keyspace::Index.key("account_ids"), |ids| {
+            ids.retain(|id| id != uuid)
+        })?;
+        self.db.remove(Keyspace::Account.key(uuid))?;
+        self.db
+            .remove(Keyspace::Account.key(format!("{}_seed", uuid)))?;
+        self.db
+            .remove(Keyspace::Account.key(format!("{}_phrase", uuid)))?;
+        self.accounts = Self::load_accounts(&self.db)?;
+        if self.accounts.is_empty() || self.default_account().is_ok() {
+            return Ok(None);
+        }
+        let promoted = self.accounts[0].address.clone();
+        self.set_default_account(&promoted)?;
+        Ok(self.default_account().ok().cloned())
+    }
+
+    /// Removes the note metadata and its secret, and drops it from the
+    /// notes index so we don't try to load it again.
+    pub fn forget_note(&mut self, uuid: String) -> Result<()> {
+        update_ids(&self.db, &Keyspace::Index.key("notes_ids"), |ids| {
+            ids.retain(|id| id != &uuid)
+        })?;
+        if self.last_note()?.as_ref() == Some(&uuid) {
+            self.set_last_note(None)?;
+        }
+        self.notes.retain(|n| n.uuid != uuid);
+        self.db.remove(Keyspace::Note.key(&uuid))?;
+        self.db
+            .remove(Keyspace::Note.key(format!("{}_secret", uuid)))?;
+        Ok(())
+    }
+
+    /// Exports all the accounts and notes into a [Backup], their secrets
+    /// are exported as they are stored, still encrypted.
+    pub fn export_backup(&self) -> Result<Backup> {
+        let mut backup = Backup {
+            version: Backup::VERSION,
+            kdf: self.db.kdf().encode(),
+            ..Default::default()
+        };
+        for account in &self.accounts {
+            let uuid = &account.uuid;
+            let seed = self
+                .db
+                .read_sealed(Keyspace::Account.key(format!("{}_seed", uuid)))?
+                .with_context(|| format!("account {} seed", account.alias))?;
+            let phrase = self.db.read_sealed(
+                Keyspace::Account.key(format!("{}_phrase", uuid)),
+            )?;
+            backup.accounts.push(BackupAccount {
+                account: Some(account.clone()),
+                seed: seed.to_vec(),
+                phrase: phrase.map(|p| p.to_vec()).unwrap_or_default(),
+            });
+        }
+        for note in &self.notes {
+            let secret = self
+                .db
+                .read_sealed(
+                    Keyspace::Note.key(format!("{}_secret", note.uuid)),
+                )?
+                .with_context(|| format!("note {} secret", note.alias))?;
+            backup.notes.push(BackupNote {
+                note: Some(note.clone()),
+                secret: secret.to_vec(),
+            });
+        }
+        Ok(backup)
+    }
+
+    /// Merges the accounts and notes of the `backup` into the datastore,
+    /// the ones we already have (by uuid) are skipped.
+    ///
+    /// a note whose alias is taken is imported as `<alias>-N`, see
+    /// [BackupImport::renamed].
+    ///
+    /// the imported accounts never replace our default account.
+    pub fn import_backup(&mut self, backup: Backup) -> Result<BackupImport> {
+        if backup.version > Backup::VERSION {
+            anyhow::bail!(
+                "the backup format version {} is newer than the supported {}, \
+                 try upgrading webb",
+                backup.version,
+                Backup::VERSION
+            );
+        }
+        let kdf = Kdf::decode(&backup.kdf).context("the backup kdf")?;
+        let mut summary = BackupImport::default();
+        let mut sealed = Vec::new();
+        let mut accounts = Vec::new();
+        let mut has_default = self.default_account().is_ok();
+        for entry in backup.accounts {
+            let mut account = entry.account.context("missing account")?;
+            if self.accounts.iter().any(|a| a.uuid == account.uuid) {
+                summary.skipped += 1;
+                continue;
+            }
+            let uuid = account.uuid.clone();
+            sealed.push((
+                Keyspace::Account.key(format!("{}_seed", uuid)),
+                entry.seed,
+            ));
+            if !entry.phrase.is_empty() {
+                sealed.push((
+                    Keyspace::Account.key(format!("{}_phrase", uuid)),
+                    entry.phrase,
+                ));
+            }
+            account.is_default = account.is_default && !has_default;
+            has_default |= account.is_default;
+            accounts.push(account);
+        }
+        let mut notes = Vec::new();
+        for entry in backup.notes {
+            let mut note = entry.note.context("missing note")?;
+            if self.notes.iter().any(|n| n.uuid == note.uuid) {
+                summary.skipped += 1;
+                continue;
+            }
+            let taken = |alias: &str| {
+                self.has_note_alias(alias)
+                    || notes.iter().any(|n: &NoteRaw| n.alias == alias)
+            };
+            if taken(&note.alias) {
+                let free = (2..)
+                    .map(|i| format!("{}-{}", note.alias, i))
+                    .find(|candidate| !taken(candidate))
+                    .expect("there are less notes than numbers");
+                let alias = std::mem::replace(&mut note.alias, free.clone());
+                summary.renamed.push((alias, free));
+            }
+            sealed.push((
+                Keyspace::Note.key(format!("{}_secret", note.uuid)),
+                entry.secret,
+            ));
+            notes.push(note);
+        }
+        // the secrets go first, so a wrong password fails the import
+        // before any metadata is written.
+        self.db.write_sealed_from(&kdf, &sealed)?;
+        for account in accounts {
+            let mut buf = Vec::new();
+            account.encode(&mut buf)?;
+            self.db
+                .write_plaintext(Keyspace::Account.key(&account.uuid), buf)?;
+            update_ids(&self.db, &Keyspace::Index.key("account_ids"), |ids| {
+                push_id(ids, account.uuid.clone())
+            })?;
+            self.accounts.push(account);
+            summary.accounts += 1;
+        }
+        for note in notes {
+            let mut buf = Vec::new();
+            note.encode(&mut buf)?;
+            self.db
+                .write_plaintext(Keyspace::Note.key(&note.uuid), buf)?;
+            update_ids(&self.db, &Keyspace::Index.key("notes_ids"), |ids| {
+                push_id(ids, note.uuid.clone())
+            })?;
+            self.notes.push(note);
+            summary.notes += 1;
+        }
+        Ok(summary)
+    }
+
+    /// Checks every record listed in the accounts and notes indexes, the
+    /// encrypted ones are only checked if we have the password.
+    ///
+    /// nothing is printed or returned of the secrets themselves, only
+    /// whether they could be read.
+    pub fn verify_records(&self) -> Result<Vec<RecordCheck>> {
+        let decrypt = self.has_secret();
+        let mut checks = Vec::new();
+        let mut check = |record: String, result: Result<()>| {
+            checks.push(RecordCheck {
+                record,
+                problem: result.err().map(|e| format!("{:#}", e)),
+            });
+        };
+        let ids = |key: &str| -> Result<Vec<String>> {
+            match self.db.read_plaintext(Keyspace::Index.key(key))? {
+                Some(buf) => Ok(NotesIds::decode(buf.as_ref())?.ids),
+                None => Ok(Vec::new()),
+            }
+        };
+        for uuid in ids("account_ids").context("the accounts index")? {
+            let metadata = self
+                .db
+                .read_plaintext(Keyspace::Account.key(&uuid))
+                .and_then(|m| m.context("missing metadata"))
+                .and_then(|m| Ok(AccountRaw::decode(m.as_ref())?));
+            check(format!("account {}", uuid), metadata.map(|_| ()));
+            if !decrypt {
+                continue;
+            }
+            let seed_key = Keyspace::Account.key(format!("{}_seed", uuid));
+            let seed = self
+                .db
+                .read(seed_key)
+                .and_then(|s| s.context("missing seed"))
+                .and_then(|s| Ok(StoredSeed::decode_or_legacy(&s)?));
+            check(format!("account {} seed", uuid), seed.map(|_| ()));
+            let phrase_key = Keyspace::Account.key(format!("{}_phrase", uuid));
+            if self.db.read_sealed(phrase_key.clone())?.is_some() {
+                let phrase = self.db.read(phrase_key).map(|_| ());
+                check(format!("account {} phrase", uuid), phrase);
+            }
+        }
+        for uuid in ids("notes_ids").context("the notes index")? {
+            let metadata = self
+                .db
+                .read_plaintext(Keyspace::Note.key(&uuid))
+                .and_then(|m| m.context("missing metadata"))
+                .and_then(|m| Ok(NoteRaw::decode(m.as_ref())?));
+            check(format!("note {}", uuid), metadata.map(|_| ()));
+            if !decrypt {
+                continue;
+            }
+            let secret = self.decrypt_note(uuid.clone()).map(|_| ());
+            check(format!("note {} secret", uuid), secret);
+        }
+        Ok(checks)
+    }
+
     fn load_accounts(db: &SledDatastore) -> Result<Vec<AccountRaw>> {
-        let maybe_ids = db.read_plaintext(b"account_ids")?;
+        let index_key = Keyspace::Index.key("account_ids");
+        let maybe_ids = db.read_plaintext(index_key.clone())?;
         if let Some(ids) = maybe_ids {
             let AccountsIds { ids } = prost::Message::decode(ids.as_ref())?;
             let mut result = Vec::new();
+            let mut stale = Vec::new();
             for id in ids {
-                let maybe_metadata = db.read_plaintext(id.as_bytes())?;
+                let maybe_metadata =
+                    db.read_plaintext(Keyspace::Account.key(&id))?;
                 let account: AccountRaw = match maybe_metadata {
                     Some(m) => prost::Message::decode(m.as_ref())?,
-                    None => continue,
+                    None => {
+                        stale.push(id);
+                        continue;
+                    },
                 };
                 result.push(account);
             }
+            prune_ids(db, &index_key, &stale)?;
             Ok(result)
         } else {
             Ok(Vec::new())
         }
     }
 
-    fn load_notes(db: &SledDatastore) -> Result<Vec<NoteRaw>> {
-        let maybe_ids = db.read_plaintext(b"notes_ids")?;
+    /// Loads all the saved notes, a note that we fail to decode does not
+    /// fail the loading, instead it is returned as (uuid, reason).
+    #[allow(clippy::type_complexity)]
+    fn load_notes(
+        db: &SledDatastore,
+    ) -> Result<(Vec<NoteRaw>, Vec<(String, String)>)> {
+        let index_key = Keyspace::Index.key("notes_ids");
+        let maybe_ids = db.read_plaintext(index_key.clone())?;
         if let Some(ids) = maybe_ids {
             let NotesIds { ids } = prost::Message::decode(ids.as_ref())?;
             let mut result = Vec::new();
+            let mut unreadable = Vec::new();
+            let mut stale = Vec::new();
             for id in ids {
-                let maybe_metadata = db.read_plaintext(id.as_bytes())?;
-                let note: NoteRaw = match maybe_metadata {
-                    Some(m) => prost::Message::decode(m.as_ref())?,
-                    None => continue,
+                let maybe_metadata =
+                    db.read_plaintext(Keyspace::Note.key(&id))?;
+                let decoded = match maybe_metadata {
+                    Some(m) => prost::Message::decode(m.as_ref()),
+                    None => {
+                        stale.push(id);
+                        continue;
+                    },
                 };
-                result.push(note);
+                match decoded {
+                    Ok(note) => result.push(note),
+                    Err(e) => {
+                        log::warn!("failed to decode note {}: {}", id, e);
+                        unreadable.push((id, e.to_string()));
+                    },
+                }
             }
-            Ok(result)
+            prune_ids(db, &index_key, &stale)?;
+            Ok((result, unreadable))
         } else {
-            Ok(Vec::new())
+            Ok((Vec::new(), Vec::new()))
         }
     }
 }
 
+/// A finalized deposit, see [ExecutionContext::deposit].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepositOutcome {
+    /// The deposit transaction hash.
+    pub xt_hash: Hash,
+    /// The hash of the block that included the deposit.
+    pub block_hash: Hash,
+    /// The number of the block that included the deposit.
+    pub block_number: u32,
+}
+
+/// What got imported from a [Backup], see [ExecutionContext::import_backup].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupImport {
+    /// The number of imported accounts.
+    pub accounts: usize,
+    /// The number of imported notes.
+    pub notes: usize,
+    /// The number of accounts and notes we already had.
+    pub skipped: usize,
+    /// The notes imported under another alias, since theirs was taken, as
+    /// `(alias, new alias)`.
+    pub renamed: Vec<(String, String)>,
+}
+
+/// The result of checking a single record, see
+/// [ExecutionContext::verify_records].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordCheck {
+    /// Which record was checked, i.e `note <uuid> secret`.
+    pub record: String,
+    /// Why the record is unreadable, `None` if it is fine.
+    pub problem: Option<String>,
+}
+
+/// Several saved notes share the alias we looked for, so it doesn't tell
+/// which one to use.
 #[derive(Debug, Clone)]
+pub struct AmbiguousAlias {
+    pub alias: String,
+    pub notes: Vec<NoteRaw>,
+}
+
+impl std::fmt::Display for AmbiguousAlias {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let uuids: Vec<_> = self.notes.iter().map(|n| &n.uuid[..]).collect();
+        write!(
+            f,
+            "{} notes are named {}, use `--uuid` to pick one of: {}",
+            self.notes.len(),
+            self.alias,
+            uuids.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for AmbiguousAlias {}
+
+/// The note among the `notes` with the `alias`, if any.
+pub fn note_with_alias(
+    notes: impl IntoIterator<Item = NoteRaw>,
+    alias: &str,
+) -> std::result::Result<Option<NoteRaw>, AmbiguousAlias> {
+    let mut matching: Vec<_> =
+        notes.into_iter().filter(|n| n.alias == alias).collect();
+    match matching.len() {
+        0 => Ok(None),
+        1 => Ok(matching.pop()),
+        _ => Err(AmbiguousAlias {
+            alias: alias.to_owned(),
+            notes: matching,
+        }),
+    }
+}
+
+/// The mixer metadata resolved while generating a note.
+///
+/// It is saved alongside the note, so later operations don't need
+/// to resolve them again.
+#[derive(Debug, Clone)]
+pub struct NoteMetadata {
+    /// The mixer deposit size in base units.
+    pub amount: u128,
+    /// The token decimals used to display the `amount`.
+    pub decimals: u8,
+    /// The mixer currency id.
+    pub currency_id: u64,
+    /// The name of the chain the note was generated for.
+    pub chain: String,
+}
+
+/// Whether the datastore `key` holds an encrypted value, i.e the account
+/// seeds and phrases and the notes secrets.
+fn is_encrypted_key(key: &[u8]) -> bool {
+    [&b"_seed"[..], b"_phrase", b"_secret"]
+        .iter()
+        .any(|suffix| key.ends_with(suffix))
+}
+
+/// Moves the entries saved before the keys got a [Keyspace] to theirs.
+///
+/// the accounts and notes metadata are both keyed by a bare uuid, so the
+/// (legacy) ids indexes tell them apart.
+fn migrate_keyspaces(db: &SledDatastore) -> Result<usize> {
+    let legacy_ids = |key: &[u8]| -> Result<Vec<String>> {
+        match db.read_plaintext(key)? {
+            Some(buf) => Ok(NotesIds::decode(buf.as_ref())?.ids),
+            None => Ok(Vec::new()),
+        }
+    };
+    let account_ids = legacy_ids(b"account_ids")?;
+    let notes_ids = legacy_ids(b"notes_ids")?;
+    let is_one_of =
+        |ids: &[String], key: &[u8]| ids.iter().any(|id| id.as_bytes() == key);
+    db.migrate_keyspaces(|key| match key {
+        b"account_ids" | b"notes_ids" => Some(Keyspace::Index),
+        b"last_mixer" | b"last_note" => Some(Keyspace::Config),
+        _ if key.ends_with(b"_seed") || key.ends_with(b"_phrase") => {
+            Some(Keyspace::Account)
+        },
+        _ if key.ends_with(b"_secret") => Some(Keyspace::Note),
+        _ if is_one_of(&account_ids, key) => Some(Keyspace::Account),
+        _ if is_one_of(&notes_ids, key) => Some(Keyspace::Note),
+        _ => None,
+    })
+}
+
+/// Updates the ids index (`account_ids` or `notes_ids`) under `key` using
+/// `f`.
+///
+/// `f` is always applied to the index currently on disk (and not to our,
+/// possibly stale, in-memory view of it), atomically, so an id added or
+/// removed concurrently is never clobbered.
+fn update_ids(
+    db: &SledDatastore,
+    key: &[u8],
+    mut f: impl FnMut(&mut Vec<String>),
+) -> Result<()> {
+    let mut error = None;
+    db.update_plaintext(key, |current| {
+        // both indexes have the same encoding, so `NotesIds` is used here
+        // for the accounts too.
+        let mut index = match current.map(NotesIds::decode) {
+            Some(Ok(index)) => index,
+            Some(Err(e)) => {
+                // keep it as is, and report the error.
+                error = Some(e);
+                return current.map(<[u8]>::to_vec);
+            },
+            None => NotesIds::default(),
+        };
+        f(&mut index.ids);
+        let mut buf = Vec::new();
+        index.encode(&mut buf).expect("a vec has enough capacity");
+        Some(buf)
+    })?;
+    match error {
+        Some(e) => Err(e).context("decoding the ids index"),
+        None => Ok(()),
+    }
+}
+
+/// Removes the `stale` ids (that have no metadata) from the index, only
+/// touching the datastore if there is any.
+fn prune_ids(db: &SledDatastore, key: &[u8], stale: &[String]) -> Result<()> {
+    if stale.is_empty() {
+        return Ok(());
+    }
+    log::info!(
+        "pruning {} stale ids from {}",
+        stale.len(),
+        String::from_utf8_lossy(key)
+    );
+    update_ids(db, key, |ids| ids.retain(|id| !stale.contains(id)))
+}
+
+/// Adds the `id` to the index, unless it is already there.
+fn push_id(ids: &mut Vec<String>, id: String) {
+    if !ids.contains(&id) {
+        ids.push(id);
+    }
+}
+
+/// The format used to print the commands output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable, decorated output.
+    Human,
+    /// Machine readable JSON output, useful for scripting.
+    Json,
+}
+
+impl Default for OutputFormat {
+    fn default() -> Self { Self::Human }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            v => anyhow::bail!("unsupported output format: {}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct SystemProperties {
     /// The address format
     pub ss58_format: u8,
@@ -360,3 +1462,774 @@ impl<'a> From<&'a subxt::SystemProperties> for SystemProperties {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use subxt::Signer;
+
+    use super::*;
+
+    fn temporary_context() -> ExecutionContext { ExecutionContext::temporary() }
+
+    /// Serves just enough of the node JSON-RPC for subxt to build a client:
+    /// an empty runtime, and a zero genesis hash.
+    fn fake_node() -> url::Url {
+        use soketto::handshake::server::Response;
+        use soketto::handshake::Server;
+
+        fn reply(request: &[u8]) -> String {
+            let request: serde_json::Value =
+                serde_json::from_slice(request).unwrap_or_default();
+            let result = match request["method"].as_str() {
+                // "meta", V12, no modules, extrinsic v4.
+                Some("state_getMetadata") => {
+                    serde_json::json!("0x6d6574610c000400")
+                },
+                Some("chain_getBlockHash") => {
+                    serde_json::json!(format!("0x{}", "00".repeat(32)))
+                },
+                Some("state_getRuntimeVersion") => serde_json::json!({
+                    "specName": "webb",
+                    "implName": "webb",
+                    "authoringVersion": 1,
+                    "specVersion": 1,
+                    "implVersion": 1,
+                    "apis": [],
+                    "transactionVersion": 1,
+                }),
+                _ => serde_json::json!({}),
+            };
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": result,
+            })
+            .to_string()
+        }
+
+        async fn serve(
+            stream: async_std::net::TcpStream,
+        ) -> anyhow::Result<()> {
+            let mut server = Server::new(stream);
+            let key = server.receive_request().await?.into_key();
+            let accept = Response::Accept {
+                key: &key,
+                protocol: None,
+            };
+            server.send_response(&accept).await?;
+            let (mut sender, mut receiver) = server.into_builder().finish();
+            loop {
+                let mut request = Vec::new();
+                receiver.receive_data(&mut request).await?;
+                sender.send_text(reply(&request)).await?;
+                sender.flush().await?;
+            }
+        }
+
+        let listener = async_std::task::block_on(
+            async_std::net::TcpListener::bind("127.0.0.1:0"),
+        )
+        .unwrap();
+        let addr = listener.local_addr().unwrap();
+        async_std::task::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                async_std::task::spawn(serve(stream));
+            }
+        });
+        url::Url::parse(&format!("ws://{}", addr)).unwrap()
+    }
+
+    fn encoded(message: &impl Message) -> Vec<u8> {
+        let mut buf = Vec::new();
+        message.encode(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn rename_default_account() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        context
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let signer = context.signer().unwrap();
+
+        let renamed = context
+            .rename_account("alice", String::from("carol"))
+            .unwrap();
+        assert!(renamed);
+        let default_account = context.default_account().unwrap();
+        assert_eq!(default_account.alias, "carol");
+        assert_eq!(context.signer().unwrap().account_id(), signer.account_id());
+        // and it survives reloading the accounts.
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        let default_account = accounts.iter().find(|a| a.is_default).unwrap();
+        assert_eq!(default_account.alias, "carol");
+    }
+
+    #[test]
+    fn migrates_legacy_seeds() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let uuid = context.default_account().unwrap().uuid.clone();
+        let signer = context.signer().unwrap();
+        let seed = context.stored_seed(&uuid).unwrap();
+        // overwrite it with the raw seed, as older versions did.
+        let key = Keyspace::Account.key(format!("{}_seed", uuid));
+        context.db.write(key.clone(), seed.bytes.clone()).unwrap();
+
+        let migrated = context.stored_seed(&uuid).unwrap();
+        assert_eq!(migrated, seed);
+        assert_eq!(migrated.scheme(), SeedScheme::Sr25519);
+        let stored = context.db.read(key).unwrap().unwrap();
+        assert_ne!(stored.len(), 32);
+        assert_eq!(context.signer().unwrap().account_id(), signer.account_id());
+    }
+
+    #[test]
+    fn store_phrase_is_opt_in() {
+        let mut context = temporary_context();
+        let (_, phrase) = context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, true)
+            .unwrap();
+        context
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let uuid = |alias: &str| {
+            let accounts = context.accounts();
+            accounts
+                .iter()
+                .find(|a| a.alias == alias)
+                .unwrap()
+                .uuid
+                .clone()
+        };
+        let (alice, bob) = (uuid("alice"), uuid("bob"));
+        assert_eq!(context.stored_phrase(&alice).unwrap(), Some(phrase));
+        assert_eq!(context.stored_phrase(&bob).unwrap(), None);
+    }
+
+    #[test]
+    fn forget_default_account() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, true)
+            .unwrap();
+        context
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let uuid = context.default_account().unwrap().uuid.clone();
+
+        let promoted = context.forget_account(&uuid).unwrap().unwrap();
+        assert_eq!(promoted.alias, "bob");
+        assert_eq!(context.default_account().unwrap(), &promoted);
+        assert!(context.stored_seed(&uuid).is_err());
+        assert_eq!(context.stored_phrase(&uuid).unwrap(), None);
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        assert_eq!(accounts, vec![promoted]);
+    }
+
+    #[test]
+    fn migrates_legacy_keys_to_keyspaces() {
+        let mut db = SledDatastore::temporary().unwrap();
+        db.set_secret(SecretString::new(String::from("password")));
+        let (account, _) =
+            account::generate(String::from("alice"), KeyScheme::Sr25519);
+        let account_uuid = account.uuid.to_string();
+        let raw = AccountRaw {
+            alias: account.alias,
+            address: account.address.to_string(),
+            uuid: account_uuid.clone(),
+            is_default: true,
+            ..Default::default()
+        };
+        let seed = StoredSeed::sr25519(account.seed.to_vec());
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        let note_uuid = uuid::Uuid::new_v4().to_string();
+        let note_raw = NoteRaw {
+            alias: String::from("my-note"),
+            uuid: note_uuid.clone(),
+            ..Default::default()
+        };
+        // the keys as they were saved before the keyspaces.
+        let ids = |id: &str| {
+            encoded(&NotesIds {
+                ids: vec![id.to_owned()],
+            })
+        };
+        db.write_plaintext(account_uuid.as_bytes(), encoded(&raw))
+            .unwrap();
+        db.write(format!("{}_seed", account_uuid).as_bytes(), encoded(&seed))
+            .unwrap();
+        db.write_plaintext(&b"account_ids"[..], ids(&account_uuid))
+            .unwrap();
+        db.write_plaintext(note_uuid.as_bytes(), encoded(&note_raw))
+            .unwrap();
+        db.write(
+            format!("{}_secret", note_uuid).as_bytes(),
+            note.to_string().into_bytes(),
+        )
+        .unwrap();
+        db.write_plaintext(&b"notes_ids"[..], ids(&note_uuid))
+            .unwrap();
+        db.write_plaintext(&b"last_note"[..], note_uuid.as_bytes())
+            .unwrap();
+
+        let context = ExecutionContext::temporary_with(db);
+        assert_eq!(context.accounts(), &[raw]);
+        assert_eq!(context.notes(), &[note_raw]);
+        assert_eq!(context.stored_seed(&account_uuid).unwrap(), seed);
+        let decrypted = context.decrypt_note(note_uuid.clone()).unwrap();
+        assert_eq!(decrypted.to_string(), note.to_string());
+        assert_eq!(context.last_note().unwrap(), Some(note_uuid.clone()));
+        assert!(context
+            .db
+            .read_plaintext(note_uuid.as_bytes())
+            .unwrap()
+            .is_none());
+        // it happens only once.
+        assert_eq!(migrate_keyspaces(&context.db).unwrap(), 0);
+    }
+
+    #[test]
+    fn gives_up_connecting_after_the_last_attempt() {
+        let mut context = temporary_context();
+        context.set_rpc_url(url::Url::parse("ws://127.0.0.1:1").unwrap());
+        context.connect_backoff = Duration::from_millis(1);
+        let result = async_std::task::block_on(context.client());
+        let error = format!("{:#}", result.err().unwrap());
+        assert!(error.contains("ws://127.0.0.1:1"), "{}", error);
+        assert!(error.contains("failed 5 times"), "{}", error);
+    }
+
+    #[test]
+    fn changing_the_url_drops_the_cached_client() {
+        let mut context = temporary_context();
+        context.connect_backoff = Duration::from_millis(1);
+        context.set_rpc_url(fake_node());
+        async_std::task::block_on(context.client()).unwrap();
+        assert!(context.client.get_mut().is_some());
+        let url = context.rpc_url().clone();
+        context.set_rpc_url(url);
+        assert!(context.client.get_mut().is_some());
+        context.set_rpc_url(url::Url::parse("ws://127.0.0.1:2").unwrap());
+        assert!(context.client.get_mut().is_none());
+        let result = async_std::task::block_on(context.client());
+        let error = format!("{:#}", result.err().unwrap());
+        assert!(error.contains("ws://127.0.0.1:2"), "{}", error);
+    }
+
+    #[cfg(feature = "integration-tests")]
+    #[async_std::test]
+    async fn fetches_the_mixers() {
+        let context = temporary_context();
+        let mixers = context.fetch_mixers().await.unwrap();
+        assert!(!mixers.is_empty());
+        for (id, info) in mixers {
+            assert_eq!(context.fetch_mixer(id).await.unwrap(), info);
+        }
+    }
+
+    #[test]
+    fn index_updates_keep_concurrent_changes() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        // another process adds an account behind our back.
+        update_ids(&context.db, &Keyspace::Index.key("account_ids"), |ids| {
+            push_id(ids, String::from("concurrent"))
+        })
+        .unwrap();
+        context
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+
+        let buf = context
+            .db
+            .read_plaintext(Keyspace::Index.key("account_ids"))
+            .unwrap()
+            .unwrap();
+        let AccountsIds { ids } = AccountsIds::decode(buf.as_ref()).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert!(ids.contains(&String::from("concurrent")));
+    }
+
+    #[test]
+    fn prunes_stale_ids_on_load() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        update_ids(&context.db, &Keyspace::Index.key("account_ids"), |ids| {
+            push_id(ids, String::from("dangling"))
+        })
+        .unwrap();
+        update_ids(&context.db, &Keyspace::Index.key("notes_ids"), |ids| {
+            push_id(ids, String::from("dangling"))
+        })
+        .unwrap();
+        let index = |key: &str| {
+            let buf = context
+                .db
+                .read_plaintext(Keyspace::Index.key(key))
+                .unwrap()
+                .unwrap();
+            NotesIds::decode(buf.as_ref()).unwrap().ids
+        };
+        assert_eq!(index("account_ids").len(), 2);
+
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        let (notes, _) = ExecutionContext::load_notes(&context.db).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert!(notes.is_empty());
+        assert_eq!(index("account_ids").len(), 1);
+        assert!(index("notes_ids").is_empty());
+        // loading again doesn't change anything.
+        ExecutionContext::load_accounts(&context.db).unwrap();
+        assert_eq!(index("account_ids").len(), 1);
+    }
+
+    #[test]
+    fn rename_account_to_taken_alias() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        context
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+        assert!(context
+            .rename_account("bob", String::from("alice"))
+            .is_err());
+        assert!(!context
+            .rename_account("eve", String::from("mallory"))
+            .unwrap());
+    }
+
+    #[test]
+    fn change_password_reencrypts_everything() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, true)
+            .unwrap();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        context
+            .import_note(String::from("a"), note.clone())
+            .unwrap();
+        let account = context.default_account().unwrap().uuid.clone();
+        let note_uuid = context.notes()[0].uuid.clone();
+        let seed = context.stored_seed(&account).unwrap();
+        let phrase = context.stored_phrase(&account).unwrap();
+
+        let old = SecretString::new(String::from("password"));
+        let new = SecretString::new(String::from("new password"));
+        let wrong = SecretString::new(String::from("wrong"));
+        assert!(context.change_password(wrong, new.clone()).is_err());
+        // the seed, the phrase and the note secret.
+        assert_eq!(context.change_password(old, new).unwrap(), 3);
+        assert_eq!(context.stored_seed(&account).unwrap(), seed);
+        assert_eq!(context.stored_phrase(&account).unwrap(), phrase);
+        let secret = context.decrypt_note(note_uuid.clone()).unwrap();
+        assert_eq!(secret.to_string(), note.to_string());
+        // the plaintext metadata is untouched.
+        assert_eq!(context.notes().len(), 1);
+        assert_eq!(
+            ExecutionContext::load_accounts(&context.db).unwrap().len(),
+            1
+        );
+
+        context.set_secret(SecretString::new(String::from("password")));
+        assert!(context.stored_seed(&account).is_err());
+        assert!(context.decrypt_note(note_uuid).is_err());
+    }
+
+    #[test]
+    fn fix_note_encrypted_with_old_password() {
+        let mut context = temporary_context();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        context
+            .import_note(String::from("a"), note.clone())
+            .unwrap();
+        let uuid = context.notes()[0].uuid.clone();
+        context.set_secret(SecretString::new(String::from("new password")));
+        assert!(context.decrypt_note(uuid.clone()).is_err());
+
+        let wrong = SecretString::new(String::from("wrong"));
+        assert!(context.fix_note_encryption(&uuid, wrong).is_err());
+        let old = SecretString::new(String::from("password"));
+        context.fix_note_encryption(&uuid, old).unwrap();
+        let secret = context.decrypt_note(uuid).unwrap();
+        assert_eq!(secret.to_string(), note.to_string());
+    }
+
+    #[test]
+    fn warnings_are_taken_once() {
+        let context = temporary_context();
+        context.warn("first");
+        context.warn(String::from("second"));
+        assert_eq!(context.take_warnings(), vec!["first", "second"]);
+        assert!(context.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn backup_round_trip() {
+        let mut source = temporary_context();
+        let (_, phrase) = source
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, true)
+            .unwrap();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        source.import_note(String::from("a"), note.clone()).unwrap();
+        let backup = source.export_backup().unwrap();
+
+        let mut wrong = ExecutionContext::temporary();
+        wrong.set_secret(SecretString::new(String::from("wrong")));
+        assert!(wrong.import_backup(backup.clone()).is_err());
+        assert!(wrong.accounts().is_empty() && wrong.notes().is_empty());
+
+        let mut target = temporary_context();
+        target
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let imported = target.import_backup(backup.clone()).unwrap();
+        assert_eq!((imported.accounts, imported.notes), (1, 1));
+        let alice = source.default_account().unwrap().clone();
+        assert_eq!(target.default_account().unwrap().alias, "bob");
+        assert_eq!(
+            target.stored_seed(&alice.uuid).unwrap(),
+            source.stored_seed(&alice.uuid).unwrap()
+        );
+        assert_eq!(target.stored_phrase(&alice.uuid).unwrap(), Some(phrase));
+        let uuid = source.notes()[0].uuid.clone();
+        let secret = target.decrypt_note(uuid).unwrap();
+        assert_eq!(secret.to_string(), note.to_string());
+        let reloaded = ExecutionContext::load_accounts(&target.db).unwrap();
+        assert_eq!(reloaded.len(), 2);
+
+        // importing it again changes nothing.
+        let imported = target.import_backup(backup.clone()).unwrap();
+        assert_eq!((imported.accounts, imported.notes), (0, 0));
+        assert_eq!(imported.skipped, 2);
+
+        // a note whose alias is taken gets another one.
+        let mut other = temporary_context();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        other.import_note(String::from("a"), note).unwrap();
+        let imported = other.import_backup(backup.clone()).unwrap();
+        assert_eq!(imported.notes, 1);
+        assert_eq!(
+            imported.renamed,
+            [(String::from("a"), String::from("a-2"))]
+        );
+        let aliases: Vec<_> = other.notes().iter().map(|n| &n.alias).collect();
+        assert_eq!(aliases, ["a", "a-2"]);
+
+        let newer = Backup {
+            version: Backup::VERSION + 1,
+            ..backup
+        };
+        assert!(temporary_context().import_backup(newer).is_err());
+    }
+
+    #[test]
+    fn verify_reports_unreadable_records() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        context.import_note(String::from("a"), note).unwrap();
+        let checks = context.verify_records().unwrap();
+        assert_eq!(checks.len(), 4);
+        assert!(checks.iter().all(|c| c.problem.is_none()));
+
+        let uuid = context.notes()[0].uuid.clone();
+        let key = Keyspace::Note.key(format!("{}_secret", uuid));
+        context.db.write(key, "garbage").unwrap();
+        let problems: Vec<_> = context
+            .verify_records()
+            .unwrap()
+            .into_iter()
+            .filter(|c| c.problem.is_some())
+            .collect();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].record, format!("note {} secret", uuid));
+    }
+
+    #[test]
+    fn set_default_account_only_writes_the_changed_accounts() {
+        let mut context = temporary_context();
+        for alias in &["alice", "bob", "carol"] {
+            context
+                .generate_account(alias.to_string(), KeyScheme::Sr25519, false)
+                .unwrap();
+        }
+        // carol is not affected, so her (stale) record must stay as is.
+        let carol = context.accounts()[2].clone();
+        let stale = AccountRaw {
+            alias: String::from("stale"),
+            ..carol.clone()
+        };
+        let key = Keyspace::Account.key(&carol.uuid);
+        context
+            .db
+            .write_plaintext(key.clone(), encoded(&stale))
+            .unwrap();
+
+        context.set_default_account("bob").unwrap();
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        let default: Vec<_> =
+            accounts.iter().filter(|a| a.is_default).collect();
+        assert_eq!(default.len(), 1);
+        assert_eq!(default[0].alias, "bob");
+        let on_disk = context.db.read_plaintext(key).unwrap().unwrap();
+        assert_eq!(on_disk, encoded(&stale));
+    }
+
+    #[test]
+    fn signs_with_a_non_default_account() {
+        use subxt::Signer;
+        let mut context = temporary_context();
+        for alias in &["alice", "bob"] {
+            context
+                .generate_account(alias.to_string(), KeyScheme::Sr25519, false)
+                .unwrap();
+        }
+        let bob = context.accounts()[1].clone();
+        let signer = context.signer_for("bob").unwrap();
+        assert_eq!(signer.account_id().to_string(), bob.address);
+        let by_uuid = context.signer_for(&bob.uuid).unwrap();
+        assert_eq!(by_uuid.account_id(), signer.account_id());
+        // the default account stays the same.
+        assert_eq!(context.default_account().unwrap().alias, "alice");
+        assert_ne!(context.signer().unwrap().account_id(), signer.account_id());
+        let by_address = context.signer_for(&bob.address).unwrap();
+        assert_eq!(by_address.account_id(), signer.account_id());
+        // naming the default account explicitly is the same as the default.
+        assert_eq!(
+            context.signer_for("alice").unwrap().account_id(),
+            context.signer().unwrap().account_id()
+        );
+        let err = context.signer_for("carol").err().unwrap();
+        assert!(err.to_string().contains("no account with alias"));
+
+        context.set_secret(SecretString::new(String::from("wrong")));
+        let err = context.signer_for("bob").err().unwrap();
+        assert!(format!("{:#}", err).contains("incorrect password"));
+    }
+
+    #[test]
+    fn signs_with_an_ed25519_account() {
+        let mut context = temporary_context();
+        let (address, _) = context
+            .generate_account(String::from("alice"), KeyScheme::Ed25519, false)
+            .unwrap();
+        let account = context.default_account().unwrap().clone();
+        assert_eq!(account.scheme, SeedScheme::Ed25519 as i32);
+        let seed = context.stored_seed(&account.uuid).unwrap();
+        assert_eq!(seed.scheme(), SeedScheme::Ed25519);
+        let signer = context.signer().unwrap();
+        assert_eq!(signer.account_id(), &address);
+        // and it survives reloading the accounts.
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        assert_eq!(accounts[0].scheme, SeedScheme::Ed25519 as i32);
+    }
+
+    #[test]
+    fn imports_a_derived_account() {
+        let mut context = temporary_context();
+        let phrase = "bottom drive obey lake curtain smoke basket hold race \
+                      lonely fit walk";
+        let mnemonic =
+            Mnemonic::from_phrase(phrase, bip39::Language::English).unwrap();
+        let address = context
+            .import_account(
+                String::from("alice"),
+                KeyScheme::Sr25519,
+                mnemonic,
+                Some("//Alice"),
+                true,
+            )
+            .unwrap();
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        assert_eq!(address.to_string(), alice);
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        assert_eq!(accounts[0].derivation_path, "//Alice");
+        // the signer is derived with the saved path too.
+        let signer = context.signer().unwrap();
+        assert_eq!(signer.account_id().to_string(), alice);
+        let stored = context.stored_phrase(&accounts[0].uuid).unwrap();
+        assert_eq!(stored.as_deref(), Some(phrase));
+    }
+
+    #[test]
+    fn addresses_use_the_known_ss58_format() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        let stored = context.accounts()[0].address.clone();
+        // offline, the stored generic format is kept.
+        assert_eq!(context.ss58_format(), None);
+        assert_eq!(context.display_address(&stored), stored);
+
+        *context.chain_ss58_format.lock().unwrap() = Some(7);
+        let edgeware = context.display_address(&stored);
+        assert_eq!(edgeware, utils::reformat_address(&stored, 7).unwrap());
+        assert_ne!(edgeware, stored);
+
+        context.chain_properties = Some(SystemProperties {
+            ss58_format: 2,
+            ..SystemProperties::default()
+        });
+        assert_eq!(context.ss58_format(), Some(2));
+        context.set_ss58_format(Some(42));
+        assert_eq!(context.ss58_format(), Some(42));
+        let account = context.display_account(&context.accounts()[0]);
+        assert_eq!(account.address, stored);
+    }
+
+    #[test]
+    fn set_unknown_default_account_keeps_the_default() {
+        let mut context = temporary_context();
+        context
+            .generate_account(String::from("alice"), KeyScheme::Sr25519, false)
+            .unwrap();
+        context
+            .generate_account(String::from("bob"), KeyScheme::Sr25519, false)
+            .unwrap();
+        assert!(context.set_default_account("nonexistent").is_err());
+        assert_eq!(context.default_account().unwrap().alias, "alice");
+        let accounts = ExecutionContext::load_accounts(&context.db).unwrap();
+        let default: Vec<_> =
+            accounts.iter().filter(|a| a.is_default).collect();
+        assert_eq!(default.len(), 1);
+        assert_eq!(default[0].alias, "alice");
+    }
+
+    #[test]
+    fn refuses_to_save_a_taken_note_alias() {
+        let mut context = temporary_context();
+        let note = || Mixer::new(0).generate_note(TokenSymbol::Edg);
+        context.import_note(String::from("a"), note()).unwrap();
+        assert!(context.import_note(String::from("a"), note()).is_err());
+        let metadata = NoteMetadata {
+            amount: 1000,
+            decimals: 12,
+            currency_id: 0,
+            chain: String::new(),
+        };
+        assert!(context
+            .generate_note(String::from("a"), 0, TokenSymbol::Edg, metadata)
+            .is_err());
+        assert_eq!(context.notes().len(), 1);
+        let (notes, _) = ExecutionContext::load_notes(&context.db).unwrap();
+        assert_eq!(notes.len(), 1);
+    }
+
+    #[test]
+    fn suggests_a_free_note_alias() {
+        let mut context = temporary_context();
+        assert!(!context.has_note_alias("a"));
+        for alias in &["a", "a-2"] {
+            let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+            context.import_note(alias.to_string(), note).unwrap();
+        }
+        assert!(context.has_note_alias("a"));
+        assert!(!context.has_note_alias("b"));
+        assert_eq!(context.free_note_alias("a"), "a-3");
+        assert_eq!(context.free_note_alias("b"), "b-2");
+    }
+
+    #[test]
+    fn tells_notes_sharing_an_alias() {
+        let note = |alias: &str, uuid: &str| NoteRaw {
+            alias: alias.to_owned(),
+            uuid: uuid.to_owned(),
+            ..Default::default()
+        };
+        let notes = vec![note("a", "1"), note("b", "2"), note("a", "3")];
+        let b = note_with_alias(notes.clone(), "b").unwrap().unwrap();
+        assert_eq!(b.uuid, "2");
+        assert!(note_with_alias(notes.clone(), "c").unwrap().is_none());
+        let ambiguous = note_with_alias(notes, "a").unwrap_err();
+        let uuids: Vec<_> = ambiguous.notes.iter().map(|n| &n.uuid).collect();
+        assert_eq!(uuids, ["1", "3"]);
+        assert!(ambiguous.to_string().contains("use `--uuid`"));
+    }
+
+    #[test]
+    fn finds_notes_by_uuid_first() {
+        let mut context = temporary_context();
+        for alias in &["a", "b"] {
+            let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+            context.import_note(alias.to_string(), note).unwrap();
+        }
+        let b_uuid = context.notes()[1].uuid.clone();
+        assert!(context.find_note(None, None).unwrap().is_none());
+        let a = context.find_note(Some("a"), None).unwrap().unwrap();
+        assert_eq!(a.alias, "a");
+        // the uuid wins over the alias.
+        let b = context
+            .find_note(Some("a"), Some(&b_uuid))
+            .unwrap()
+            .unwrap();
+        assert_eq!(b.alias, "b");
+        assert!(context.find_note(Some("c"), None).is_err());
+        assert!(context.find_note(None, Some("nope")).is_err());
+
+        // notes saved by older versions could share an alias.
+        context.notes.push(NoteRaw {
+            alias: String::from("a"),
+            uuid: String::from("dup"),
+            ..Default::default()
+        });
+        let err = context.find_note(Some("a"), None).unwrap_err();
+        let ambiguous = err.downcast::<AmbiguousAlias>().unwrap();
+        assert_eq!(ambiguous.notes.len(), 2);
+        let dup = context.find_note(Some("a"), Some("dup")).unwrap().unwrap();
+        assert_eq!(dup.uuid, "dup");
+    }
+
+    #[test]
+    fn imported_v2_note_amount_matches_the_mixer_size() {
+        let mut context = temporary_context();
+        let mut note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        note.version = webb_cli::mixer::NoteVersion::V2;
+        note.index = Some(0);
+        note.amount = Some(String::from("1.5"));
+        context.import_note(String::from("a"), note).unwrap();
+        let saved = &context.notes()[0];
+        assert_eq!(saved.human_amount().unwrap(), "1.5 EDG");
+        // a 1.5 EDG Mixer Group size, with the chain 12 decimals.
+        assert_eq!(saved.amount_with_decimals(12), Some(1_500_000_000_000));
+    }
+
+    #[test]
+    fn rename_note_keeps_secret() {
+        let mut context = temporary_context();
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        context
+            .import_note(String::from("a"), note.clone())
+            .unwrap();
+        context
+            .import_note(
+                String::from("b"),
+                Mixer::new(0).generate_note(TokenSymbol::Edg),
+            )
+            .unwrap();
+        let uuid = context.notes()[0].uuid.clone();
+        assert!(context.rename_note(&uuid, String::from("b")).is_err());
+
+        context.rename_note(&uuid, String::from("c")).unwrap();
+        assert_eq!(context.notes()[0].alias, "c");
+        let (notes, _) = ExecutionContext::load_notes(&context.db).unwrap();
+        assert!(notes.iter().any(|n| n.uuid == uuid && n.alias == "c"));
+        let secret = context.decrypt_note(uuid).unwrap();
+        assert_eq!(secret.to_string(), note.to_string());
+    }
+}
@@ -1,19 +1,242 @@
+use std::convert::TryInto;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
 use anyhow::Context;
+use argon2::{Algorithm, Argon2, Version};
 use chacha::aead::{Aead, NewAead};
 use chacha::{Key, XChaCha20Poly1305, XNonce};
 use directories_next::ProjectDirs;
 use rand::RngCore;
-use secrecy::{SecretString, Zeroize};
+use secrecy::{ExposeSecret, SecretString, Zeroize};
+use sled::transaction::TransactionResult;
+use zeroize::Zeroizing;
 
 use crate::utils;
 
+/// The prefix of the keys of the encrypted entries.
+const ENCRYPTED: &[u8] = b"enc:";
+/// The prefix of the keys of the plaintext entries.
+const PLAINTEXT: &[u8] = b"raw:";
+
+/// The `key` under the `namespace`, either [ENCRYPTED] or [PLAINTEXT].
+///
+/// the namespaces record which entries are encrypted, so reading one the
+/// wrong way is an error and not garbage.
+fn namespaced(namespace: &[u8], key: &[u8]) -> Vec<u8> {
+    [namespace, key].concat()
+}
+
+/// The plaintext key where the [Kdf] of the store is recorded.
+const KDF_KEY: &[u8] = b"kdf";
+
+/// The profile used unless `--profile` is given.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The directory of the `profile`, where its datastore lives.
+///
+/// the profile name is limited to ASCII letters, digits, `-` and `_`, so
+/// it can never point outside of the profiles directory.
+pub fn profile_dir(
+    dirs: &ProjectDirs,
+    profile: &str,
+) -> anyhow::Result<PathBuf> {
+    let valid = !profile.is_empty()
+        && profile.len() <= 64
+        && profile
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if !valid {
+        anyhow::bail!(
+            "invalid profile name `{}`, only letters, digits, `-` and `_` \
+             are allowed",
+            profile
+        );
+    }
+    Ok(dirs.data_dir().join("profiles").join(profile))
+}
+
+/// The kinds of entries saved in the datastore, each one has its own key
+/// prefix, inside the [ENCRYPTED] and [PLAINTEXT] namespaces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyspace {
+    /// The accounts metadata, seeds and phrases.
+    Account,
+    /// The notes metadata and secrets.
+    Note,
+    /// The accounts and notes ids indexes.
+    Index,
+    /// The CLI settings, i.e the last selected mixer group.
+    Config,
+}
+
+impl Keyspace {
+    const ALL: [Keyspace; 4] =
+        [Self::Account, Self::Note, Self::Index, Self::Config];
+
+    pub fn prefix(self) -> &'static [u8] {
+        match self {
+            Self::Account => b"acct:",
+            Self::Note => b"note:",
+            Self::Index => b"idx:",
+            Self::Config => b"cfg:",
+        }
+    }
+
+    /// The `key` under this keyspace.
+    pub fn key(self, key: impl AsRef<[u8]>) -> Vec<u8> {
+        [self.prefix(), key.as_ref()].concat()
+    }
+
+    /// Whether the `key` is already under one of the keyspaces.
+    fn contains(key: &[u8]) -> bool {
+        Self::ALL
+            .iter()
+            .any(|space| key.starts_with(space.prefix()))
+    }
+}
+
+/// The parameters of the Argon2id key derivation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// The memory cost, in KiB.
+    pub memory_kib: u32,
+    /// The number of passes over the memory.
+    pub iterations: u32,
+    /// The degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+/// How the encryption key is derived from the password.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kdf {
+    /// A single SHA-256 of the password, used by the stores created before
+    /// Argon2 was supported.
+    Sha256,
+    /// Argon2id, with a random salt per store.
+    Argon2id { params: KdfParams, salt: [u8; 16] },
+}
+
+impl Kdf {
+    /// The version byte of the encoded [Kdf::Argon2id].
+    const ARGON2ID: u8 = 1;
+    /// The version byte of the encoded [Kdf::Sha256].
+    const SHA256: u8 = 0;
+
+    /// Argon2id with the `params` and a new random salt.
+    pub fn argon2id(params: KdfParams) -> Self {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self::Argon2id { params, salt }
+    }
+
+    /// Derives the 32 bytes encryption key from the `secret`.
+    pub fn derive(&self, secret: &SecretString) -> anyhow::Result<[u8; 32]> {
+        match self {
+            Self::Sha256 => {
+                let mut hash = utils::sha256(secret.clone());
+                let key = hash.as_slice().try_into()?;
+                hash.zeroize();
+                Ok(key)
+            },
+            Self::Argon2id { params, salt } => {
+                let argon2 = Argon2::new(
+                    None,
+                    params.iterations,
+                    params.memory_kib,
+                    params.parallelism,
+                    Version::V0x13,
+                )
+                .map_err(|e| anyhow::anyhow!("bad argon2 params: {}", e))?;
+                let mut key = [0u8; 32];
+                argon2
+                    .hash_password_into(
+                        Algorithm::Argon2id,
+                        secret.expose_secret().as_bytes(),
+                        salt,
+                        &[],
+                        &mut key,
+                    )
+                    .map_err(|e| anyhow::anyhow!("argon2 failed: {}", e))?;
+                Ok(key)
+            },
+        }
+    }
+
+    /// Encodes the kdf as a version byte followed by its parameters.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Sha256 => vec![Self::SHA256],
+            Self::Argon2id { params, salt } => {
+                let mut buf = vec![Self::ARGON2ID];
+                buf.extend(&params.memory_kib.to_le_bytes());
+                buf.extend(&params.iterations.to_le_bytes());
+                buf.extend(&params.parallelism.to_le_bytes());
+                buf.extend(salt);
+                buf
+            },
+        }
+    }
+
+    pub fn decode(buf: &[u8]) -> anyhow::Result<Self> {
+        let u32_at = |i: usize| -> anyhow::Result<u32> {
+            let bytes = buf.get(i..i + 4).context("truncated kdf params")?;
+            Ok(u32::from_le_bytes(bytes.try_into()?))
+        };
+        match buf.first() {
+            Some(&Self::SHA256) => Ok(Self::Sha256),
+            Some(&Self::ARGON2ID) => {
+                let params = KdfParams {
+                    memory_kib: u32_at(1)?,
+                    iterations: u32_at(5)?,
+                    parallelism: u32_at(9)?,
+                };
+                let salt = buf
+                    .get(13..29)
+                    .context("truncated kdf salt")?
+                    .try_into()?;
+                Ok(Self::Argon2id { params, salt })
+            },
+            Some(v) => anyhow::bail!(
+                "unsupported datastore kdf #{}, created by a newer version?",
+                v
+            ),
+            None => anyhow::bail!("empty datastore kdf"),
+        }
+    }
+}
+
 pub struct SledDatastore {
     sled: sled::Db,
     secret: Option<SecretString>,
+    /// The kdf used by this store.
+    kdf: Kdf,
+    /// The params used when switching to Argon2.
+    kdf_params: KdfParams,
+    /// The key derived from the `secret`, derived once since it is costly.
+    key: Mutex<Option<[u8; 32]>>,
 }
 
 impl SledDatastore {
-    pub fn new() -> anyhow::Result<Self> {
+    #[cfg(test)]
+    const TEST_KDF_PARAMS: KdfParams = KdfParams {
+        memory_kib: 8,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    /// Opens the datastore of the `profile`, see [profile_dir].
+    pub fn new(profile: &str) -> anyhow::Result<Self> {
         let dirs = ProjectDirs::from(
             crate::PACKAGE_ID[0],
             crate::PACKAGE_ID[1],
@@ -21,45 +244,124 @@ impl SledDatastore {
         )
         .context("getting project data")?;
 
-        let db_path = dirs.data_dir().join("db");
-        let db = sled::open(db_path).context("open database")?;
+        let home = profile_dir(&dirs, profile)?;
+        let db_path = home.join("db");
+        let legacy_path = dirs.data_dir().join("db");
+        if profile == DEFAULT_PROFILE
+            && !db_path.exists()
+            && legacy_path.exists()
+        {
+            log::info!("moving the datastore to the default profile");
+            std::fs::create_dir_all(&home)?;
+            std::fs::rename(&legacy_path, &db_path)
+                .context("moving the datastore to the default profile")?;
+        }
+        Self::open_at(db_path)
+    }
+
+    /// Opens (or creates) the datastore at `path`.
+    pub fn open_at(path: PathBuf) -> anyhow::Result<Self> {
+        let db = sled::open(path).context("open database")?;
+        Self::from_db(db, KdfParams::default())
+    }
+
+    /// A datastore that lives only in memory, and gets removed when dropped.
+    ///
+    /// it uses the cheapest Argon2 params, to keep the tests fast.
+    #[cfg(test)]
+    pub fn temporary() -> anyhow::Result<Self> {
+        let db = sled::Config::new().temporary(true).open()?;
+        Self::from_db(db, Self::TEST_KDF_PARAMS)
+    }
+
+    /// Opens the datastore using the kdf recorded in the `db`.
+    ///
+    /// a new (empty) store uses Argon2id with `kdf_params`, while the stores
+    /// created before the kdf got recorded use the legacy SHA-256.
+    fn from_db(db: sled::Db, kdf_params: KdfParams) -> anyhow::Result<Self> {
+        let recorded = db.get(namespaced(PLAINTEXT, KDF_KEY))?;
+        let kdf = match recorded {
+            Some(buf) => Kdf::decode(&buf)?,
+            None if db.is_empty() => {
+                let kdf = Kdf::argon2id(kdf_params);
+                db.insert(namespaced(PLAINTEXT, KDF_KEY), kdf.encode())?;
+                db.flush()?;
+                kdf
+            },
+            None => Kdf::Sha256,
+        };
         Ok(Self {
-            secret: None,
             sled: db,
+            secret: None,
+            kdf,
+            kdf_params,
+            key: Mutex::default(),
         })
     }
 
-    pub fn with_secret(secret: SecretString) -> anyhow::Result<Self>
+    /// The kdf used by this store.
+    pub fn kdf(&self) -> &Kdf { &self.kdf }
+
+    /// The encryption key, derived from the secret on first use.
+    fn key(&self) -> anyhow::Result<[u8; 32]> {
+        let secret = self
+            .secret
+            .as_ref()
+            .context("password must be provided for decryption!")?;
+        let mut cached = self
+            .key
+            .lock()
+            .map_err(|_| anyhow::anyhow!("poisoned key cache"))?;
+        match *cached {
+            Some(key) => Ok(key),
+            None => {
+                let key = self.kdf.derive(secret)?;
+                *cached = Some(key);
+                Ok(key)
+            },
+        }
+    }
+
+    pub fn with_secret(
+        profile: &str,
+        secret: SecretString,
+    ) -> anyhow::Result<Self>
     where
         Self: Sized,
     {
-        let mut this = Self::new()?;
-        this.secret = Some(secret);
+        let mut this = Self::new(profile)?;
+        this.set_secret(secret);
         Ok(this)
     }
 
+    /// Reads and decrypts the value of `key`.
+    ///
+    /// the plaintext is wiped from memory once the returned buffer is
+    /// dropped, so don't copy it around more than needed.
     pub fn read(
         &self,
         key: impl Into<sled::IVec>,
-    ) -> anyhow::Result<Option<sled::IVec>> {
-        let secret = self
-            .secret
-            .clone()
-            .context("password must be provided for decryption!")?;
-        let mut deckey_hash = utils::sha256(secret);
-        let encrypted = self.sled.get(key.into())?;
+    ) -> anyhow::Result<Option<Zeroizing<Vec<u8>>>> {
+        let key = key.into();
+        let encrypted = self.sled.get(namespaced(ENCRYPTED, &key))?;
         if let Some(data) = encrypted {
-            let nonce_bytes = &data[0..24]; // 24 bytes are the nonce.
-            let contents = &data[24..]; // the rest is the encrypted data.
-            let deckey = Key::from_slice(&deckey_hash);
-            let nonce = XNonce::from_slice(nonce_bytes);
-            let aead = XChaCha20Poly1305::new(deckey);
-            let plaintext = aead
-                .decrypt(nonce, contents)
-                .map_err(|_| anyhow::anyhow!("datastore decrypt failed"))
-                .context("data decryption!")?;
-            deckey_hash.zeroize();
-            Ok(Some(plaintext.into()))
+            let plaintext =
+                decrypt(&self.key()?, &data).with_context(|| {
+                    format!("decrypting `{}`", String::from_utf8_lossy(&key))
+                })?;
+            if is_legacy_record(&data) {
+                log::info!(
+                    "adding a header to the legacy record `{}`",
+                    String::from_utf8_lossy(&key)
+                );
+                self.write(key, plaintext.as_slice())?;
+            }
+            Ok(Some(plaintext))
+        } else if self.sled.contains_key(namespaced(PLAINTEXT, &key))? {
+            anyhow::bail!(
+                "`{}` is not encrypted, it can't be decrypted",
+                String::from_utf8_lossy(&key)
+            )
         } else {
             Ok(None)
         }
@@ -68,40 +370,173 @@ impl SledDatastore {
     pub fn write(
         &self,
         key: impl Into<sled::IVec>,
-        value: impl Into<sled::IVec>,
+        value: impl Into<Vec<u8>>,
     ) -> anyhow::Result<Option<sled::IVec>> {
-        let secret = self
-            .secret
-            .clone()
-            .context("password must be provided for encryption")?;
-        let mut enckey_hash = utils::sha256(secret);
-        let mut buffer = Vec::new(); // a buffer to hold the nonce + encrypted bytes.
-        let mut nonce_bytes = [0u8; 24];
-        let mut rng = rand::thread_rng();
-        rng.fill_bytes(&mut nonce_bytes);
-        let nonce = XNonce::from_slice(&nonce_bytes);
-        let enckey = Key::from_slice(&enckey_hash);
-        let aead = XChaCha20Poly1305::new(enckey);
-        let mut encrypted = aead
-            .encrypt(&nonce, value.into().as_ref())
-            .map_err(|_| anyhow::anyhow!("datastore encryption failed"))
-            .context("data encryption")?;
-        buffer.extend(&nonce_bytes); // add nonce. [0..24]
-        buffer.append(&mut encrypted); // add encrypted bytes [24..]
-        enckey_hash.zeroize(); // clear the key.
+        let plaintext = Zeroizing::new(value.into());
+        let buffer = encrypt(&self.key()?, &plaintext)?;
         let val = self
             .sled
-            .insert(key.into(), buffer)
+            .insert(namespaced(ENCRYPTED, &key.into()), buffer)
             .map_err(anyhow::Error::from)?;
         self.sled.flush()?;
         Ok(val)
     }
 
+    /// Reads the value of `key` as it is stored, still encrypted.
+    pub fn read_sealed(
+        &self,
+        key: impl Into<sled::IVec>,
+    ) -> anyhow::Result<Option<sled::IVec>> {
+        let key = key.into();
+        Ok(self.sled.get(namespaced(ENCRYPTED, &key))?)
+    }
+
+    /// Writes the `sealed` (key, value) entries, encrypted by a store using
+    /// the `kdf` and the same password, re-encrypted with our key.
+    ///
+    /// all of them are decrypted before writing any, so a wrong password
+    /// leaves the store untouched.
+    pub fn write_sealed_from(
+        &self,
+        kdf: &Kdf,
+        sealed: &[(Vec<u8>, Vec<u8>)],
+    ) -> anyhow::Result<()> {
+        let their_key = if kdf == &self.kdf {
+            self.key()?
+        } else {
+            let secret = self
+                .secret
+                .as_ref()
+                .context("password must be provided for decryption!")?;
+            kdf.derive(secret)?
+        };
+        let mut plaintexts = Vec::with_capacity(sealed.len());
+        for (key, data) in sealed {
+            let plaintext = decrypt(&their_key, data).with_context(|| {
+                format!("decrypting `{}`", String::from_utf8_lossy(key))
+            })?;
+            plaintexts.push((key, plaintext));
+        }
+        for (key, plaintext) in plaintexts {
+            self.write(key.as_slice(), plaintext.as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Re-encrypts the value of `key`, that was encrypted using the `old`
+    /// secret, with the current secret.
+    ///
+    /// the legacy SHA-256 kdf is tried too, for the entries left behind by a
+    /// store that switched to Argon2id. returns `false` if there is no such
+    /// key.
+    pub fn reencrypt_from(
+        &self,
+        key: impl Into<sled::IVec>,
+        old: SecretString,
+    ) -> anyhow::Result<bool> {
+        let key = key.into();
+        let data = match self.sled.get(namespaced(ENCRYPTED, &key))? {
+            Some(data) => data,
+            None => return Ok(false),
+        };
+        let old_key = self.kdf.derive(&old)?;
+        let plaintext = match decrypt(&old_key, &data) {
+            Ok(plaintext) => plaintext,
+            Err(_) if self.kdf != Kdf::Sha256 => {
+                decrypt(&Kdf::Sha256.derive(&old)?, &data)
+                    .context("the old password is incorrect")?
+            },
+            Err(e) => return Err(e).context("the old password is incorrect"),
+        };
+        self.write(key, plaintext.as_slice())?;
+        Ok(true)
+    }
+
+    /// Re-encrypts all of the encrypted entries, encrypted with the `old`
+    /// secret, with the `new` secret, and uses it from now on.
+    ///
+    /// every entry is decrypted before writing any, so a wrong `old` secret,
+    /// or an entry encrypted with another secret, leaves the store
+    /// untouched. all the entries are rewritten in a single transaction, so
+    /// a crash never leaves the store encrypted with a mix of both secrets.
+    ///
+    /// a store still using the legacy SHA-256 kdf switches to Argon2id.
+    ///
+    /// returns the number of re-encrypted entries.
+    pub fn reencrypt(
+        &mut self,
+        old: SecretString,
+        new: SecretString,
+    ) -> anyhow::Result<usize> {
+        let old_key = self.kdf.derive(&old)?;
+        let mut plaintexts = Vec::new();
+        let mut undecryptable = Vec::new();
+        for entry in self.sled.scan_prefix(ENCRYPTED) {
+            let (key, data) = entry?;
+            match decrypt(&old_key, &data) {
+                Ok(plaintext) => plaintexts.push((key, plaintext)),
+                Err(_) => undecryptable.push(key),
+            }
+        }
+        if !undecryptable.is_empty() {
+            if plaintexts.is_empty() {
+                anyhow::bail!("the current password is incorrect");
+            }
+            let keys: Vec<_> = undecryptable
+                .iter()
+                .map(|key| String::from_utf8_lossy(&key[ENCRYPTED.len()..]))
+                .collect();
+            anyhow::bail!(
+                "{} can't be decrypted with the current password, they were \
+                 saved using another password",
+                keys.join(", ")
+            );
+        }
+        let kdf = match self.kdf {
+            Kdf::Sha256 => Kdf::argon2id(self.kdf_params),
+            ref kdf => kdf.clone(),
+        };
+        let new_key = kdf.derive(&new)?;
+        let mut updates = Vec::with_capacity(plaintexts.len());
+        for (key, plaintext) in plaintexts {
+            let encrypted = encrypt(&new_key, &plaintext)?;
+            updates.push((key, encrypted));
+        }
+        let kdf_key = namespaced(PLAINTEXT, KDF_KEY);
+        let result: TransactionResult<()> = self.sled.transaction(|tx| {
+            for (key, encrypted) in &updates {
+                tx.insert(key, encrypted.as_slice())?;
+            }
+            tx.insert(kdf_key.as_slice(), kdf.encode())?;
+            Ok(())
+        });
+        result.map_err(|e| {
+            anyhow::anyhow!("re-encrypting the datastore failed: {:?}", e)
+        })?;
+        self.sled.flush()?;
+        self.kdf = kdf;
+        self.secret = Some(new);
+        if let Ok(mut cached) = self.key.lock() {
+            *cached = Some(new_key);
+        }
+        Ok(updates.len())
+    }
+
     pub fn read_plaintext(
         &self,
         key: impl Into<sled::IVec>,
     ) -> anyhow::Result<Option<sled::IVec>> {
-        self.sled.get(key.into()).map_err(anyhow::Error::from)
+        let key = key.into();
+        let value = self.sled.get(namespaced(PLAINTEXT, &key))?;
+        if value.is_none()
+            && self.sled.contains_key(namespaced(ENCRYPTED, &key))?
+        {
+            anyhow::bail!(
+                "`{}` is encrypted, it can't be read as plaintext",
+                String::from_utf8_lossy(&key)
+            );
+        }
+        Ok(value)
     }
 
     pub fn write_plaintext(
@@ -111,22 +546,513 @@ impl SledDatastore {
     ) -> anyhow::Result<Option<sled::IVec>> {
         let val = self
             .sled
-            .insert(key.into(), value.into())
+            .insert(namespaced(PLAINTEXT, &key.into()), value.into())
             .map_err(anyhow::Error::from)?;
         self.sled.flush()?;
         Ok(val)
     }
 
+    /// Atomically updates the plaintext value of `key` using `f`.
+    ///
+    /// `f` is given the current value on disk and could be called more than
+    /// once if the value changes concurrently, returning `None` removes it.
+    pub fn update_plaintext<V, F>(
+        &self,
+        key: impl AsRef<[u8]>,
+        f: F,
+    ) -> anyhow::Result<()>
+    where
+        V: Into<sled::IVec>,
+        F: FnMut(Option<&[u8]>) -> Option<V>,
+    {
+        self.sled
+            .fetch_and_update(namespaced(PLAINTEXT, key.as_ref()), f)?;
+        self.sled.flush()?;
+        Ok(())
+    }
+
     pub fn has_secret(&self) -> bool { self.secret.is_some() }
 
+    pub fn secret(&self) -> Option<&SecretString> { self.secret.as_ref() }
+
     pub fn set_secret(&mut self, secret: SecretString) {
         self.secret = Some(secret);
+        if let Ok(mut cached) = self.key.lock() {
+            if let Some(ref mut key) = *cached {
+                key.zeroize();
+            }
+            *cached = None;
+        }
     }
 
     pub fn remove(
         &self,
         key: impl Into<sled::IVec>,
     ) -> anyhow::Result<Option<sled::IVec>> {
-        self.sled.remove(key.into()).map_err(anyhow::Error::from)
+        let key = key.into();
+        let encrypted = self.sled.remove(namespaced(ENCRYPTED, &key))?;
+        let plaintext = self.sled.remove(namespaced(PLAINTEXT, &key))?;
+        Ok(encrypted.or(plaintext))
+    }
+
+    /// Moves the entries saved before the keys got namespaced to their
+    /// namespace, `is_encrypted` tells which of them are encrypted.
+    ///
+    /// all of them are moved in a single transaction, returns how many.
+    pub fn migrate_flat_keys(
+        &self,
+        is_encrypted: impl Fn(&[u8]) -> bool,
+    ) -> anyhow::Result<usize> {
+        let mut legacy = Vec::new();
+        for entry in self.sled.iter() {
+            let (key, value) = entry?;
+            if !key.starts_with(ENCRYPTED) && !key.starts_with(PLAINTEXT) {
+                legacy.push((key, value));
+            }
+        }
+        if legacy.is_empty() {
+            return Ok(0);
+        }
+        let result: TransactionResult<()> = self.sled.transaction(|tx| {
+            for (key, value) in &legacy {
+                let namespace = if is_encrypted(key) {
+                    ENCRYPTED
+                } else {
+                    PLAINTEXT
+                };
+                tx.insert(namespaced(namespace, key), value)?;
+                tx.remove(key)?;
+            }
+            Ok(())
+        });
+        result.map_err(|e| {
+            anyhow::anyhow!("migrating the datastore keys failed: {:?}", e)
+        })?;
+        self.sled.flush()?;
+        Ok(legacy.len())
+    }
+
+    /// Moves the entries saved before the keys got a [Keyspace] to theirs,
+    /// `keyspace_of` tells which one a key belongs to, the keys it doesn't
+    /// know are left as they are.
+    ///
+    /// all of them are moved in a single transaction, returns how many.
+    pub fn migrate_keyspaces(
+        &self,
+        keyspace_of: impl Fn(&[u8]) -> Option<Keyspace>,
+    ) -> anyhow::Result<usize> {
+        let mut legacy = Vec::new();
+        for namespace in [ENCRYPTED, PLAINTEXT].iter() {
+            for entry in self.sled.scan_prefix(namespace) {
+                let (key, value) = entry?;
+                let logical = &key[namespace.len()..];
+                let internal = *namespace == PLAINTEXT && logical == KDF_KEY;
+                if internal || Keyspace::contains(logical) {
+                    continue;
+                }
+                if let Some(space) = keyspace_of(logical) {
+                    let new_key = namespaced(namespace, &space.key(logical));
+                    legacy.push((key, new_key, value));
+                }
+            }
+        }
+        if legacy.is_empty() {
+            return Ok(0);
+        }
+        let result: TransactionResult<()> = self.sled.transaction(|tx| {
+            for (key, new_key, value) in &legacy {
+                tx.insert(new_key.as_slice(), value)?;
+                tx.remove(key)?;
+            }
+            Ok(())
+        });
+        result.map_err(|e| {
+            anyhow::anyhow!("migrating the datastore keyspaces failed: {:?}", e)
+        })?;
+        self.sled.flush()?;
+        Ok(legacy.len())
+    }
+}
+
+/// The magic bytes every encrypted record starts with.
+const RECORD_MAGIC: &[u8] = b"WEBB";
+/// The current format version of the encrypted records.
+const RECORD_VERSION: u8 = 1;
+/// The length of the record header, the magic followed by the version.
+const HEADER_LEN: usize = RECORD_MAGIC.len() + 1;
+/// The length of the XChaCha20 nonce.
+const NONCE_LEN: usize = 24;
+
+/// Whether the encrypted `data` is a legacy record, written before the
+/// records had a header.
+fn is_legacy_record(data: &[u8]) -> bool { !data.starts_with(RECORD_MAGIC) }
+
+/// Encrypts the `plaintext` using the `key`, the result is the record
+/// header, the 24 bytes nonce and then the encrypted bytes.
+fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    let mut rng = rand::thread_rng();
+    rng.fill_bytes(&mut nonce_bytes);
+    let nonce = XNonce::from_slice(&nonce_bytes);
+    let enckey = Key::from_slice(key);
+    let aead = XChaCha20Poly1305::new(enckey);
+    let encrypted = aead
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| anyhow::anyhow!("datastore encryption failed"))
+        .context("data encryption")?;
+    // header [0..5], nonce [5..29] and the encrypted bytes [29..].
+    let mut buffer =
+        Vec::with_capacity(HEADER_LEN + NONCE_LEN + encrypted.len());
+    buffer.extend_from_slice(RECORD_MAGIC);
+    buffer.push(RECORD_VERSION);
+    buffer.extend_from_slice(&nonce_bytes);
+    buffer.extend_from_slice(&encrypted);
+    Ok(buffer)
+}
+
+/// Decrypts the `data` written by [encrypt] using the same `key`.
+///
+/// a record with a valid header that fails to decrypt was encrypted with
+/// another key, so it is reported as a wrong password. anything else is
+/// reported as a corrupt record. legacy records, without a header, are
+/// still decrypted, but a failure there can be either one of them.
+fn decrypt(key: &[u8; 32], data: &[u8]) -> anyhow::Result<Zeroizing<Vec<u8>>> {
+    let sealed = if is_legacy_record(data) {
+        data
+    } else {
+        match data.get(RECORD_MAGIC.len()) {
+            Some(&RECORD_VERSION) => &data[HEADER_LEN..],
+            Some(version) => anyhow::bail!(
+                "unsupported record version {}, try upgrading webb",
+                version
+            ),
+            None => anyhow::bail!("corrupt record, the header is truncated"),
+        }
+    };
+    if sealed.len() < NONCE_LEN {
+        anyhow::bail!("corrupt record, the data is too short");
+    }
+    let (nonce_bytes, contents) = sealed.split_at(NONCE_LEN);
+    let deckey = Key::from_slice(key);
+    let nonce = XNonce::from_slice(nonce_bytes);
+    let aead = XChaCha20Poly1305::new(deckey);
+    aead.decrypt(nonce, contents)
+        .map(Zeroizing::new)
+        .map_err(|_| {
+            if is_legacy_record(data) {
+                anyhow::anyhow!(
+                    "wrong password or corrupt record (legacy format)"
+                )
+            } else {
+                anyhow::anyhow!("wrong password")
+            }
+        })
+}
+
+impl Drop for SledDatastore {
+    fn drop(&mut self) {
+        // clear the key.
+        if let Ok(mut cached) = self.key.lock() {
+            if let Some(ref mut key) = *cached {
+                key.zeroize();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temporary_with_secret() -> SledDatastore {
+        let mut db = SledDatastore::temporary().unwrap();
+        db.set_secret(SecretString::new(String::from("password")));
+        db
+    }
+
+    /// A new store in the `dir`, using the cheap test kdf params.
+    fn create_in(dir: &tempfile::TempDir) -> SledDatastore {
+        let db = sled::open(dir.path().join("db")).unwrap();
+        SledDatastore::from_db(db, SledDatastore::TEST_KDF_PARAMS).unwrap()
+    }
+
+    /// Reopens the store in the `dir`.
+    ///
+    /// sled's flusher thread can hold the file lock for a moment after the
+    /// last handle is dropped, so this retries for a bit before giving up.
+    fn reopen_in(dir: &tempfile::TempDir) -> SledDatastore {
+        let path = dir.path().join("db");
+        for _ in 0..50 {
+            if let Ok(db) = SledDatastore::open_at(path.clone()) {
+                return db;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        SledDatastore::open_at(path).unwrap()
+    }
+
+    #[test]
+    fn encrypted_round_trip_across_reopens() {
+        let dir = tempfile::tempdir().unwrap();
+        let secret = || SecretString::new(String::from("password"));
+        let mut db = create_in(&dir);
+        db.set_secret(secret());
+        db.write("key", "value").unwrap();
+        drop(db);
+
+        let mut db = reopen_in(&dir);
+        db.set_secret(secret());
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+        db.set_secret(SecretString::new(String::from("wrong")));
+        let err = db.read("key").unwrap_err();
+        assert!(format!("{:#}", err).ends_with("wrong password"));
+    }
+
+    #[test]
+    fn plaintext_bypasses_encryption() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = create_in(&dir);
+        db.write_plaintext("raw", "value").unwrap();
+        let stored = db.sled.get(namespaced(PLAINTEXT, b"raw")).unwrap();
+        assert_eq!(stored.unwrap(), "value");
+        // no secret is needed for the plaintext entries.
+        assert_eq!(db.read_plaintext("raw").unwrap().unwrap(), "value");
+
+        db.set_secret(SecretString::new(String::from("password")));
+        db.write("enc", "value").unwrap();
+        let stored = db.sled.get(namespaced(ENCRYPTED, b"enc")).unwrap();
+        let stored = stored.unwrap();
+        assert!(!stored.windows(5).any(|w| w == b"value"));
+    }
+
+    #[test]
+    fn remove_deletes_both_namespaces() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = create_in(&dir);
+        db.set_secret(SecretString::new(String::from("password")));
+        db.write("key", "secret").unwrap();
+        db.sled
+            .insert(namespaced(PLAINTEXT, b"key"), "value")
+            .unwrap();
+        assert!(db.remove("key").unwrap().is_some());
+        assert!(!db.sled.contains_key(namespaced(ENCRYPTED, b"key")).unwrap());
+        assert!(!db.sled.contains_key(namespaced(PLAINTEXT, b"key")).unwrap());
+        assert_eq!(db.remove("key").unwrap(), None);
+    }
+
+    #[test]
+    fn reading_without_a_secret_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = create_in(&dir);
+        db.set_secret(SecretString::new(String::from("password")));
+        db.write("key", "value").unwrap();
+        drop(db);
+
+        let db = reopen_in(&dir);
+        let err = db.read("key").unwrap_err();
+        assert!(format!("{:#}", err).contains("password must be provided"));
+    }
+
+    #[test]
+    fn profile_names_stay_in_the_profiles_dir() {
+        let dirs = ProjectDirs::from("tools", "webb", "webb-cli-test").unwrap();
+        let profiles = dirs.data_dir().join("profiles");
+        let dir = profile_dir(&dirs, "test-net_2").unwrap();
+        assert_eq!(dir, profiles.join("test-net_2"));
+        for name in &["", "..", "../default", "a/b", "a\\b", "main net"] {
+            assert!(profile_dir(&dirs, name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn plaintext_is_not_read_as_encrypted() {
+        let db = temporary_with_secret();
+        db.write_plaintext("key", "value").unwrap();
+        assert!(db.read("key").is_err());
+        assert_eq!(db.read_plaintext("key").unwrap().unwrap(), "value");
+    }
+
+    #[test]
+    fn encrypted_is_not_read_as_plaintext() {
+        let db = temporary_with_secret();
+        db.write("key", "value").unwrap();
+        assert!(db.read_plaintext("key").is_err());
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+        db.remove("key").unwrap();
+        assert_eq!(db.read("key").unwrap(), None);
+        assert_eq!(db.read_plaintext("key").unwrap(), None);
+    }
+
+    #[test]
+    fn decrypted_values_are_zeroizing() {
+        let db = temporary_with_secret();
+        db.write("key", "value").unwrap();
+        let plaintext: Zeroizing<Vec<u8>> = db.read("key").unwrap().unwrap();
+        assert_eq!(plaintext.as_slice(), b"value");
+    }
+
+    #[test]
+    fn tells_a_wrong_password_from_a_corrupt_record() {
+        let mut db = temporary_with_secret();
+        db.write("key", "value").unwrap();
+        db.sled
+            .insert(namespaced(ENCRYPTED, b"garbled"), &b"garbage"[..])
+            .unwrap();
+        let err = db.read("garbled").unwrap_err();
+        assert!(format!("{:#}", err).contains("corrupt record"));
+
+        db.set_secret(SecretString::new(String::from("wrong")));
+        let err = db.read("key").unwrap_err();
+        assert!(format!("{:#}", err).ends_with("wrong password"));
+    }
+
+    #[test]
+    fn legacy_records_get_a_header() {
+        let db = temporary_with_secret();
+        let record = encrypt(&db.key().unwrap(), b"value").unwrap();
+        let key = namespaced(ENCRYPTED, b"key");
+        db.sled.insert(&key, &record[HEADER_LEN..]).unwrap();
+
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+        let migrated = db.sled.get(&key).unwrap().unwrap();
+        assert!(!is_legacy_record(&migrated));
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+    }
+
+    #[test]
+    fn migrates_flat_keys() {
+        let db = temporary_with_secret();
+        let encrypted = encrypt(&db.key().unwrap(), b"secret").unwrap();
+        db.sled.insert("uuid_seed", encrypted).unwrap();
+        db.sled.insert("uuid", "metadata").unwrap();
+        let is_encrypted = |key: &[u8]| key.ends_with(b"_seed");
+
+        assert_eq!(db.migrate_flat_keys(is_encrypted).unwrap(), 2);
+        assert_eq!(*db.read("uuid_seed").unwrap().unwrap(), b"secret");
+        assert_eq!(db.read_plaintext("uuid").unwrap().unwrap(), "metadata");
+        assert!(!db.sled.contains_key("uuid").unwrap());
+        // already migrated.
+        assert_eq!(db.migrate_flat_keys(is_encrypted).unwrap(), 0);
+    }
+
+    #[test]
+    fn reopens_argon2_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let secret = || SecretString::new(String::from("password"));
+        let kdf = {
+            let db = sled::open(&path).unwrap();
+            let mut db =
+                SledDatastore::from_db(db, SledDatastore::TEST_KDF_PARAMS)
+                    .unwrap();
+            assert!(matches!(db.kdf(), Kdf::Argon2id { .. }));
+            db.set_secret(secret());
+            db.write("key", "value").unwrap();
+            db.kdf().clone()
+        };
+        // the recorded params win over the ones for new stores.
+        let mut db = reopen_in(&dir);
+        assert_eq!(db.kdf(), &kdf);
+        db.set_secret(secret());
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+        db.set_secret(SecretString::new(String::from("wrong")));
+        assert!(db.read("key").is_err());
+    }
+
+    #[test]
+    fn legacy_store_upgrades_to_argon2() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let legacy_key = Kdf::Sha256
+            .derive(&SecretString::new(String::from("password")))
+            .unwrap();
+        db.insert(
+            namespaced(ENCRYPTED, b"key"),
+            encrypt(&legacy_key, b"value").unwrap(),
+        )
+        .unwrap();
+        let mut db =
+            SledDatastore::from_db(db, SledDatastore::TEST_KDF_PARAMS).unwrap();
+        assert_eq!(db.kdf(), &Kdf::Sha256);
+        db.set_secret(SecretString::new(String::from("password")));
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+
+        let old = SecretString::new(String::from("password"));
+        let new = SecretString::new(String::from("new password"));
+        assert_eq!(db.reencrypt(old, new).unwrap(), 1);
+        assert!(matches!(db.kdf(), Kdf::Argon2id { .. }));
+        assert_eq!(*db.read("key").unwrap().unwrap(), b"value");
+        let recorded = db.read_plaintext(KDF_KEY).unwrap().unwrap();
+        assert_eq!(&Kdf::decode(&recorded).unwrap(), db.kdf());
+    }
+
+    #[test]
+    fn reencrypt_checks_every_entry() {
+        let mut db = temporary_with_secret();
+        db.write("a", "value").unwrap();
+        let old = || SecretString::new(String::from("password"));
+        let wrong = || SecretString::new(String::from("wrong"));
+        let new = || SecretString::new(String::from("new password"));
+        let sealed = db.read_sealed("a").unwrap().unwrap();
+
+        assert!(db.reencrypt(wrong(), new()).is_err());
+        assert_eq!(db.read_sealed("a").unwrap().unwrap(), sealed);
+        // an entry encrypted with another password aborts it all.
+        db.set_secret(SecretString::new(String::from("other")));
+        db.write("b", "other value").unwrap();
+        assert!(db.reencrypt(old(), new()).is_err());
+        assert_eq!(db.read_sealed("a").unwrap().unwrap(), sealed);
+
+        db.set_secret(old());
+        assert!(db
+            .reencrypt_from("b", SecretString::new(String::from("other")))
+            .unwrap());
+        assert_eq!(db.reencrypt(old(), new()).unwrap(), 2);
+        assert_eq!(*db.read("a").unwrap().unwrap(), b"value");
+        assert_eq!(*db.read("b").unwrap().unwrap(), b"other value");
+    }
+
+    #[test]
+    fn legacy_store_keeps_its_kdf_until_everything_is_readable() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let legacy_key = |password: &str| {
+            Kdf::Sha256
+                .derive(&SecretString::new(String::from(password)))
+                .unwrap()
+        };
+        db.insert(
+            namespaced(ENCRYPTED, b"a"),
+            encrypt(&legacy_key("password"), b"value").unwrap(),
+        )
+        .unwrap();
+        db.insert(
+            namespaced(ENCRYPTED, b"b"),
+            encrypt(&legacy_key("other"), b"other value").unwrap(),
+        )
+        .unwrap();
+        let mut db =
+            SledDatastore::from_db(db, SledDatastore::TEST_KDF_PARAMS).unwrap();
+        db.set_secret(SecretString::new(String::from("password")));
+        let old = || SecretString::new(String::from("password"));
+        let new = || SecretString::new(String::from("new password"));
+
+        assert!(db.reencrypt(old(), new()).is_err());
+        assert_eq!(db.kdf(), &Kdf::Sha256);
+        assert!(db.read_plaintext(KDF_KEY).unwrap().is_none());
+
+        // an entry left behind under the legacy kdf is still recoverable
+        // once the store switched to Argon2id.
+        db.sled.remove(namespaced(ENCRYPTED, b"b")).unwrap();
+        assert_eq!(db.reencrypt(old(), new()).unwrap(), 1);
+        assert!(matches!(db.kdf(), Kdf::Argon2id { .. }));
+        db.sled
+            .insert(
+                namespaced(ENCRYPTED, b"b"),
+                encrypt(&legacy_key("other"), b"other value").unwrap(),
+            )
+            .unwrap();
+        let other = SecretString::new(String::from("other"));
+        assert!(db.reencrypt_from("b", other).unwrap());
+        assert_eq!(*db.read("b").unwrap().unwrap(), b"other value");
+        assert_eq!(*db.read("a").unwrap().unwrap(), b"value");
     }
 }
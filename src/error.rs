@@ -8,12 +8,33 @@ pub enum Error {
     Subxt(#[from] subxt::Error),
     #[error("Mnemonic: {}", _0)]
     Mnemonic(String),
+    #[error("Unknown Mnemonic Word #{position} `{word}`, {hint}")]
+    UnknownMnemonicWord {
+        position: usize,
+        word: String,
+        hint: String,
+    },
+    #[error(
+        "Invalid Mnemonic Length: expected 12, 15, 18, 21 or 24 words but \
+         got {}, check for a missing or an extra word",
+        _0
+    )]
+    InvalidMnemonicLength(usize),
+    #[error(
+        "Invalid Mnemonic Checksum: the words are known, but one of them is \
+         probably mistyped or out of order"
+    )]
+    InvalidMnemonicChecksum,
+    #[error("Invalid Derivation Path: {}", _0)]
+    InvalidDerivationPath(String),
     #[error("Secret: {:?}", _0)]
     SecretString(subxt::sp_core::crypto::SecretStringError),
     #[error("Bad Ss58: {:?}", _0)]
     Public(subxt::sp_core::crypto::PublicError),
     #[error(transparent)]
     Hex(#[from] hex::FromHexError),
+    #[error("Unsupported Key Scheme: {}", _0)]
+    UnsupportedKeyScheme(String),
     #[error("Unsupported Token Symbol: {}", _0)]
     UnsupportedTokenSymbol(String),
     #[error("Unsupported Note Version: {}", _0)]
@@ -26,8 +47,20 @@ pub enum Error {
     InvalidNoteMixerId,
     #[error("Invalid Note Block Number")]
     InvalidNoteBlockNumber,
+    #[error("Invalid Note Leaf Index")]
+    InvalidNoteLeafIndex,
+    #[error("Invalid Note Amount")]
+    InvalidNoteAmount,
     #[error("Invalid Note Footer")]
     InvalidNoteFooter,
+    #[error("Invalid Note Fields for the {} version", _0)]
+    InvalidNoteFields(String),
+    #[error("Invalid Note Secret")]
+    InvalidNoteSecret,
     #[error("not A 32 bytes array")]
     NotA32BytesArray,
+    #[error("Note is not deposited in the mixer tree")]
+    NoteNotInTree,
+    #[error("Transaction failed: {}", _0)]
+    DispatchFailed(subxt::RuntimeError),
 }
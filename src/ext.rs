@@ -3,20 +3,42 @@ use std::str::FromStr;
 
 use dialoguer::theme::Theme;
 
+use crate::context::ExecutionContext;
+
+/// Fails if the prompts are disabled (see [ExecutionContext::can_prompt]),
+/// `what` is the missing argument we were about to prompt for.
+pub fn ensure_can_prompt(
+    context: &ExecutionContext,
+    what: &str,
+) -> anyhow::Result<()> {
+    if context.can_prompt() {
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "missing {}, prompts are disabled by `--non-interactive` or \
+             `--json`",
+            what
+        )
+    }
+}
+
 pub trait OptionPromptExt {
     type Output: FromStr;
     fn unwrap_or_prompt(
         self,
+        context: &ExecutionContext,
         prompt: &str,
         theme: &impl Theme,
     ) -> anyhow::Result<Self::Output>;
     fn unwrap_or_prompt_password(
         self,
+        context: &ExecutionContext,
         prompt: &str,
         theme: &impl Theme,
     ) -> anyhow::Result<Self::Output>;
     fn unwrap_or_prompt_password_with_confirmation(
         self,
+        context: &ExecutionContext,
         prompt: &str,
         theme: &impl Theme,
     ) -> anyhow::Result<Self::Output>;
@@ -31,12 +53,14 @@ where
 
     fn unwrap_or_prompt(
         self,
+        context: &ExecutionContext,
         prompt: &str,
         theme: &impl Theme,
     ) -> anyhow::Result<Self::Output> {
         if let Some(val) = self {
             Ok(val)
         } else {
+            ensure_can_prompt(context, prompt)?;
             let term = console::Term::stdout();
             let s: String = dialoguer::Input::with_theme(theme)
                 .with_prompt(prompt)
@@ -49,12 +73,14 @@ where
 
     fn unwrap_or_prompt_password(
         self,
+        context: &ExecutionContext,
         prompt: &str,
         theme: &impl Theme,
     ) -> anyhow::Result<Self::Output> {
         if let Some(val) = self {
             Ok(val)
         } else {
+            ensure_can_prompt(context, prompt)?;
             let term = console::Term::stdout();
             let s: String = dialoguer::Password::with_theme(theme)
                 .with_prompt(prompt)
@@ -66,12 +92,14 @@ where
 
     fn unwrap_or_prompt_password_with_confirmation(
         self,
+        context: &ExecutionContext,
         prompt: &str,
         theme: &impl Theme,
     ) -> anyhow::Result<Self::Output> {
         if let Some(val) = self {
             Ok(val)
         } else {
+            ensure_can_prompt(context, prompt)?;
             let term = console::Term::stdout();
             let s: String = dialoguer::Password::with_theme(theme)
                 .with_prompt(prompt)
@@ -82,3 +110,30 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dialoguer::theme::SimpleTheme;
+
+    use super::*;
+
+    #[test]
+    fn missing_values_are_errors_without_prompts() {
+        let mut context = ExecutionContext::temporary();
+        context.set_prompts(false);
+        let error = Option::<String>::None
+            .unwrap_or_prompt(&context, "Note Alias", &SimpleTheme)
+            .unwrap_err();
+        assert!(error.to_string().contains("missing Note Alias"));
+        let password = Option::<String>::None.unwrap_or_prompt_password(
+            &context,
+            "Password",
+            &SimpleTheme,
+        );
+        assert!(password.is_err());
+        let alias = Some(String::from("alice"))
+            .unwrap_or_prompt(&context, "Note Alias", &SimpleTheme)
+            .unwrap();
+        assert_eq!(alias, "alice");
+    }
+}
@@ -1,43 +1,335 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+
+use bip39::{ErrorKind, Language, Mnemonic};
+use subxt::extrinsic::SignedPayload;
+use subxt::sp_core::crypto::{AccountId32, DeriveJunction};
+use subxt::sp_core::ed25519::Pair as Ed25519Pair;
 use subxt::sp_core::sr25519::Pair as Sr25519Pair;
 use subxt::sp_core::Pair;
+use subxt::sp_runtime::traits::IdentifyAccount;
+use subxt::sp_runtime::MultiSigner;
+use subxt::{PairSigner, Signer, UncheckedExtrinsic};
 use zeroize::Zeroize;
 
 use crate::error::Error;
+use crate::runtime::WebbRuntime;
 
 /// Public key type for Runtime
 pub type PublicFor<P> = <P as subxt::sp_core::Pair>::Public;
 /// Seed type for Runtime
 pub type SeedFor<P> = <P as subxt::sp_core::Pair>::Seed;
 
+/// The signature scheme of a [KeyPair].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyScheme {
+    Sr25519,
+    Ed25519,
+}
+
+impl Default for KeyScheme {
+    fn default() -> Self { Self::Sr25519 }
+}
+
+impl fmt::Display for KeyScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyScheme::Sr25519 => write!(f, "sr25519"),
+            KeyScheme::Ed25519 => write!(f, "ed25519"),
+        }
+    }
+}
+
+impl FromStr for KeyScheme {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sr25519" => Ok(KeyScheme::Sr25519),
+            "ed25519" => Ok(KeyScheme::Ed25519),
+            v => Err(Error::UnsupportedKeyScheme(v.to_owned())),
+        }
+    }
+}
+
+/// A key pair of one of the [KeyScheme]s.
+#[derive(Clone)]
+pub enum SchemePair {
+    Sr25519(Sr25519Pair),
+    Ed25519(Ed25519Pair),
+}
+
+impl SchemePair {
+    /// The pair of the `scheme` from its 32 bytes `seed`.
+    pub fn from_seed_slice(
+        scheme: KeyScheme,
+        seed: &[u8],
+    ) -> Result<Self, Error> {
+        match scheme {
+            KeyScheme::Sr25519 => Sr25519Pair::from_seed_slice(seed)
+                .map(SchemePair::Sr25519)
+                .map_err(Error::SecretString),
+            KeyScheme::Ed25519 => Ed25519Pair::from_seed_slice(seed)
+                .map(SchemePair::Ed25519)
+                .map_err(Error::SecretString),
+        }
+    }
+
+    pub fn scheme(&self) -> KeyScheme {
+        match self {
+            SchemePair::Sr25519(_) => KeyScheme::Sr25519,
+            SchemePair::Ed25519(_) => KeyScheme::Ed25519,
+        }
+    }
+
+    /// The account id of the pair, which is its public key for both of the
+    /// schemes.
+    pub fn account_id(&self) -> AccountId32 {
+        let signer = match self {
+            SchemePair::Sr25519(pair) => MultiSigner::from(pair.public()),
+            SchemePair::Ed25519(pair) => MultiSigner::from(pair.public()),
+        };
+        signer.into_account()
+    }
+
+    /// Derives the pair using the `//hard/soft` derivation `path`.
+    ///
+    /// ed25519 only supports the hard junctions.
+    pub fn derive(&self, path: &str) -> Result<Self, Error> {
+        let junctions = parse_derivation_path(path)?.into_iter();
+        match self {
+            SchemePair::Sr25519(pair) => match pair.derive(junctions, None) {
+                Ok((pair, _)) => Ok(SchemePair::Sr25519(pair)),
+                Err(never) => match never {},
+            },
+            SchemePair::Ed25519(pair) => pair
+                .derive(junctions, None)
+                .map(|(pair, _)| SchemePair::Ed25519(pair))
+                .map_err(|_| Error::InvalidDerivationPath(path.to_owned())),
+        }
+    }
+
+    /// A signer for the extrinsics, using this pair.
+    pub fn signer(&self) -> AccountSigner {
+        match self {
+            SchemePair::Sr25519(pair) => {
+                AccountSigner::Sr25519(PairSigner::new(pair.clone()))
+            },
+            SchemePair::Ed25519(pair) => {
+                AccountSigner::Ed25519(PairSigner::new(pair.clone()))
+            },
+        }
+    }
+}
+
+/// Signs the extrinsics using a pair of either of the [KeyScheme]s.
+#[derive(Clone)]
+pub enum AccountSigner {
+    Sr25519(PairSigner<WebbRuntime, Sr25519Pair>),
+    Ed25519(PairSigner<WebbRuntime, Ed25519Pair>),
+}
+
+impl Signer<WebbRuntime> for AccountSigner {
+    fn account_id(&self) -> &AccountId32 {
+        match self {
+            AccountSigner::Sr25519(signer) => signer.account_id(),
+            AccountSigner::Ed25519(signer) => signer.account_id(),
+        }
+    }
+
+    fn nonce(&self) -> Option<u32> {
+        match self {
+            AccountSigner::Sr25519(signer) => signer.nonce(),
+            AccountSigner::Ed25519(signer) => signer.nonce(),
+        }
+    }
+
+    fn sign(
+        &self,
+        extrinsic: SignedPayload<WebbRuntime>,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<UncheckedExtrinsic<WebbRuntime>, String>>
+                + Send,
+        >,
+    > {
+        match self {
+            AccountSigner::Sr25519(signer) => signer.sign(extrinsic),
+            AccountSigner::Ed25519(signer) => signer.sign(extrinsic),
+        }
+    }
+}
+
 pub struct KeyPair {
-    pair: Sr25519Pair,
+    pair: SchemePair,
     phrase: Option<String>,
-    seed: SeedFor<Sr25519Pair>,
+    seed: [u8; 32],
+    path: Option<String>,
+}
+
+/// Splits a `<phrase>//hard/soft` input, like the ones of Polkadot.js, into
+/// the phrase and its derivation path.
+pub fn split_derivation_path(input: &str) -> (&str, Option<&str>) {
+    match input.find('/') {
+        Some(i) => (input[..i].trim(), Some(input[i..].trim())),
+        None => (input.trim(), None),
+    }
+}
+
+/// Parses an english mnemonic `phrase`, telling apart an unknown word, a
+/// wrong number of words and a wrong checksum.
+pub fn parse_mnemonic(phrase: &str) -> Result<Mnemonic, Error> {
+    let wordmap = Language::English.wordmap();
+    let unknown = phrase
+        .split_whitespace()
+        .enumerate()
+        .find(|(_, word)| wordmap.get_bits(word).is_err());
+    if let Some((i, word)) = unknown {
+        return Err(Error::UnknownMnemonicWord {
+            position: i + 1,
+            word: word.to_owned(),
+            hint: spelling_hint(word),
+        });
+    }
+    Mnemonic::from_phrase(phrase, Language::English).map_err(|e| match e
+        .downcast_ref::<ErrorKind>(
+    ) {
+        Some(ErrorKind::InvalidWordLength(n)) => {
+            Error::InvalidMnemonicLength(*n)
+        },
+        Some(ErrorKind::InvalidChecksum) => Error::InvalidMnemonicChecksum,
+        _ => Error::Mnemonic(e.to_string()),
+    })
+}
+
+/// Suggests the known words close to an unknown `word`, the english words
+/// are unique by their first four letters.
+fn spelling_hint(word: &str) -> String {
+    let wordlist = Language::English.wordlist();
+    let word = word.to_lowercase();
+    let candidates = (1..=word.len().min(4))
+        .rev()
+        .filter_map(|n| word.get(..n))
+        .map(|prefix| wordlist.get_words_by_prefix(prefix))
+        .find(|words| !words.is_empty() && words.len() <= 4);
+    match candidates {
+        Some([only]) => format!("did you mean `{}`?", only),
+        Some(words) => format!("did you mean one of `{}`?", words.join("`, `")),
+        None => String::from("check its spelling"),
+    }
+}
+
+/// Parses a `//hard/soft` derivation path into its junctions.
+pub fn parse_derivation_path(path: &str) -> Result<Vec<DeriveJunction>, Error> {
+    let invalid = || Error::InvalidDerivationPath(path.to_owned());
+    let mut junctions = Vec::new();
+    let mut rest = path;
+    while !rest.is_empty() {
+        rest = rest.strip_prefix('/').ok_or_else(invalid)?;
+        let hard = rest.starts_with('/');
+        if hard {
+            rest = &rest[1..];
+        }
+        let end = rest.find('/').unwrap_or_else(|| rest.len());
+        let code = &rest[..end];
+        if code.is_empty() {
+            return Err(invalid());
+        }
+        // a leading `/` is how sp_core marks a hard junction.
+        let junction = if hard {
+            DeriveJunction::from(format!("/{}", code))
+        } else {
+            DeriveJunction::from(code)
+        };
+        junctions.push(junction);
+        rest = &rest[end..];
+    }
+    if junctions.is_empty() {
+        return Err(invalid());
+    }
+    Ok(junctions)
 }
 
 impl KeyPair {
+    /// Generates a new sr25519 pair, see [KeyPair::generate].
     pub fn new(password: Option<&str>) -> Self {
-        let (pair, phrase, seed) = Sr25519Pair::generate_with_phrase(password);
+        Self::generate(KeyScheme::Sr25519, password)
+    }
+
+    /// Generates a new pair of the `scheme`, along with its phrase.
+    pub fn generate(scheme: KeyScheme, password: Option<&str>) -> Self {
+        let (pair, phrase, seed) = match scheme {
+            KeyScheme::Sr25519 => {
+                let (pair, phrase, seed) =
+                    Sr25519Pair::generate_with_phrase(password);
+                (SchemePair::Sr25519(pair), phrase, seed)
+            },
+            KeyScheme::Ed25519 => {
+                let (pair, phrase, seed) =
+                    Ed25519Pair::generate_with_phrase(password);
+                (SchemePair::Ed25519(pair), phrase, seed)
+            },
+        };
         KeyPair {
             pair,
             phrase: Some(phrase),
             seed,
+            path: None,
         }
     }
 
+    /// Restores the sr25519 pair of the `phrase`, see
+    /// [KeyPair::restore_with_scheme].
     pub fn restore(
         phrase: &str,
         password: Option<&str>,
     ) -> Result<Self, Error> {
-        let (pair, seed) = Sr25519Pair::from_phrase(phrase, password)
-            .map_err(Error::SecretString)?;
-        Ok(KeyPair {
+        Self::restore_with_scheme(KeyScheme::Sr25519, phrase, password)
+    }
+
+    /// Restores the `scheme` pair of the `phrase`, which could end with a
+    /// `//hard/soft` derivation path.
+    pub fn restore_with_scheme(
+        scheme: KeyScheme,
+        phrase: &str,
+        password: Option<&str>,
+    ) -> Result<Self, Error> {
+        let (phrase, path) = split_derivation_path(phrase);
+        parse_mnemonic(phrase)?;
+        let (pair, seed) = match scheme {
+            KeyScheme::Sr25519 => Sr25519Pair::from_phrase(phrase, password)
+                .map(|(pair, seed)| (SchemePair::Sr25519(pair), seed)),
+            KeyScheme::Ed25519 => Ed25519Pair::from_phrase(phrase, password)
+                .map(|(pair, seed)| (SchemePair::Ed25519(pair), seed)),
+        }
+        .map_err(Error::SecretString)?;
+        let keys = KeyPair {
             pair,
             phrase: Some(phrase.to_owned()),
             seed,
-        })
+            path: None,
+        };
+        match path {
+            Some(path) => keys.derive(path),
+            None => Ok(keys),
+        }
+    }
+
+    /// Derives the pair using the `//hard/soft` derivation `path`.
+    ///
+    /// the seed and the phrase stay the ones of the root pair, since the
+    /// derived pair can't be restored without the path anyway.
+    pub fn derive(mut self, path: &str) -> Result<Self, Error> {
+        self.pair = self.pair.derive(path)?;
+        self.path = Some(path.to_owned());
+        Ok(self)
     }
 
+    /// The derivation path of the pair, if it was derived.
+    pub fn path(&self) -> Option<&str> { self.path.as_deref() }
+
     pub fn backup(&self) -> Option<String> { self.phrase.clone() }
 
     pub fn clean(mut self) {
@@ -46,18 +338,33 @@ impl KeyPair {
         drop(self.pair);
     }
 
-    pub fn public(&self) -> PublicFor<Sr25519Pair> { self.pair.public() }
+    pub fn scheme(&self) -> KeyScheme { self.pair.scheme() }
+
+    pub fn account_id(&self) -> AccountId32 { self.pair.account_id() }
 
-    pub fn pair(&self) -> &Sr25519Pair { &self.pair }
+    pub fn pair(&self) -> &SchemePair { &self.pair }
 
-    pub fn seed(&self) -> SeedFor<Sr25519Pair> { self.seed }
+    pub fn seed(&self) -> [u8; 32] { self.seed }
 
+    /// An sr25519 pair from its `seed`, see [KeyPair::init_with_scheme].
     pub fn init(seed: [u8; 32]) -> Self {
-        let pair = Sr25519Pair::from_seed(&seed);
+        Self::init_with_scheme(KeyScheme::Sr25519, seed)
+    }
+
+    pub fn init_with_scheme(scheme: KeyScheme, seed: [u8; 32]) -> Self {
+        let pair = match scheme {
+            KeyScheme::Sr25519 => {
+                SchemePair::Sr25519(Sr25519Pair::from_seed(&seed))
+            },
+            KeyScheme::Ed25519 => {
+                SchemePair::Ed25519(Ed25519Pair::from_seed(&seed))
+            },
+        };
         KeyPair {
             pair,
             seed,
             phrase: None,
+            path: None,
         }
     }
 }
@@ -65,7 +372,6 @@ impl KeyPair {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use subxt::sp_core::Pair;
 
     #[test]
     fn create() {
@@ -78,7 +384,7 @@ mod tests {
     fn init() {
         let keypair = KeyPair::new(Some("super-secret"));
         let keypair2 = KeyPair::init(keypair.seed());
-        assert_eq!(keypair.pair().public(), keypair2.pair().public());
+        assert_eq!(keypair.account_id(), keypair2.account_id());
         keypair.clean();
         keypair2.clean();
     }
@@ -87,8 +393,160 @@ mod tests {
         let keypair = KeyPair::new(Some("super-secret"));
         let phrase = keypair.backup().unwrap();
         let keypair2 = KeyPair::restore(&phrase, Some("super-secret")).unwrap();
-        assert_eq!(keypair.pair().public(), keypair2.pair().public());
+        assert_eq!(keypair.account_id(), keypair2.account_id());
         keypair.clean();
         keypair2.clean();
     }
+
+    const DEV_PHRASE: &str =
+        "bottom drive obey lake curtain smoke basket hold race lonely fit walk";
+
+    #[test]
+    fn tells_apart_the_mnemonic_errors() {
+        assert!(parse_mnemonic(DEV_PHRASE).is_ok());
+        let typo = DEV_PHRASE.replace("obey", "obez");
+        match parse_mnemonic(&typo) {
+            Err(e @ Error::UnknownMnemonicWord { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "Unknown Mnemonic Word #3 `obez`, did you mean `obey`?"
+                );
+            },
+            other => panic!("unexpected {:?}", other.map(|_| ())),
+        }
+        let short = DEV_PHRASE.replace(" walk", "");
+        assert!(matches!(
+            parse_mnemonic(&short),
+            Err(Error::InvalidMnemonicLength(11))
+        ));
+        // known words, but not the right ones.
+        let swapped =
+            DEV_PHRASE.replace("walk", "fit").replacen("fit", "walk", 1);
+        assert!(matches!(
+            parse_mnemonic(&swapped),
+            Err(Error::InvalidMnemonicChecksum)
+        ));
+        assert!(matches!(
+            KeyPair::restore(&typo, None),
+            Err(Error::UnknownMnemonicWord { .. })
+        ));
+    }
+
+    #[test]
+    fn restore_with_derivation_path() {
+        let alice =
+            KeyPair::restore(&format!("{}//Alice", DEV_PHRASE), None).unwrap();
+        assert_eq!(
+            alice.account_id().to_string(),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+        assert_eq!(alice.path(), Some("//Alice"));
+        assert_eq!(alice.backup().as_deref(), Some(DEV_PHRASE));
+        let stash =
+            KeyPair::restore(&format!("{}//Alice//stash", DEV_PHRASE), None)
+                .unwrap();
+        assert_eq!(
+            stash.account_id().to_string(),
+            "5GNJqTPyNqANBkUVMN1LPPrxXnFouWXoe2wNSmmEoLctxiZY"
+        );
+        // the seed is the root one, deriving it again gives the same pair.
+        let again = KeyPair::init(stash.seed())
+            .derive("//Alice//stash")
+            .unwrap();
+        assert_eq!(again.account_id(), stash.account_id());
+        alice.clean();
+        stash.clean();
+        again.clean();
+    }
+
+    #[test]
+    fn restore_without_derivation_path() {
+        let root = KeyPair::restore(DEV_PHRASE, None).unwrap();
+        assert_eq!(
+            root.account_id().to_string(),
+            "5DfhGyQdFobKM8NsWvEeAKk5EQQgYe9AydgJ7rMB6E1EqRzV"
+        );
+        assert_eq!(root.path(), None);
+        root.clean();
+    }
+
+    /// Signs a message with the `keys` and checks the signature.
+    fn sign_round_trip(keys: &KeyPair) -> bool {
+        let message = b"webb";
+        match keys.pair() {
+            SchemePair::Sr25519(pair) => {
+                let signature = pair.sign(message);
+                Sr25519Pair::verify(&signature, message, &pair.public())
+            },
+            SchemePair::Ed25519(pair) => {
+                let signature = pair.sign(message);
+                Ed25519Pair::verify(&signature, message, &pair.public())
+            },
+        }
+    }
+
+    #[test]
+    fn both_schemes_round_trip() {
+        for &scheme in &[KeyScheme::Sr25519, KeyScheme::Ed25519] {
+            let keys = KeyPair::generate(scheme, None);
+            assert_eq!(keys.scheme(), scheme);
+            assert!(sign_round_trip(&keys));
+            let phrase = keys.backup().unwrap();
+            let restored =
+                KeyPair::restore_with_scheme(scheme, &phrase, None).unwrap();
+            assert_eq!(restored.account_id(), keys.account_id());
+            let init = KeyPair::init_with_scheme(scheme, keys.seed());
+            assert_eq!(init.account_id(), keys.account_id());
+            assert!(sign_round_trip(&init));
+            let signer = init.pair().signer();
+            assert_eq!(signer.account_id(), &keys.account_id());
+            keys.clean();
+            restored.clean();
+            init.clean();
+        }
+    }
+
+    #[test]
+    fn the_same_phrase_differs_between_schemes() {
+        let ed25519 = KeyPair::restore_with_scheme(
+            KeyScheme::Ed25519,
+            &format!("{}//Alice", DEV_PHRASE),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            ed25519.account_id().to_string(),
+            "5FA9nQDVg267DEd8m1ZypXLBnvN7SFxYwV7ndqSYGiN9TTpu"
+        );
+        let sr25519 = KeyPair::restore(DEV_PHRASE, None).unwrap();
+        let root =
+            KeyPair::restore_with_scheme(KeyScheme::Ed25519, DEV_PHRASE, None)
+                .unwrap();
+        assert_ne!(root.account_id(), sr25519.account_id());
+        // ed25519 has no soft derivation.
+        assert!(root.derive("/soft").is_err());
+        assert_eq!("ed25519".parse::<KeyScheme>().unwrap(), KeyScheme::Ed25519);
+        assert!("ecdsa".parse::<KeyScheme>().is_err());
+    }
+
+    #[test]
+    fn derivation_paths() {
+        assert_eq!(parse_derivation_path("//hard/soft").unwrap().len(), 2);
+        assert_eq!(
+            parse_derivation_path("/0").unwrap(),
+            vec![DeriveJunction::soft(0u64)]
+        );
+        assert_eq!(
+            parse_derivation_path("//Alice").unwrap(),
+            vec![DeriveJunction::hard("Alice")]
+        );
+        for bad in &["", "Alice", "///Alice", "//Alice/", "//"] {
+            assert!(parse_derivation_path(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            split_derivation_path("a b c//x/y"),
+            ("a b c", Some("//x/y"))
+        );
+        assert_eq!(split_derivation_path(" a b c "), ("a b c", None));
+    }
 }
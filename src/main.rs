@@ -1,6 +1,10 @@
 use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Context;
+use console::{style, Emoji};
 use directories_next::ProjectDirs;
 use secrecy::SecretString;
 use structopt::StructOpt;
@@ -9,11 +13,15 @@ mod commands;
 mod context;
 mod database;
 mod ext;
+mod paper;
 mod raw;
+mod relayer;
+mod term;
+mod transaction;
 mod utils;
 
-use commands::{CommandExec, NodeOpts, PasswordOpts, SubCommand};
-use context::ExecutionContext;
+use commands::{CommandExec, NodeOpts, OutputOpts, PasswordOpts, SubCommand};
+use context::{ExecutionContext, OutputFormat};
 use database::SledDatastore;
 
 const PACKAGE_ID: [&str; 3] = ["tools", "webb", "webb-cli"];
@@ -43,12 +51,40 @@ struct Opts {
     /// and many other unsafe operations.
     #[structopt(global = true, long = "unsafe")]
     unsafe_flag: bool,
+    /// Never prompt, a missing argument or confirmation is an error
+    /// instead, for running the CLI from scripts and CI.
+    #[structopt(
+        global = true,
+        long = "non-interactive",
+        conflicts_with = "password-interactive"
+    )]
+    non_interactive: bool,
+    /// Copy all of the commands output to this file, along with the command
+    /// line, so it could be attached to a bug report.
+    ///
+    /// secrets passed as arguments are redacted and anything entered in the
+    /// prompts is never captured.
+    #[structopt(global = true, long, value_name = "PATH", parse(from_os_str))]
+    transcript: Option<PathBuf>,
+    /// The profile to use, each one has its own accounts and notes.
+    ///
+    /// handy to keep the testnet and the mainnet accounts apart.
+    #[structopt(
+        global = true,
+        long,
+        value_name = "NAME",
+        default_value = database::DEFAULT_PROFILE
+    )]
+    profile: String,
     /// Password Options.
     #[structopt(flatten)]
     password: PasswordOpts,
     /// Node Options.
     #[structopt(flatten)]
     node: NodeOpts,
+    /// Output Options.
+    #[structopt(flatten)]
+    output: OutputOpts,
     /// Sub-Commands.
     #[structopt(subcommand)]
     sub: SubCommand,
@@ -69,6 +105,13 @@ async fn main(args: Opts) -> anyhow::Result<()> {
         .format_timestamp(None)
         .filter_module("webb", log_level)
         .init();
+    if let SubCommand::Completions(ref cmd) = args.sub {
+        cmd.generate(Opts::clap(), &mut std::io::stdout())?;
+        return Ok(());
+    }
+    if let SubCommand::Man(ref cmd) = args.sub {
+        return cmd.generate(Opts::clap);
+    }
     log::debug!("Getting default dirs for webb cli");
     let dirs = ProjectDirs::from(
         crate::PACKAGE_ID[0],
@@ -78,48 +121,195 @@ async fn main(args: Opts) -> anyhow::Result<()> {
     .context("getting project data")?;
 
     log::debug!("our data dirs live in: {}", dirs.data_dir().display());
+    let home = database::profile_dir(&dirs, &args.profile)?;
     log::debug!("now let's try to get the account password");
     let db = if let Some(secret) = password(&args)? {
         log::debug!("now we have a secret, creating a secret datastore!");
-        SledDatastore::with_secret(secret)
+        SledDatastore::with_secret(&args.profile, secret)
     } else {
         log::debug!("no secrets provided, open the datastore anyway");
-        SledDatastore::new()
+        SledDatastore::new(&args.profile)
     }
     .context("failed to open the secret datastore!")?;
 
     log::debug!("creating an execution context for all of the commands");
-    let mut context = ExecutionContext::new(db, dirs, args.node.url)
+    let mut context = ExecutionContext::new(db, home)
         .context("create execution context for other commands")?;
-    match args.sub {
-        SubCommand::Show(cmd) => cmd.exec(&mut context).await?,
-        SubCommand::Default(cmd) => cmd.exec(&mut context).await?,
-        SubCommand::Account(cmd) => cmd.exec(&mut context).await?,
-        SubCommand::Mixer(cmd) => cmd.exec(&mut context).await?,
+    context.set_rpc_url(args.node.url);
+    context.set_unsafe(args.unsafe_flag);
+    context.set_rpc_concurrency(args.node.rpc_concurrency.get());
+    context.set_connect_timeout(Duration::from_secs(args.node.connect_timeout));
+    if args.output.json {
+        context.set_output(OutputFormat::Json);
+    } else {
+        context.set_output(args.output.format);
+    }
+    context.set_ss58_format(args.output.ss58_format);
+    if args.non_interactive || context.output() == OutputFormat::Json {
+        context.set_prompts(false);
+    }
+    if let Some(ref path) = args.node.chain_properties_file {
+        context.load_chain_properties(path)?;
+    }
+    if let Some(ref path) = args.transcript {
+        context.set_transcript(path)?;
+    }
+    let result = match args.sub {
+        SubCommand::Show(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Default(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Account(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Mixer(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Relayer(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Keystore(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Report(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Db(cmd) => cmd.exec(&mut context).await,
+        SubCommand::Completions(_) | SubCommand::Man(_) => {
+            unreachable!("handled above")
+        },
     };
+    // the warnings are reported even if the command failed, they may
+    // explain why.
+    print_warnings(&context)?;
+    result
+}
 
+/// Prints the warnings collected while running the command, as one block.
+///
+/// with the JSON output they are printed to stderr as a `warnings` array,
+/// so the command output on stdout stays a single JSON document.
+fn print_warnings(context: &ExecutionContext) -> anyhow::Result<()> {
+    let warnings = context.take_warnings();
+    if warnings.is_empty() {
+        return Ok(());
+    }
+    match context.output() {
+        OutputFormat::Human => {
+            let mut term = context.term();
+            writeln!(term)?;
+            writeln!(term, "{}", style("Warnings:").yellow().bold())?;
+            for warning in warnings {
+                writeln!(term, "  {} {}", Emoji("⚠️ ", "!"), warning)?;
+            }
+        },
+        OutputFormat::Json => {
+            let value = serde_json::json!({ "warnings": warnings });
+            eprintln!("{}", value);
+        },
+    }
     Ok(())
 }
 
 fn password(args: &Opts) -> anyhow::Result<Option<SecretString>> {
-    let password_opts = &args.password;
+    let env = std::env::var(PASSWORD_ENV).ok();
+    resolve_password(&args.password, args.unsafe_flag, env)
+}
+
+/// The env variable that could hold the datastore password, with `--unsafe`.
+const PASSWORD_ENV: &str = "WEBB_PASSWORD";
+
+/// Resolves the datastore password, the first one of these wins:
+/// `--password-interactive`, `--password-stdin`, `--password-filename`,
+/// `--password` and then the `env` value of [PASSWORD_ENV].
+///
+/// the last two are only allowed with `--unsafe`, and an empty `env` is the
+/// same as not having it.
+fn resolve_password(
+    password_opts: &PasswordOpts,
+    unsafe_flag: bool,
+    env: Option<String>,
+) -> anyhow::Result<Option<SecretString>> {
+    let env = env.filter(|v| !v.is_empty());
     if password_opts.password_interactive {
         let theme = dialoguer::theme::ColorfulTheme::default();
         let password = dialoguer::Password::with_theme(&theme)
             .with_prompt("Password")
             .interact()?;
         Ok(Some(SecretString::new(password)))
+    } else if password_opts.password_stdin {
+        let mut password = String::new();
+        std::io::stdin()
+            .read_to_string(&mut password)
+            .context("trying to read the password from stdin")?;
+        let len = password
+            .strip_suffix('\n')
+            .map(|p| p.strip_suffix('\r').unwrap_or(p))
+            .unwrap_or(&password)
+            .len();
+        password.truncate(len);
+        Ok(Some(SecretString::new(password)))
     } else if let Some(ref path) = password_opts.password_filename {
         let password = fs::read_to_string(path)
             .context("trying to read the password from the file")?;
         Ok(Some(SecretString::new(password)))
-    } else if password_opts.password.is_some() && args.unsafe_flag {
+    } else if password_opts.password.is_some() && unsafe_flag {
         log::warn!("using unsafe flag!!");
         // TODO(shekohex): emit a warning here about unsafe flag.
         Ok(password_opts.password.clone())
-    } else if password_opts.password.is_some() && !args.unsafe_flag {
+    } else if password_opts.password.is_some() && !unsafe_flag {
         anyhow::bail!(include_str!("messages/password_option.txt"));
+    } else if env.is_some() && unsafe_flag {
+        log::warn!("using unsafe flag!! reading {}", PASSWORD_ENV);
+        Ok(env.map(SecretString::new))
+    } else if env.is_some() && !unsafe_flag {
+        anyhow::bail!(
+            "{} is set, but reading the password from the environment is \
+             unsafe; re-run with `--unsafe`, or unset it",
+            PASSWORD_ENV
+        );
     } else {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::*;
+
+    fn opts() -> PasswordOpts {
+        PasswordOpts {
+            password_interactive: false,
+            password: None,
+            password_filename: None,
+            password_stdin: false,
+        }
+    }
+
+    fn resolved(
+        opts: &PasswordOpts,
+        unsafe_flag: bool,
+        env: Option<&str>,
+    ) -> anyhow::Result<Option<String>> {
+        let password =
+            resolve_password(opts, unsafe_flag, env.map(String::from))?;
+        Ok(password.map(|p| p.expose_secret().clone()))
+    }
+
+    #[test]
+    fn password_precedence() {
+        let pw = Some("env");
+        assert_eq!(resolved(&opts(), false, None).unwrap(), None);
+        assert_eq!(resolved(&opts(), true, Some("")).unwrap(), None);
+        assert_eq!(resolved(&opts(), false, Some("")).unwrap(), None);
+        assert_eq!(resolved(&opts(), true, pw).unwrap().unwrap(), "env");
+        assert!(resolved(&opts(), false, pw).is_err());
+
+        let inline = PasswordOpts {
+            password: Some(SecretString::new(String::from("inline"))),
+            ..opts()
+        };
+        assert_eq!(resolved(&inline, true, pw).unwrap().unwrap(), "inline");
+        assert!(resolved(&inline, false, None).is_err());
+
+        let path = std::env::temp_dir()
+            .join(format!("webb-password-{}", uuid::Uuid::new_v4()));
+        fs::write(&path, "file").unwrap();
+        let file = PasswordOpts {
+            password_filename: Some(path.clone()),
+            ..opts()
+        };
+        assert_eq!(resolved(&file, false, pw).unwrap().unwrap(), "file");
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::str::FromStr;
 
@@ -25,6 +25,8 @@ pub enum TokenSymbol {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NoteVersion {
     V1,
+    /// Adds the leaf `index` and the deposit `amount` to the note.
+    V2,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,12 +36,19 @@ pub struct Note {
     pub token_symbol: TokenSymbol,
     pub mixer_id: u32,
     pub block_number: Option<u32>,
+    /// The leaf index of the note in the mixer tree, only in
+    /// [NoteVersion::V2].
+    pub index: Option<u64>,
+    /// The deposit amount as a decimal string, with its decimals explicit
+    /// (i.e `1.000000000000`), only in [NoteVersion::V2].
+    pub amount: Option<String>,
     r: ScalarData,
     nullifier: ScalarData,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ZkProof {
+    pub root: ScalarData,
     pub comms: Vec<Commitment>,
     pub nullifier_hash: ScalarData,
     pub proof_bytes: Vec<u8>,
@@ -61,6 +70,7 @@ impl fmt::Display for NoteVersion {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             NoteVersion::V1 => write!(f, "v1"),
+            NoteVersion::V2 => write!(f, "v2"),
         }
     }
 }
@@ -75,6 +85,10 @@ impl fmt::Display for Note {
             format!("{}", self.token_symbol),
             format!("{}", self.mixer_id),
         ];
+        if let NoteVersion::V2 = self.version {
+            parts.push(format!("{}", self.index.unwrap_or_default()));
+            parts.push(self.amount.clone().unwrap_or_default());
+        }
         if let Some(bn) = self.block_number {
             parts.push(format!("{}", bn));
         }
@@ -101,6 +115,7 @@ impl FromStr for NoteVersion {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "v1" => Ok(NoteVersion::V1),
+            "v2" => Ok(NoteVersion::V2),
             v => Err(Error::UnsupportedNoteVersion(v.to_owned())),
         }
     }
@@ -111,55 +126,161 @@ impl FromStr for Note {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let parts: Vec<&str> = s.split('-').collect();
-        let partial = parts.len() == 5;
-        let full = parts.len() == 6;
-        if !partial && !full {
+        if parts.len() < 2 {
             return Err(Error::InvalidNoteLength);
         }
-
         if parts[0] != NOTE_PREFIX {
             return Err(Error::InvalidNotePrefix);
         }
 
+        // the fields after the mixer id depend on the version.
         let version: NoteVersion = parts[1].parse()?;
+        let versioned_fields = match version {
+            NoteVersion::V1 => 0,
+            NoteVersion::V2 => 2,
+        };
+        let partial = parts.len() == 5 + versioned_fields;
+        let full = parts.len() == 6 + versioned_fields;
+        if !partial && !full {
+            return Err(Error::InvalidNoteLength);
+        }
+
         let token_symbol: TokenSymbol = parts[2].parse()?;
         let mixer_id =
             parts[3].parse().map_err(|_| Error::InvalidNoteMixerId)?;
+        let (index, amount) = match version {
+            NoteVersion::V1 => (None, None),
+            NoteVersion::V2 => {
+                let index = parts[4]
+                    .parse()
+                    .map_err(|_| Error::InvalidNoteLeafIndex)?;
+                let amount = parts[5];
+                if !is_decimal(amount) {
+                    return Err(Error::InvalidNoteAmount);
+                }
+                (Some(index), Some(amount.to_owned()))
+            },
+        };
+        let rest = &parts[4 + versioned_fields..];
         let (block_number, note_val) = match partial {
-            true => (None, parts[4]),
+            true => (None, rest[0]),
             false => {
-                let bn = parts[4]
+                let bn = rest[0]
                     .parse()
                     .map_err(|_| Error::InvalidNoteBlockNumber)?;
-                (Some(bn), parts[5])
+                (Some(bn), rest[1])
             },
         };
         if note_val.len() != 128 {
             return Err(Error::InvalidNoteFooter);
         }
 
-        let r = hex::decode(&note_val[..64]).map(|v| {
-            v.try_into()
-                .map_err(|_| Error::NotA32BytesArray)
-                .map(ScalarData)
-        })??;
-        let nullifier = hex::decode(&note_val[64..]).map(|v| {
+        // decoded as a whole, since slicing a (pasted) non-ASCII footer
+        // in the middle of a char would panic.
+        let footer = hex::decode(note_val)?;
+        let (r, nullifier) = footer.split_at(32);
+        let to_scalar = |v: &[u8]| {
             v.try_into()
                 .map_err(|_| Error::NotA32BytesArray)
                 .map(ScalarData)
-        })??;
+        };
+        let r = to_scalar(r)?;
+        let nullifier = to_scalar(nullifier)?;
         Ok(Note {
             prefix: NOTE_PREFIX.to_owned(),
             version,
             token_symbol,
             mixer_id,
             block_number,
+            index,
+            amount,
             r,
             nullifier,
         })
     }
 }
 
+impl Note {
+    /// The note amount in base units along with its decimals, i.e
+    /// `1.500000000000` is `(1_500_000_000_000, 12)`.
+    ///
+    /// `None` if the note has no amount, or it overflows.
+    pub fn base_amount(&self) -> Option<(u128, u8)> {
+        let amount = self.amount.as_deref()?;
+        let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+        let decimals = u8::try_from(fraction.len()).ok()?;
+        let digits = [whole, fraction].concat();
+        Some((digits.parse().ok()?, decimals))
+    }
+
+    /// The note amount formatted for display with the token symbol, i.e
+    /// `1.500000000000` is `1.5 EDG`.
+    pub fn human_amount(&self) -> Option<String> {
+        let amount = self.amount.as_deref()?;
+        let (whole, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+        let whole = match whole.trim_start_matches('0') {
+            "" => "0",
+            whole => whole,
+        };
+        let value = match fraction.trim_end_matches('0') {
+            "" => whole.to_owned(),
+            fraction => format!("{}.{}", whole, fraction),
+        };
+        Some(format!("{} {}", value, self.token_symbol))
+    }
+
+    /// Checks that the note fields are consistent with each other, so a bad
+    /// note is rejected early, and not when it gets deposited.
+    ///
+    /// the note secrets must be canonical scalars, and the leaf index and
+    /// the amount must be there only (and always) in [NoteVersion::V2].
+    pub fn validate(&self) -> Result<(), Error> {
+        ensure_mixer_note(self)?;
+        match self.version {
+            NoteVersion::V1
+                if self.index.is_some() || self.amount.is_some() =>
+            {
+                return Err(Error::InvalidNoteFields(self.version.to_string()));
+            }
+            NoteVersion::V1 => {},
+            NoteVersion::V2 => {
+                if self.index.is_none() {
+                    return Err(Error::InvalidNoteLeafIndex);
+                }
+                match self.amount {
+                    Some(ref amount) if is_decimal(amount) => {},
+                    _ => return Err(Error::InvalidNoteAmount),
+                }
+            },
+        }
+        for secret in &[self.r, self.nullifier] {
+            if Scalar::from_canonical_bytes(secret.0).is_none() {
+                return Err(Error::InvalidNoteSecret);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Makes sure the `note` is a mixer note, the only kind that can be
+/// deposited to (or withdrawn from) the mixer pallet.
+pub fn ensure_mixer_note(note: &Note) -> Result<(), Error> {
+    if note.prefix != NOTE_PREFIX {
+        return Err(Error::InvalidNotePrefix);
+    }
+    Ok(())
+}
+
+/// Whether `s` is a decimal number, like `10` or `1.500`.
+fn is_decimal(s: &str) -> bool {
+    let digits =
+        |v: &str| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit());
+    match s.split_once('.') {
+        Some((whole, fraction)) => digits(whole) && digits(fraction),
+        None => digits(s),
+    }
+}
+
 pub struct Mixer {
     id: u32,
     tree: FixedDepositTree,
@@ -210,6 +331,8 @@ impl Mixer {
             token_symbol,
             mixer_id: self.id,
             block_number: None,
+            index: None,
+            amount: None,
             r: ScalarData(r.to_bytes()),
             nullifier: ScalarData(nullifier.to_bytes()),
         }
@@ -222,10 +345,16 @@ impl Mixer {
         ScalarData(leaf.to_bytes())
     }
 
+    /// Generates the withdraw zkProof of the `leaf` against the `root`.
+    ///
+    /// The `recipient` and `relayer` are the raw bytes of their account ids,
+    /// since they are bound to the proof.
     pub fn generate_proof(
         &mut self,
         root: ScalarData,
         leaf: ScalarData,
+        recipient: ScalarData,
+        relayer: ScalarData,
     ) -> ZkProof {
         let pc_gens = PedersenGens::default();
         let bp_gens = BulletproofGens::new(16400, 1);
@@ -234,8 +363,8 @@ impl Mixer {
 
         let root = Scalar::from_bytes_mod_order(root.0);
         let leaf = Scalar::from_bytes_mod_order(leaf.0);
-        let recipient = Scalar::default();
-        let relayer = Scalar::default();
+        let recipient = Scalar::from_bytes_mod_order(recipient.0);
+        let relayer = Scalar::from_bytes_mod_order(relayer.0);
         let (
             proof_bytes,
             (comms, nullifier_hash, leaf_index_commitments, proof_commitments),
@@ -261,6 +390,7 @@ impl Mixer {
         let relayer = ScalarData(relayer.to_bytes());
 
         ZkProof {
+            root: ScalarData(self.tree.tree.root.to_bytes()),
             comms,
             nullifier_hash,
             proof_bytes,
@@ -272,10 +402,111 @@ impl Mixer {
     }
 }
 
+/// Generates the withdraw zkProof of the `note` against the mixer tree
+/// built from all of its `leaves`.
+///
+/// The `recipient` and `relayer` are the raw bytes of their account ids,
+/// and the proof is generated against the tree root (see [ZkProof::root]),
+/// which must be one of the roots cached on chain.
+pub fn generate_withdraw_proof(
+    note: Note,
+    leaves: Vec<ScalarData>,
+    recipient: ScalarData,
+    relayer: ScalarData,
+) -> Result<ZkProof, Error> {
+    let mut mixer = Mixer::new(note.mixer_id);
+    let leaf = mixer.save_note(note);
+    if !leaves.contains(&leaf) {
+        return Err(Error::NoteNotInTree);
+    }
+    mixer.add_leaves(leaves);
+    let root = mixer.root();
+    Ok(mixer.generate_proof(root, leaf, recipient, relayer))
+}
+
+/// What a withdraw of a note would be proven against, see
+/// [preview_withdraw].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawPreview {
+    /// The root of the mixer tree, it must be one of the roots cached on
+    /// chain.
+    pub root: ScalarData,
+    /// The note nullifier hash, it must not be used on chain yet.
+    pub nullifier_hash: ScalarData,
+}
+
+/// Checks the `note` is in the mixer tree built from all of its `leaves`,
+/// without generating the (costly) withdraw zkProof.
+pub fn preview_withdraw(
+    note: Note,
+    leaves: Vec<ScalarData>,
+) -> Result<WithdrawPreview, Error> {
+    let mut mixer = Mixer::new(note.mixer_id);
+    let (.., nullifier_hash, leaf) =
+        mixer.tree.leaf_data_from_bytes(note.r.0, note.nullifier.0);
+    if !leaves.contains(&ScalarData(leaf.to_bytes())) {
+        return Err(Error::NoteNotInTree);
+    }
+    mixer.add_leaves(leaves);
+    Ok(WithdrawPreview {
+        root: mixer.root(),
+        nullifier_hash: ScalarData(nullifier_hash.to_bytes()),
+    })
+}
+
 #[cfg(test)]
 mod tests {
+    use bulletproofs::r1cs::{R1CSProof, Verifier};
+    use bulletproofs_gadgets::fixed_deposit_tree::mixer_verif_gadget;
+    use bulletproofs_gadgets::poseidon::allocate_statics_for_verifier;
+    use bulletproofs_gadgets::utils::AllocatedScalar;
+    use curve25519_dalek::ristretto::CompressedRistretto;
+    use rand_core::OsRng;
+
     use super::*;
 
+    fn verify(proof: &ZkProof, recipient: Scalar, relayer: Scalar) -> bool {
+        let pc_gens = PedersenGens::default();
+        let bp_gens = BulletproofGens::new(16400, 1);
+        let mut transcript = Transcript::new(b"zk_membership_proof");
+        let mut verifier = Verifier::new(&mut transcript);
+        let mut alloc = |c: &Commitment| AllocatedScalar {
+            variable: verifier.commit(CompressedRistretto(c.0)),
+            assignment: None,
+        };
+        let r = alloc(&proof.comms[0]);
+        let nullifier = alloc(&proof.comms[1]);
+        let leaf = alloc(&proof.comms[2]);
+        let leaf_index_bits = proof
+            .leaf_index_commitments
+            .iter()
+            .map(&mut alloc)
+            .collect();
+        let proof_nodes =
+            proof.proof_commitments.iter().map(&mut alloc).collect();
+        let statics = allocate_statics_for_verifier(&mut verifier, 4, &pc_gens);
+        let gadget = mixer_verif_gadget(
+            &mut verifier,
+            &recipient,
+            &relayer,
+            32,
+            &Scalar::from_bytes_mod_order(proof.root.0),
+            &Scalar::from_bytes_mod_order(proof.nullifier_hash.0),
+            r,
+            nullifier,
+            leaf,
+            leaf_index_bits,
+            proof_nodes,
+            statics,
+            &default_hasher(),
+        );
+        let r1cs_proof = R1CSProof::from_bytes(&proof.proof_bytes).unwrap();
+        gadget.is_ok()
+            && verifier
+                .verify_with_rng(&r1cs_proof, &pc_gens, &bp_gens, &mut OsRng)
+                .is_ok()
+    }
+
     #[test]
     fn generate_note() {
         let mut mixer = Mixer::new(0);
@@ -284,4 +515,239 @@ mod tests {
         assert_eq!(note.token_symbol, TokenSymbol::Edg);
         eprintln!("{:#?}", note);
     }
+
+    #[test]
+    fn should_generate_and_parse_note_correctly() {
+        let mut mixer = Mixer::new(3);
+        let note = mixer.generate_note(TokenSymbol::Edg);
+        let v1 = note.to_string();
+        assert_eq!(v1.parse::<Note>().unwrap(), note);
+        let with_block = Note {
+            block_number: Some(42),
+            ..note.clone()
+        };
+        assert_eq!(with_block.to_string().parse::<Note>().unwrap(), with_block);
+
+        let v2 = Note {
+            version: NoteVersion::V2,
+            index: Some(7),
+            amount: Some(String::from("1.000000000000")),
+            ..note
+        };
+        let encoded = v2.to_string();
+        assert!(encoded.starts_with("webb.mix-v2-EDG-3-7-1.000000000000-"));
+        assert_eq!(encoded.parse::<Note>().unwrap(), v2);
+        let v2_with_block = Note {
+            block_number: Some(42),
+            ..v2
+        };
+        assert_eq!(
+            v2_with_block.to_string().parse::<Note>().unwrap(),
+            v2_with_block
+        );
+
+        // a v2 note with the v1 fields is rejected.
+        let v1_fields = v1.replacen("-v1-", "-v2-", 1);
+        assert!(matches!(
+            v1_fields.parse::<Note>(),
+            Err(Error::InvalidNoteLength)
+        ));
+        let bad_amount = encoded.replacen("1.000000000000", "1.", 1);
+        assert!(matches!(
+            bad_amount.parse::<Note>(),
+            Err(Error::InvalidNoteAmount)
+        ));
+    }
+
+    #[test]
+    fn note_amounts_are_human_readable() {
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        assert_eq!(note.human_amount(), None);
+        let with_amount = |amount: &str| Note {
+            version: NoteVersion::V2,
+            index: Some(0),
+            amount: Some(amount.to_owned()),
+            ..note.clone()
+        };
+        let cases = [
+            ("1.000000000000", (1_000_000_000_000, 12), "1 EDG"),
+            ("1.500000000000", (1_500_000_000_000, 12), "1.5 EDG"),
+            ("0.000000000001", (1, 12), "0.000000000001 EDG"),
+            ("2.50", (250, 2), "2.5 EDG"),
+            ("100.010", (100_010, 3), "100.01 EDG"),
+            ("12", (12, 0), "12 EDG"),
+            ("007.0", (70, 1), "7 EDG"),
+        ];
+        for (amount, base, human) in cases.iter() {
+            let note = with_amount(amount);
+            assert_eq!(note.base_amount(), Some(*base), "{}", amount);
+            assert_eq!(note.human_amount().unwrap(), *human, "{}", amount);
+        }
+        assert_eq!(with_amount(&"9".repeat(40)).base_amount(), None);
+    }
+
+    #[test]
+    fn note_enums_round_trip() {
+        // a new variant fails these matches, as a reminder to list it here.
+        let versions = [NoteVersion::V1, NoteVersion::V2];
+        match versions[0] {
+            NoteVersion::V1 | NoteVersion::V2 => {},
+        }
+        let symbols = [TokenSymbol::Edg];
+        match symbols[0] {
+            TokenSymbol::Edg => {},
+        }
+        for v in versions.iter() {
+            assert_eq!(v.to_string().parse::<NoteVersion>().unwrap(), *v);
+        }
+        for v in symbols.iter() {
+            assert_eq!(v.to_string().parse::<TokenSymbol>().unwrap(), *v);
+        }
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        assert!(note.to_string().starts_with(&format!("{}-", NOTE_PREFIX)));
+        assert_eq!(
+            note.to_string().parse::<Note>().unwrap().prefix,
+            NOTE_PREFIX
+        );
+    }
+
+    #[test]
+    fn malformed_notes_are_errors() {
+        let footer = "1".repeat(128);
+        let malformed = [
+            format!("webb.mix-v1-EDG-x-{}", footer),
+            format!("webb.mix-v1-EDG-0-x-{}", footer),
+            format!("webb.mix-v2-EDG-0-x-10-{}", footer),
+            format!("webb.mix-v2-EDG-0-1-1e3-{}", footer),
+            format!("webb.mix-v1-EDG-0-{}", "1".repeat(127)),
+            format!("webb.mix-v1-EDG-0-{0}é{0}", "1".repeat(63)),
+            format!("webb.mix-v1-EDG-0-{}", "x".repeat(128)),
+            String::from("webb.mix-v1-EDG"),
+            String::from("webb.mix"),
+        ];
+        for note in &malformed {
+            assert!(note.parse::<Note>().is_err(), "{}", note);
+        }
+    }
+
+    #[test]
+    fn inconsistent_notes_are_invalid() {
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        assert!(note.validate().is_ok());
+        let v2 = Note {
+            version: NoteVersion::V2,
+            index: Some(7),
+            amount: Some(String::from("1.5")),
+            ..note.clone()
+        };
+        assert!(v2.validate().is_ok());
+
+        let bridge = Note {
+            prefix: String::from("webb.bridge"),
+            ..note.clone()
+        };
+        assert!(matches!(bridge.validate(), Err(Error::InvalidNotePrefix)));
+        let v1_with_amount = Note {
+            amount: Some(String::from("1")),
+            ..note
+        };
+        assert!(matches!(
+            v1_with_amount.validate(),
+            Err(Error::InvalidNoteFields(_))
+        ));
+        let v2_without_index = Note {
+            index: None,
+            ..v2.clone()
+        };
+        assert!(matches!(
+            v2_without_index.validate(),
+            Err(Error::InvalidNoteLeafIndex)
+        ));
+        let v2_bad_amount = Note {
+            amount: Some(String::from("1e3")),
+            ..v2
+        };
+        assert!(matches!(
+            v2_bad_amount.validate(),
+            Err(Error::InvalidNoteAmount)
+        ));
+        // parses, but the secrets are not canonical scalars.
+        let non_canonical: Note =
+            format!("webb.mix-v1-EDG-0-{}", "f".repeat(128))
+                .parse()
+                .unwrap();
+        assert!(matches!(
+            non_canonical.validate(),
+            Err(Error::InvalidNoteSecret)
+        ));
+    }
+
+    #[test]
+    fn only_mixer_notes_are_accepted() {
+        let note = Mixer::new(0).generate_note(TokenSymbol::Edg);
+        assert!(ensure_mixer_note(&note).is_ok());
+        let bridge = Note {
+            prefix: String::from("webb.bridge"),
+            ..note
+        };
+        assert!(matches!(
+            ensure_mixer_note(&bridge),
+            Err(Error::InvalidNotePrefix)
+        ));
+    }
+
+    #[test]
+    fn withdraw_proof_round_trip() {
+        let mut mixer = Mixer::new(0);
+        let note = mixer.generate_note(TokenSymbol::Edg);
+        let other = mixer.generate_note(TokenSymbol::Edg);
+        let leaves = vec![
+            Mixer::new(0).save_note(other),
+            Mixer::new(0).save_note(note.clone()),
+        ];
+        let recipient = ScalarData([1; 32]);
+        let relayer = ScalarData([2; 32]);
+        let proof =
+            generate_withdraw_proof(note, leaves, recipient, relayer).unwrap();
+        let recipient = Scalar::from_bytes_mod_order(recipient.0);
+        let relayer = Scalar::from_bytes_mod_order(relayer.0);
+        assert!(verify(&proof, recipient, relayer));
+        assert!(!verify(&proof, relayer, recipient));
+    }
+
+    #[test]
+    fn withdraw_proof_of_non_deposited_note() {
+        let mut mixer = Mixer::new(0);
+        let note = mixer.generate_note(TokenSymbol::Edg);
+        let other = mixer.generate_note(TokenSymbol::Edg);
+        let leaves = vec![Mixer::new(0).save_note(other)];
+        let result = generate_withdraw_proof(
+            note,
+            leaves,
+            ScalarData::default(),
+            ScalarData::default(),
+        );
+        assert!(matches!(result, Err(Error::NoteNotInTree)));
+    }
+
+    #[test]
+    fn preview_withdraw_without_a_proof() {
+        let mut mixer = Mixer::new(0);
+        let note = mixer.generate_note(TokenSymbol::Edg);
+        let other = mixer.generate_note(TokenSymbol::Edg);
+        let leaves = vec![Mixer::new(0).save_note(note.clone())];
+        let mut tree = Mixer::new(0);
+        tree.add_leaves(leaves.clone());
+
+        let preview = preview_withdraw(note.clone(), leaves).unwrap();
+        assert_eq!(preview.root, tree.root());
+        let other_preview = preview_withdraw(
+            other.clone(),
+            vec![Mixer::new(0).save_note(other)],
+        )
+        .unwrap();
+        assert_ne!(preview.nullifier_hash, other_preview.nullifier_hash);
+        let result = preview_withdraw(note, vec![]);
+        assert!(matches!(result, Err(Error::NoteNotInTree)));
+    }
 }
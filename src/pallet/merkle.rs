@@ -20,8 +20,30 @@ pub trait Merkle: System + Balances {
         + Sync;
 }
 
+// return types ..
+
+/// The head of a pallet Merkle tree, the fields after it (the root, the
+/// edge nodes, ..) are not decoded.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, Debug)]
+pub struct MerkleTreeInfo {
+    /// The number of leaves, one per deposit to the tree.
+    pub leaf_count: u32,
+    pub max_leaves: u32,
+    pub depth: u8,
+}
+
 // Storage ..
 
+#[derive(Clone, Debug, Eq, Encode, PartialEq, subxt::Store)]
+pub struct TreesStore<T: Merkle> {
+    #[store(returns = MerkleTreeInfo)]
+    tree_id: T::TreeId,
+}
+
+impl<T: Merkle> TreesStore<T> {
+    pub fn new(tree_id: T::TreeId) -> Self { Self { tree_id } }
+}
+
 #[derive(Clone, Debug, Eq, Encode, PartialEq, subxt::Store)]
 pub struct CachedRootsStore<T: Merkle> {
     #[store(returns = Vec<ScalarData>)]
@@ -37,3 +59,17 @@ impl<T: Merkle> CachedRootsStore<T> {
         }
     }
 }
+
+#[derive(Clone, Debug, Eq, Encode, PartialEq, subxt::Store)]
+pub struct UsedNullifiersStore<T: Merkle> {
+    #[store(returns = bool)]
+    key: (T::TreeId, ScalarData),
+}
+
+impl<T: Merkle> UsedNullifiersStore<T> {
+    pub fn new(tree_id: T::TreeId, nullifier_hash: ScalarData) -> Self {
+        Self {
+            key: (tree_id, nullifier_hash),
+        }
+    }
+}
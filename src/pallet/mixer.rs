@@ -136,7 +136,22 @@ pub struct DepositCall<T: Mixer> {
     data_points: Vec<ScalarData>,
 }
 
+impl<T: Mixer> DepositCall<T> {
+    pub fn new(group_id: T::TreeId, data_points: Vec<ScalarData>) -> Self {
+        Self {
+            group_id,
+            data_points,
+        }
+    }
+}
+
 #[derive(Clone, Encode, PartialEq, subxt::Call)]
 pub struct WithdrawCall<T: Mixer> {
     withdraw_proof: WithdrawProof<T>,
 }
+
+impl<T: Mixer> WithdrawCall<T> {
+    pub fn new(withdraw_proof: WithdrawProof<T>) -> Self {
+        Self { withdraw_proof }
+    }
+}
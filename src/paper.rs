@@ -0,0 +1,120 @@
+//! Printable "paper wallet" backups of the notes.
+
+use qrcode::render::{svg, unicode};
+use qrcode::QrCode;
+
+use crate::raw::NoteRaw;
+
+/// Encodes the `secret_note` string as a QR code.
+pub fn note_qr_code(secret_note: &str) -> anyhow::Result<QrCode> {
+    Ok(QrCode::new(secret_note.as_bytes())?)
+}
+
+/// Renders the `code` for the terminal, using unicode half blocks.
+pub fn render_terminal(code: &QrCode) -> String {
+    code.render::<unicode::Dense1x2>()
+        .dark_color(unicode::Dense1x2::Light)
+        .light_color(unicode::Dense1x2::Dark)
+        .build()
+}
+
+/// Renders a self-contained printable HTML page for the `note`, with its
+/// QR code, the `secret_note` string, its parameters and the recovery
+/// instructions.
+///
+/// The page doesn't load any external resources, so it works offline.
+pub fn render_html(
+    note: &NoteRaw,
+    secret_note: &str,
+) -> anyhow::Result<String> {
+    let qr = note_qr_code(secret_note)?
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .build();
+    let amount = note
+        .human_amount()
+        .unwrap_or_else(|| format!("unknown amount of {}", note.token_symbol));
+    let chain = if note.chain.is_empty() {
+        "unknown"
+    } else {
+        &note.chain
+    };
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Webb Note: {alias}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 40em; margin: 2em auto; }}
+.note {{ font-family: monospace; word-break: break-all; border: 1px solid; padding: 1em; }}
+.warning {{ font-weight: bold; }}
+</style>
+</head>
+<body>
+<h1>Webb Mixer Note</h1>
+<p class="warning">This Note is the only key to the deposited assets, anyone
+who has it can withdraw them. Keep it somewhere safe, and never share it.</p>
+<div>{qr}</div>
+<p class="note">{note}</p>
+<table>
+<tr><th>Alias</th><td>{alias}</td></tr>
+<tr><th>Amount</th><td>{amount}</td></tr>
+<tr><th>Token</th><td>{token}</td></tr>
+<tr><th>Mixer Group</th><td>#{mixer_id}</td></tr>
+<tr><th>Chain</th><td>{chain}</td></tr>
+</table>
+<h2>Recovery</h2>
+<ol>
+<li>Scan the QR code, or type the Note above exactly as printed.</li>
+<li>Import it: <code>webb mixer import-note -a &lt;ALIAS&gt; &lt;NOTE&gt;</code>,
+or paste it in the Webb UI.</li>
+<li>Withdraw: <code>webb mixer withdraw -a &lt;ALIAS&gt;</code>.</li>
+</ol>
+</body>
+</html>
+"#,
+        alias = escape(&note.alias),
+        qr = qr,
+        note = escape(secret_note),
+        amount = escape(&amount),
+        token = escape(&note.token_symbol),
+        mixer_id = note.mixer_id,
+        chain = escape(chain),
+    ))
+}
+
+/// Escapes the HTML special characters in `s`.
+fn escape(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_owned(),
+            '<' => "&lt;".to_owned(),
+            '>' => "&gt;".to_owned(),
+            '"' => "&quot;".to_owned(),
+            '\'' => "&#39;".to_owned(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paper_is_self_contained() {
+        let note = NoteRaw {
+            alias: String::from("<b>mine</b>"),
+            token_symbol: String::from("EDG"),
+            mixer_id: 2,
+            ..Default::default()
+        };
+        let html = render_html(&note, "webb.mix-v1-EDG-2-abcd").unwrap();
+        assert!(html.contains("webb.mix-v1-EDG-2-abcd"));
+        assert!(html.contains("&lt;b&gt;mine&lt;/b&gt;"));
+        assert!(html.contains("<svg"));
+        assert!(!html.contains("src="));
+        assert!(!html.contains("href="));
+    }
+}
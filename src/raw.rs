@@ -2,6 +2,10 @@ use std::fmt;
 
 use console::Emoji;
 use prost::Message;
+use subxt::sp_core::crypto::{AccountId32, Ss58Codec};
+use webb_cli::keystore::KeyScheme;
+
+use crate::utils;
 
 #[derive(Clone, PartialEq, Message)]
 pub struct AccountRaw {
@@ -13,6 +17,33 @@ pub struct AccountRaw {
     pub address: String,
     #[prost(bool, tag = "4")]
     pub is_default: bool,
+    /// The `//hard/soft` path the account was derived from its seed with,
+    /// empty if it was not derived.
+    #[prost(string, tag = "5")]
+    pub derivation_path: String,
+    /// The signature scheme of the account, sr25519 for the accounts saved
+    /// by older versions.
+    #[prost(enumeration = "SeedScheme", tag = "6")]
+    pub scheme: i32,
+}
+
+impl AccountRaw {
+    /// Whether this is the account with the `alias_or_address`, where the
+    /// address could be in any ss58 format.
+    pub fn matches(&self, alias_or_address: &str) -> bool {
+        if self.alias == alias_or_address || self.address == alias_or_address {
+            return true;
+        }
+        let account_id = |address: &str| {
+            AccountId32::from_ss58check_with_version(address)
+                .map(|(id, _)| id)
+                .ok()
+        };
+        match (account_id(alias_or_address), account_id(&self.address)) {
+            (Some(given), Some(ours)) => given == ours,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for AccountRaw {
@@ -27,10 +58,106 @@ impl fmt::Display for AccountRaw {
             }
         )?;
         write!(f, "{}: {}", self.alias, self.address)?;
+        if !self.derivation_path.is_empty() {
+            write!(f, " ({})", self.derivation_path)?;
+        }
+        if self.scheme != SeedScheme::Sr25519 as i32 {
+            write!(
+                f,
+                " [{}]",
+                SeedScheme::from_i32(self.scheme).unwrap_or_default()
+            )?;
+        }
         Ok(())
     }
 }
 
+/// The length of the raw sr25519 seeds stored by older versions.
+const LEGACY_SEED_LEN: usize = 32;
+
+/// The signature scheme of a stored account seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, prost::Enumeration)]
+#[repr(i32)]
+pub enum SeedScheme {
+    Sr25519 = 0,
+    Ed25519 = 1,
+    Ecdsa = 2,
+}
+
+/// An account seed, as stored (encrypted) under the `<uuid>_seed` key.
+///
+/// Older versions stored the raw 32 bytes sr25519 seed instead, see
+/// [StoredSeed::decode_or_legacy].
+#[derive(Clone, PartialEq, Message)]
+pub struct StoredSeed {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    #[prost(enumeration = "SeedScheme", tag = "2")]
+    pub scheme: i32,
+    #[prost(bytes, tag = "3")]
+    pub bytes: Vec<u8>,
+}
+
+impl StoredSeed {
+    pub const VERSION: u32 = 1;
+
+    pub fn sr25519(bytes: Vec<u8>) -> Self {
+        Self::new(SeedScheme::Sr25519, bytes)
+    }
+
+    pub fn new(scheme: SeedScheme, bytes: Vec<u8>) -> Self {
+        Self {
+            version: Self::VERSION,
+            scheme: scheme as i32,
+            bytes,
+        }
+    }
+
+    /// Decodes a stored seed, the returned flag is `true` if it was a legacy
+    /// raw sr25519 seed that should be migrated.
+    ///
+    /// An encoded `StoredSeed` is never exactly 32 bytes, since it always
+    /// carries the version and the seed length prefix.
+    pub fn decode_or_legacy(
+        buf: &[u8],
+    ) -> Result<(Self, bool), prost::DecodeError> {
+        if buf.len() == LEGACY_SEED_LEN {
+            return Ok((Self::sr25519(buf.to_vec()), true));
+        }
+        Self::decode(buf).map(|seed| (seed, false))
+    }
+}
+
+impl fmt::Display for SeedScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SeedScheme::Sr25519 => write!(f, "sr25519"),
+            SeedScheme::Ed25519 => write!(f, "ed25519"),
+            SeedScheme::Ecdsa => write!(f, "ecdsa"),
+        }
+    }
+}
+
+impl From<KeyScheme> for SeedScheme {
+    fn from(scheme: KeyScheme) -> Self {
+        match scheme {
+            KeyScheme::Sr25519 => SeedScheme::Sr25519,
+            KeyScheme::Ed25519 => SeedScheme::Ed25519,
+        }
+    }
+}
+
+impl SeedScheme {
+    /// The [KeyScheme] we could sign with, if it is supported.
+    pub fn key_scheme(self) -> Option<KeyScheme> {
+        match self {
+            SeedScheme::Sr25519 => Some(KeyScheme::Sr25519),
+            SeedScheme::Ed25519 => Some(KeyScheme::Ed25519),
+            SeedScheme::Ecdsa => None,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Message)]
 pub struct AccountsIds {
     #[prost(repeated, string, tag = "1")]
@@ -49,6 +176,179 @@ pub struct NoteRaw {
     pub mixer_id: u32,
     #[prost(bool, tag = "6")]
     pub used: bool,
+    /// The deposit amount of this note in units of `decimals`, as a
+    /// decimal string. the generated notes use the chain decimals (i.e it is
+    /// the mixer `fixed_deposit_size`), while the imported v2 notes keep
+    /// their own precision (i.e `1.5` is 15 with 1 decimal), see
+    /// [NoteRaw::amount_with_decimals].
+    ///
+    /// Empty for notes saved before the amount was tracked or imported
+    /// without knowing their mixer size.
+    #[prost(string, tag = "7")]
+    pub amount: String,
+    /// The decimals of `amount`.
+    #[prost(uint32, tag = "8")]
+    pub decimals: u32,
+    /// The mixer currency id, resolved when the note got generated.
+    #[prost(uint64, optional, tag = "9")]
+    pub currency_id: Option<u64>,
+    /// The name of the chain the note was generated for.
+    ///
+    /// Empty if unknown (i.e imported notes).
+    #[prost(string, tag = "10")]
+    pub chain: String,
+}
+
+impl NoteRaw {
+    /// The deposit amount in units of the note `decimals`, if known.
+    pub fn amount(&self) -> Option<u128> { self.amount.parse().ok() }
+
+    /// The deposit amount scaled to a token with `decimals`, i.e the chain
+    /// base units, if known.
+    ///
+    /// `None` if it overflows, or it has more significant decimals.
+    pub fn amount_with_decimals(&self, decimals: u32) -> Option<u128> {
+        let amount = self.amount()?;
+        if decimals >= self.decimals {
+            amount.checked_mul(10u128.checked_pow(decimals - self.decimals)?)
+        } else {
+            let base = 10u128.checked_pow(self.decimals - decimals)?;
+            Some(amount / base).filter(|_| amount % base == 0)
+        }
+    }
+
+    /// The deposit amount formatted as a human readable value, if known.
+    pub fn human_amount(&self) -> Option<String> {
+        let decimals = self.decimals as u8;
+        self.amount()
+            .map(|v| utils::format_balance(v, decimals, &self.token_symbol))
+    }
+}
+
+/// Which notes to keep, by whether they got used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsedFilter {
+    All,
+    Used,
+    Unused,
+}
+
+impl Default for UsedFilter {
+    fn default() -> Self { UsedFilter::All }
+}
+
+impl std::str::FromStr for UsedFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(UsedFilter::All),
+            "used" => Ok(UsedFilter::Used),
+            "unused" => Ok(UsedFilter::Unused),
+            _ => Err(format!("expected all, used or unused, got {}", s)),
+        }
+    }
+}
+
+/// Filters the saved notes, i.e for `mixer list-notes`.
+#[derive(Debug, Clone, Default)]
+pub struct NoteFilter {
+    pub used: UsedFilter,
+    /// The token symbol, ignoring the case.
+    pub token: Option<String>,
+    /// The deposit size in base units, notes with an unknown amount never
+    /// match it.
+    ///
+    /// the base units of a token are its notes highest decimals, so the
+    /// imported notes are compared at the same precision as the generated
+    /// ones.
+    pub size: Option<u128>,
+}
+
+impl NoteFilter {
+    /// Whether every note is kept.
+    pub fn is_empty(&self) -> bool {
+        self.used == UsedFilter::All
+            && self.token.is_none()
+            && self.size.is_none()
+    }
+
+    /// Whether the `note` is kept, its amount compared to the `size` in
+    /// units of `decimals`.
+    pub fn matches(&self, note: &NoteRaw, decimals: u32) -> bool {
+        let used = match self.used {
+            UsedFilter::All => true,
+            UsedFilter::Used => note.used,
+            UsedFilter::Unused => !note.used,
+        };
+        let token = self
+            .token
+            .as_ref()
+            .map_or(true, |t| t.eq_ignore_ascii_case(&note.token_symbol));
+        let size = self
+            .size
+            .map_or(true, |s| note.amount_with_decimals(decimals) == Some(s));
+        used && token && size
+    }
+
+    pub fn apply(&self, notes: Vec<NoteRaw>) -> Vec<NoteRaw> {
+        let decimals: Vec<_> = token_decimals(&notes)
+            .into_iter()
+            .map(|(token, decimals)| (token.to_owned(), decimals))
+            .collect();
+        let decimals_of = |note: &NoteRaw| {
+            decimals
+                .iter()
+                .find(|(t, _)| *t == note.token_symbol)
+                .map_or(note.decimals, |(_, d)| *d)
+        };
+        notes
+            .into_iter()
+            .filter(|n| self.matches(n, decimals_of(n)))
+            .collect()
+    }
+}
+
+/// The highest decimals of the `notes` with a known amount by token symbol,
+/// the precision their amounts are compared and summed at.
+///
+/// the generated notes use the chain decimals, which are never less than
+/// the ones of the imported notes of the same token.
+fn token_decimals<'a>(
+    notes: impl IntoIterator<Item = &'a NoteRaw>,
+) -> Vec<(&'a str, u32)> {
+    let mut decimals: Vec<(&str, u32)> = Vec::new();
+    for note in notes.into_iter().filter(|n| n.amount().is_some()) {
+        match decimals.iter_mut().find(|(t, _)| *t == note.token_symbol) {
+            Some((_, d)) => *d = note.decimals.max(*d),
+            None => decimals.push((&note.token_symbol, note.decimals)),
+        }
+    }
+    decimals
+}
+
+/// The total amount of the unused `notes` by token symbol, in base units,
+/// along with the token decimals and how many of them have an unknown amount.
+pub fn unused_totals(notes: &[NoteRaw]) -> (Vec<(String, u32, u128)>, usize) {
+    let unused: Vec<_> = notes.iter().filter(|n| !n.used).collect();
+    let mut totals: Vec<_> = token_decimals(unused.iter().copied())
+        .into_iter()
+        .map(|(token, decimals)| (token.to_owned(), decimals, 0u128))
+        .collect();
+    let mut unknown = 0;
+    for note in unused {
+        let total = totals.iter_mut().find(|(t, ..)| *t == note.token_symbol);
+        match total {
+            Some((_, decimals, total)) => {
+                match note.amount_with_decimals(*decimals) {
+                    Some(amount) => *total = total.saturating_add(amount),
+                    None => unknown += 1,
+                }
+            },
+            None => unknown += 1,
+        }
+    }
+    (totals, unknown)
 }
 
 impl fmt::Display for NoteRaw {
@@ -62,11 +362,18 @@ impl fmt::Display for NoteRaw {
                 Emoji("✔️ ", "-")
             }
         )?;
-        write!(
-            f,
-            "{} with {} Token at #{} Mixer Group",
-            self.alias, self.token_symbol, self.mixer_id
-        )?;
+        match self.human_amount() {
+            Some(amount) => write!(
+                f,
+                "{} with {} at #{} Mixer Group",
+                self.alias, amount, self.mixer_id
+            )?,
+            None => write!(
+                f,
+                "{} with {} Token at #{} Mixer Group",
+                self.alias, self.token_symbol, self.mixer_id
+            )?,
+        };
         Ok(())
     }
 }
@@ -76,3 +383,196 @@ pub struct NotesIds {
     #[prost(repeated, string, tag = "1")]
     pub ids: Vec<String>,
 }
+
+/// A portable backup of the datastore, written by `webb db export`.
+///
+/// the seeds, phrases and notes secrets stay encrypted, so importing them
+/// needs the `kdf` of the exporting store and the same password.
+#[derive(Clone, PartialEq, Message)]
+pub struct Backup {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    /// The encoded kdf of the exporting store.
+    #[prost(bytes, tag = "2")]
+    pub kdf: Vec<u8>,
+    #[prost(message, repeated, tag = "3")]
+    pub accounts: Vec<BackupAccount>,
+    #[prost(message, repeated, tag = "4")]
+    pub notes: Vec<BackupNote>,
+}
+
+impl Backup {
+    pub const VERSION: u32 = 1;
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BackupAccount {
+    #[prost(message, optional, tag = "1")]
+    pub account: Option<AccountRaw>,
+    /// The encrypted [StoredSeed].
+    #[prost(bytes, tag = "2")]
+    pub seed: Vec<u8>,
+    /// The encrypted mnemonic phrase, empty if it was not stored.
+    #[prost(bytes, tag = "3")]
+    pub phrase: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct BackupNote {
+    #[prost(message, optional, tag = "1")]
+    pub note: Option<NoteRaw>,
+    /// The encrypted note secret.
+    #[prost(bytes, tag = "2")]
+    pub secret: Vec<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(alias: &str, token: &str, amount: &str, used: bool) -> NoteRaw {
+        NoteRaw {
+            alias: alias.to_owned(),
+            token_symbol: token.to_owned(),
+            amount: amount.to_owned(),
+            used,
+            ..Default::default()
+        }
+    }
+
+    fn aliases(notes: Vec<NoteRaw>) -> Vec<String> {
+        notes.into_iter().map(|n| n.alias).collect()
+    }
+
+    #[test]
+    fn filters_notes() {
+        let notes = vec![
+            note("a", "EDG", "1000", false),
+            note("b", "EDG", "1000", true),
+            note("c", "tEDG", "1000", false),
+            note("d", "EDG", "", false),
+        ];
+        let all = NoteFilter::default();
+        assert!(all.is_empty());
+        assert_eq!(all.apply(notes.clone()).len(), 4);
+
+        let unused = NoteFilter {
+            used: "unused".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(aliases(unused.apply(notes.clone())), ["a", "c", "d"]);
+        let used = NoteFilter {
+            used: UsedFilter::Used,
+            ..Default::default()
+        };
+        assert_eq!(aliases(used.apply(notes.clone())), ["b"]);
+
+        let tedg = NoteFilter {
+            token: Some(String::from("tedg")),
+            ..Default::default()
+        };
+        assert_eq!(aliases(tedg.apply(notes.clone())), ["c"]);
+        // a note with an unknown amount never matches a size.
+        let edg_1000 = NoteFilter {
+            used: UsedFilter::Unused,
+            token: Some(String::from("EDG")),
+            size: Some(1000),
+        };
+        assert!(!edg_1000.is_empty());
+        assert_eq!(aliases(edg_1000.apply(notes)), ["a"]);
+
+        // an imported 1.5 EDG note has the size of a generated one.
+        let notes = vec![
+            NoteRaw {
+                decimals: 12,
+                ..note("a", "EDG", "1500000000000", false)
+            },
+            NoteRaw {
+                decimals: 1,
+                ..note("b", "EDG", "15", false)
+            },
+        ];
+        let size = NoteFilter {
+            size: Some(1_500_000_000_000),
+            ..Default::default()
+        };
+        assert_eq!(aliases(size.apply(notes)), ["a", "b"]);
+        assert!("spent".parse::<UsedFilter>().is_err());
+    }
+
+    #[test]
+    fn sums_the_unused_notes_by_token() {
+        let notes = vec![
+            note("a", "EDG", "1000", false),
+            note("b", "EDG", "5000", true),
+            note("c", "tEDG", "250", false),
+            note("d", "EDG", "2000", false),
+            note("e", "EDG", "", false),
+        ];
+        let (totals, unknown) = unused_totals(&notes);
+        assert_eq!(
+            totals,
+            [
+                (String::from("EDG"), 0, 3000),
+                (String::from("tEDG"), 0, 250)
+            ]
+        );
+        assert_eq!(unknown, 1);
+
+        // a generated 1 EDG note, and an imported 1.5 EDG one.
+        let generated = NoteRaw {
+            decimals: 12,
+            ..note("a", "EDG", "1000000000000", false)
+        };
+        let imported = NoteRaw {
+            decimals: 1,
+            ..note("b", "EDG", "15", false)
+        };
+        let (totals, unknown) = unused_totals(&[imported, generated]);
+        assert_eq!(totals, [(String::from("EDG"), 12, 2_500_000_000_000)]);
+        assert_eq!(unknown, 0);
+    }
+
+    #[test]
+    fn matches_the_address_in_any_format() {
+        let account = AccountRaw {
+            alias: String::from("alice"),
+            // alice, in the generic substrate format.
+            address: String::from(
+                "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+            ),
+            ..Default::default()
+        };
+        assert!(account.matches("alice"));
+        assert!(account.matches(&account.address));
+        // the same account, in the polkadot format.
+        assert!(
+            account.matches("15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5")
+        );
+        assert!(!account.matches("bob"));
+        assert!(!account
+            .matches("5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"));
+    }
+
+    #[test]
+    fn scales_the_amount_to_other_decimals() {
+        let note = |amount: &str, decimals| NoteRaw {
+            amount: amount.to_owned(),
+            decimals,
+            ..Default::default()
+        };
+        // an imported `1.5` note, and the same generated one.
+        assert_eq!(
+            note("15", 1).amount_with_decimals(12),
+            Some(1_500_000_000_000)
+        );
+        assert_eq!(
+            note("1500000000000", 12).amount_with_decimals(12),
+            Some(1_500_000_000_000)
+        );
+        assert_eq!(note("1500000000000", 12).amount_with_decimals(1), Some(15));
+        assert_eq!(note("1500000000001", 12).amount_with_decimals(1), None);
+        assert_eq!(note("1", 0).amount_with_decimals(40), None);
+        assert_eq!(note("", 12).amount_with_decimals(12), None);
+    }
+}
@@ -0,0 +1,405 @@
+//! A small client for the withdraw API of the Webb relayers.
+//!
+//! A relayer submits the withdraw transaction for us, in exchange of a
+//! fee, so the recipient account doesn't need any balance to pay for it.
+//! The withdraw is submitted with `POST /api/v1/withdraw`, then its status
+//! is polled with `GET /api/v1/withdraw/<id>` until it is finalized.
+use std::time::Duration;
+
+use anyhow::Context;
+use futures::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use serde::{Deserialize, Serialize};
+use subxt::sp_core::crypto::{AccountId32, Ss58Codec};
+use url::Url;
+use webb_cli::pallet::mixer::WithdrawProof;
+use webb_cli::pallet::{Commitment, ScalarData};
+use webb_cli::runtime::WebbRuntime;
+
+/// How often we poll the relayer for the withdraw status.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// How many times we poll the relayer before giving up, about 10 minutes.
+const MAX_POLLS: usize = 200;
+/// How long we wait for the relayer to answer a single request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the relayer tells about itself, see `GET /api/v1/info`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RelayerInfo {
+    /// The account the relayer submits the withdraws with, it must be the
+    /// relayer of the withdraw proof.
+    pub account: String,
+    /// The minimum fee the relayer accepts, in base units.
+    pub fee: String,
+    /// The Mixer Groups the relayer withdraws from, if it tells.
+    #[serde(default)]
+    pub mixers: Option<Vec<u32>>,
+}
+
+/// A withdraw for the relayer to submit, see `POST /api/v1/withdraw`.
+///
+/// the 32 bytes values are `0x` prefixed hex strings, and the accounts are
+/// ss58 addresses.
+#[derive(Debug, Clone, Serialize)]
+pub struct WithdrawRequest {
+    pub mixer_id: u32,
+    pub cached_block: u32,
+    pub cached_root: String,
+    pub comms: Vec<String>,
+    pub nullifier_hash: String,
+    pub proof_bytes: String,
+    pub leaf_index_commitments: Vec<String>,
+    pub proof_commitments: Vec<String>,
+    pub recipient: String,
+    pub relayer: String,
+    /// The fee we pay to the relayer, in base units.
+    pub fee: String,
+}
+
+impl WithdrawRequest {
+    pub fn new(proof: &WithdrawProof<WebbRuntime>, fee: u128) -> Self {
+        let scalar = |v: &ScalarData| format!("0x{}", hex::encode(v.0));
+        let commitments = |v: &[Commitment]| {
+            v.iter()
+                .map(|c| format!("0x{}", hex::encode(c.0)))
+                .collect()
+        };
+        let address = |v: &Option<AccountId32>| {
+            v.as_ref().map(Ss58Codec::to_ss58check).unwrap_or_default()
+        };
+        Self {
+            mixer_id: proof.mixer_id,
+            cached_block: proof.cached_block,
+            cached_root: scalar(&proof.cached_root),
+            comms: commitments(&proof.comms),
+            nullifier_hash: scalar(&proof.nullifier_hash),
+            proof_bytes: format!("0x{}", hex::encode(&proof.proof_bytes)),
+            leaf_index_commitments: commitments(&proof.leaf_index_commitments),
+            proof_commitments: commitments(&proof.proof_commitments),
+            recipient: address(&proof.recipient),
+            relayer: address(&proof.relayer),
+            fee: fee.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct WithdrawAccepted {
+    id: String,
+}
+
+/// The status of a withdraw submitted to the relayer.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum WithdrawStatus {
+    /// Waiting to be submitted by the relayer.
+    Pending,
+    /// Submitted, but not finalized yet.
+    Submitted { tx_hash: String },
+    /// Finalized in the block `block_hash`.
+    Finalized {
+        tx_hash: String,
+        block_hash: String,
+        block_number: u32,
+    },
+    /// The relayer gave up, i.e the transaction failed.
+    Failed { reason: String },
+}
+
+/// An error answered by the relayer.
+#[derive(Debug, Clone, Deserialize)]
+struct RelayerError {
+    code: String,
+    #[serde(default)]
+    message: String,
+}
+
+impl RelayerError {
+    /// A message that tells what to do about the error.
+    fn explain(&self) -> String {
+        match self.code.as_str() {
+            "insufficient_fee" => format!(
+                "the relayer refused the fee: {}, try again with a higher \
+                 `--fee`",
+                self.message
+            ),
+            "invalid_proof" => format!(
+                "the relayer rejected the zkProof as invalid: {}, the Mixer \
+                 Group root could have changed, try again",
+                self.message
+            ),
+            _ => format!(
+                "the relayer failed with {}: {}",
+                self.code, self.message
+            ),
+        }
+    }
+}
+
+/// A client for the withdraw API of the relayer at `url`.
+pub struct RelayerClient {
+    url: Url,
+}
+
+impl RelayerClient {
+    pub fn new(url: &str) -> anyhow::Result<Self> {
+        let url = Url::parse(url)
+            .with_context(|| format!("invalid relayer url {}", url))?;
+        anyhow::ensure!(
+            matches!(url.scheme(), "http" | "https"),
+            "the relayer url must be http or https, got {}",
+            url
+        );
+        Ok(Self { url })
+    }
+
+    pub async fn info(&self) -> anyhow::Result<RelayerInfo> {
+        self.request("GET", "api/v1/info", None).await
+    }
+
+    /// Submits the withdraw, returning its id to poll with [Self::status].
+    pub async fn withdraw(
+        &self,
+        request: &WithdrawRequest,
+    ) -> anyhow::Result<String> {
+        let body = serde_json::to_vec(request)?;
+        let accepted: WithdrawAccepted =
+            self.request("POST", "api/v1/withdraw", Some(body)).await?;
+        Ok(accepted.id)
+    }
+
+    pub async fn status(&self, id: &str) -> anyhow::Result<WithdrawStatus> {
+        self.request("GET", &format!("api/v1/withdraw/{}", id), None)
+            .await
+    }
+
+    /// Polls the withdraw `id` until it is finalized, `on_status` is called
+    /// with every new status.
+    pub async fn wait_finalized(
+        &self,
+        id: &str,
+        mut on_status: impl FnMut(&WithdrawStatus),
+    ) -> anyhow::Result<WithdrawStatus> {
+        let mut last = None;
+        for _ in 0..MAX_POLLS {
+            let status = self.status(id).await?;
+            match status {
+                WithdrawStatus::Finalized { .. } => return Ok(status),
+                WithdrawStatus::Failed { reason } => {
+                    anyhow::bail!("the relayer failed to withdraw: {}", reason)
+                },
+                _ => {},
+            }
+            if last.as_ref() != Some(&status) {
+                on_status(&status);
+                last = Some(status);
+            }
+            async_std::task::sleep(POLL_INTERVAL).await;
+        }
+        anyhow::bail!(
+            "the relayer didn't finalize the withdraw {} in time, check it \
+             later at {}",
+            id,
+            self.url
+        )
+    }
+
+    async fn request<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> anyhow::Result<T> {
+        let url = self.url.join(path)?;
+        let request = async {
+            let response = send(method, &url, body).await?;
+            parse_response(&response)
+        };
+        let (status, body) =
+            async_std::future::timeout(REQUEST_TIMEOUT, request)
+                .await
+                .with_context(|| {
+                    format!("the relayer at {} timed out", self.url)
+                })?
+                .with_context(|| {
+                    format!("requesting the relayer at {}", url)
+                })?;
+        if !(200..300).contains(&status) {
+            return match serde_json::from_slice::<RelayerError>(&body) {
+                Ok(e) => Err(anyhow::anyhow!(e.explain())),
+                Err(_) => Err(anyhow::anyhow!(
+                    "the relayer answered with HTTP {}: {}",
+                    status,
+                    String::from_utf8_lossy(&body)
+                )),
+            };
+        }
+        serde_json::from_slice(&body)
+            .with_context(|| format!("unexpected answer from {}", url))
+    }
+}
+
+/// Sends an HTTP/1.1 request and reads the whole response, the connection
+/// is closed by the relayer once it answered.
+async fn send(
+    method: &str,
+    url: &Url,
+    body: Option<Vec<u8>>,
+) -> anyhow::Result<Vec<u8>> {
+    let host = url.host_str().context("the relayer url has no host")?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: webb-cli/{}\r\nAccept: \
+         application/json\r\nConnection: close\r\n",
+        method,
+        &url[url::Position::BeforePath..],
+        host,
+        env!("CARGO_PKG_VERSION"),
+    )
+    .into_bytes();
+    if let Some(ref body) = body {
+        request.extend_from_slice(
+            format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n",
+                body.len()
+            )
+            .as_bytes(),
+        );
+    }
+    request.extend_from_slice(b"\r\n");
+    request.extend(body.unwrap_or_default());
+    let stream = async_std::net::TcpStream::connect((host, port)).await?;
+    if url.scheme() == "https" {
+        let connector = async_tls::TlsConnector::default();
+        let stream = connector.connect(host, stream).await?;
+        exchange(stream, &request).await
+    } else {
+        exchange(stream, &request).await
+    }
+}
+
+async fn exchange<S>(mut stream: S, request: &[u8]) -> anyhow::Result<Vec<u8>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream.write_all(request).await?;
+    stream.flush().await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    Ok(response)
+}
+
+/// Splits a raw HTTP/1.1 response into its status code and body.
+fn parse_response(response: &[u8]) -> anyhow::Result<(u16, Vec<u8>)> {
+    let end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .context("truncated HTTP response")?;
+    let head = std::str::from_utf8(&response[..end])?;
+    let body = &response[end + 4..];
+    let mut lines = head.split("\r\n");
+    let status = lines
+        .next()
+        .and_then(|line| line.split(' ').nth(1))
+        .and_then(|code| code.parse().ok())
+        .context("malformed HTTP status line")?;
+    let mut chunked = false;
+    let mut length = None;
+    for line in lines {
+        let (name, value) = match line.find(':') {
+            Some(i) => (&line[..i], line[i + 1..].trim()),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            chunked = value.eq_ignore_ascii_case("chunked");
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = value.parse::<usize>().ok();
+        }
+    }
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        match length {
+            Some(length) => body.get(..length).unwrap_or(body).to_vec(),
+            None => body.to_vec(),
+        }
+    };
+    Ok((status, body))
+}
+
+fn decode_chunked(mut body: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let end = body
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .context("truncated HTTP chunk")?;
+        let size = std::str::from_utf8(&body[..end])?;
+        // the size could be followed by chunk extensions.
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .with_context(|| format!("malformed HTTP chunk size {}", size))?;
+        if size == 0 {
+            return Ok(decoded);
+        }
+        let chunk = body
+            .get(end + 2..end + 2 + size)
+            .context("truncated HTTP chunk")?;
+        decoded.extend_from_slice(chunk);
+        body = body.get(end + 4 + size..).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plain_and_chunked_responses() {
+        let plain = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+                      Content-Length: 13\r\n\r\n{\"id\":\"abc\"}\n";
+        let (status, body) = parse_response(plain).unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(body, b"{\"id\":\"abc\"}\n");
+
+        let chunked = b"HTTP/1.1 400 Bad Request\r\ntransfer-encoding: \
+                        chunked\r\n\r\n5\r\n{\"cod\r\n6;ext=1\r\ne\":\"x\"\r\n1\r\n}\
+                        \r\n0\r\n\r\n";
+        let (status, body) = parse_response(chunked).unwrap();
+        assert_eq!(status, 400);
+        assert_eq!(body, b"{\"code\":\"x\"}");
+
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn reads_the_withdraw_status() {
+        let pending: WithdrawStatus =
+            serde_json::from_str(r#"{"status":"pending"}"#).unwrap();
+        assert_eq!(pending, WithdrawStatus::Pending);
+        let finalized: WithdrawStatus = serde_json::from_str(
+            r#"{"status":"finalized","tx_hash":"0x01","block_hash":"0x02",
+                "block_number":7}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            finalized,
+            WithdrawStatus::Finalized {
+                tx_hash: String::from("0x01"),
+                block_hash: String::from("0x02"),
+                block_number: 7,
+            }
+        );
+        let error: RelayerError = serde_json::from_str(
+            r#"{"code":"insufficient_fee","message":"at least 10"}"#,
+        )
+        .unwrap();
+        assert!(error.explain().contains("`--fee`"));
+    }
+
+    #[test]
+    fn only_http_relayers() {
+        assert!(RelayerClient::new("https://relayer.webb.tools").is_ok());
+        assert!(RelayerClient::new("ws://relayer.webb.tools").is_err());
+        assert!(RelayerClient::new("relayer").is_err());
+    }
+}
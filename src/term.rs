@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// Options that carry secrets as their value.
+const SECRET_OPTIONS: [&str; 4] = ["--password", "-p", "--mnemonic", "-m"];
+
+/// A handle to the terminal standard output, that also tees everything
+/// written to it into the transcript file (if any).
+///
+/// It derefs to [console::Term] so it could be used for prompts, and
+/// anything written by the prompts (or read from the user) is never
+/// captured in the transcript.
+pub struct Term {
+    inner: console::Term,
+    transcript: Option<Arc<Mutex<File>>>,
+}
+
+impl Term {
+    pub fn new(transcript: Option<Arc<Mutex<File>>>) -> Self {
+        Self {
+            inner: console::Term::stdout(),
+            transcript,
+        }
+    }
+}
+
+impl Deref for Term {
+    type Target = console::Term;
+
+    fn deref(&self) -> &Self::Target { &self.inner }
+}
+
+impl Write for Term {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = (&self.inner).write(buf)?;
+        if let Some(ref transcript) = self.transcript {
+            let mut file = transcript.lock().expect("transcript poisoned");
+            file.write_all(&buf[..n])?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.inner).flush()?;
+        if let Some(ref transcript) = self.transcript {
+            transcript.lock().expect("transcript poisoned").flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Redacts the secrets passed as command line arguments, so the command
+/// line could be saved in the transcript.
+pub fn redact_args(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut redact_next = false;
+    args.into_iter()
+        .map(|arg| {
+            let redacted = if redact_next {
+                true
+            } else if SECRET_OPTIONS.iter().any(|o| arg.starts_with(o)) {
+                // either `--password=secret` or `-psecret`.
+                let (opt, value) = match arg.find('=') {
+                    Some(i) => (&arg[..i], &arg[i + 1..]),
+                    None if !arg.starts_with("--") => arg.split_at(2),
+                    None => (arg.as_str(), ""),
+                };
+                if value.is_empty() {
+                    redact_next = SECRET_OPTIONS.contains(&opt);
+                    return arg;
+                }
+                true
+            } else {
+                // a note passed as a positional argument.
+                arg.starts_with("webb.")
+            };
+            redact_next = false;
+            if redacted {
+                String::from("[REDACTED]")
+            } else {
+                arg
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(args: &str) -> String {
+        redact_args(args.split(' ').map(String::from)).join(" ")
+    }
+
+    #[test]
+    fn redacts_secret_options() {
+        assert_eq!(
+            redact("webb --unsafe -p secret account list"),
+            "webb --unsafe -p [REDACTED] account list"
+        );
+        assert_eq!(
+            redact("webb --password=secret account list"),
+            "webb [REDACTED] account list"
+        );
+        assert_eq!(redact("webb -psecret show"), "webb [REDACTED] show");
+        assert_eq!(
+            redact("webb account import -a me --mnemonic word"),
+            "webb account import -a me --mnemonic [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn redacts_notes() {
+        assert_eq!(
+            redact("webb mixer import-note webb.mix-v1-EDG-0-abcd"),
+            "webb mixer import-note [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn keeps_other_args() {
+        assert_eq!(
+            redact("webb --password-filename pw.txt show home"),
+            "webb --password-filename pw.txt show home"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use codec::Encode;
+use jsonrpsee_types::jsonrpc::Params;
+use serde::Deserialize;
+use subxt::sp_runtime::traits::{BlakeTwo256, Hash as _};
+use subxt::{
+    Call, Client, EventStorageSubscription, EventSubscription, RpcClient,
+    Signer,
+};
+use webb_cli::error::Error;
+use webb_cli::runtime::{Hash, WebbRuntime};
+
+/// How often we poll the finalized head while waiting for confirmations.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Possible transaction status events.
+///
+/// This is copied from `subxt`, since it does not export it, and it must be
+/// kept compatible with the substrate transaction pool.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionStatus {
+    /// Transaction is part of the future queue.
+    Future,
+    /// Transaction is part of the ready queue.
+    Ready,
+    /// The transaction has been broadcast to the given peers.
+    Broadcast(Vec<String>),
+    /// Transaction has been included in block with given hash.
+    InBlock(Hash),
+    /// The block this transaction was included in has been retracted.
+    Retracted(Hash),
+    /// Maximum number of finality watchers has been reached,
+    /// old watchers are being removed.
+    FinalityTimeout(Hash),
+    /// Transaction has been finalized by a finality-gadget, e.g GRANDPA
+    Finalized(Hash),
+    /// Transaction has been replaced in the pool, by another transaction
+    /// that provides the same tags. (e.g. same (sender, nonce)).
+    Usurped(Hash),
+    /// Transaction has been dropped from the pool because of the limit.
+    Dropped,
+    /// Transaction is no longer valid in the current state.
+    Invalid,
+}
+
+/// The outcome of a successfully submitted transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxOutcome {
+    /// The transaction hash.
+    pub xt_hash: Hash,
+    /// The hash of the block that included the transaction.
+    pub block_hash: Hash,
+}
+
+/// Signs and submits the `call`, then watches it until it gets finalized.
+///
+/// if `confirmations` is provided, we stop waiting for the `Finalized` status
+/// once the transaction is `InBlock`, and instead poll the finalized head
+/// until the transaction block has that many finalized confirmations.
+/// This is useful for nodes (or proxies) that don't reliably emit the
+/// `Finalized` status.
+pub async fn sign_and_watch<C>(
+    client: &Client<WebbRuntime>,
+    rpc_client: &RpcClient,
+    call: C,
+    signer: &(dyn Signer<WebbRuntime> + Send + Sync),
+    confirmations: Option<u32>,
+) -> anyhow::Result<TxOutcome>
+where
+    C: Call<WebbRuntime> + Send + Sync,
+{
+    let xt = client.create_signed(call, signer).await?;
+    let xt_hash = BlakeTwo256::hash_of(&xt);
+    let encoded = format!("0x{}", hex::encode(xt.encode()));
+    // subscribed before submitting, so the events of the transaction block
+    // are never missed.
+    let mut events = Some(client.subscribe_events().await?);
+    log::debug!("submitting transaction {:?}", xt_hash);
+    let mut sub = rpc_client
+        .subscribe::<TransactionStatus>(
+            "author_submitAndWatchExtrinsic",
+            Params::Array(vec![encoded.into()]),
+            "author_unwatchExtrinsic",
+        )
+        .await?;
+    let mut last = None;
+    while let Some(status) = sub.next().await {
+        log::info!(
+            "tx_hash={:?} status={} block_hash={}",
+            xt_hash,
+            status.name(),
+            status
+                .block_hash()
+                .map(|h| format!("{:?}", h))
+                .unwrap_or_else(|| String::from("-"))
+        );
+        let step = next_step(&status, confirmations);
+        last = Some(status);
+        match step {
+            Step::Wait => continue,
+            Step::Confirm(block_hash, n) => {
+                if let Some(events) = events.take() {
+                    ensure_dispatched(client, events, block_hash, xt_hash)
+                        .await?;
+                }
+                wait_for_confirmations(client, block_hash, n).await?;
+                return Ok(TxOutcome {
+                    xt_hash,
+                    block_hash,
+                });
+            },
+            Step::Done(block_hash) => {
+                if let Some(events) = events.take() {
+                    ensure_dispatched(client, events, block_hash, xt_hash)
+                        .await?;
+                }
+                return Ok(TxOutcome {
+                    xt_hash,
+                    block_hash,
+                });
+            },
+            Step::Failed(reason) => {
+                anyhow::bail!("transaction {:?} {}", xt_hash, reason)
+            },
+        }
+    }
+    match last {
+        Some(status) => anyhow::bail!(
+            "transaction subscription dropped, the last status was {}",
+            status.name()
+        ),
+        None => anyhow::bail!("transaction subscription dropped"),
+    }
+}
+
+/// Makes sure the transaction `xt_hash`, included in the `block_hash`,
+/// did not fail, by looking for an `ExtrinsicFailed` event in its `events`.
+///
+/// the failure is decoded using the runtime metadata, so it names the
+/// pallet error, i.e `InsufficientBalance from Mixer`.
+async fn ensure_dispatched(
+    client: &Client<WebbRuntime>,
+    events: EventStorageSubscription<WebbRuntime>,
+    block_hash: Hash,
+    xt_hash: Hash,
+) -> anyhow::Result<()> {
+    let block = client
+        .block(Some(block_hash))
+        .await?
+        .context("transaction block not found")?;
+    let index = block
+        .block
+        .extrinsics
+        .iter()
+        .position(|xt| BlakeTwo256::hash_of(xt) == xt_hash)
+        .context("transaction not found in its block")?;
+    let mut events = EventSubscription::new(events, client.events_decoder());
+    events.filter_extrinsic(block_hash, index);
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(event) => {
+                log::debug!("event {}::{}", event.module, event.variant)
+            },
+            Err(subxt::Error::Runtime(e)) => {
+                log::info!(
+                    "tx_hash={:?} status=failed block_hash={:?}",
+                    xt_hash,
+                    block_hash
+                );
+                return Err(Error::DispatchFailed(e).into());
+            },
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+/// What to do after getting a transaction status, see [next_step].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    /// Keep watching the transaction.
+    Wait,
+    /// The transaction is in the block, wait for that many confirmations.
+    Confirm(Hash, u32),
+    /// The transaction got finalized in the block.
+    Done(Hash),
+    /// The transaction failed, for the reason.
+    Failed(&'static str),
+}
+
+/// Decides what to do with the `status` of a transaction, when waiting for
+/// `confirmations` (if any) instead of the `Finalized` status.
+fn next_step(status: &TransactionStatus, confirmations: Option<u32>) -> Step {
+    match *status {
+        TransactionStatus::Future
+        | TransactionStatus::Ready
+        | TransactionStatus::Broadcast(_) => Step::Wait,
+        TransactionStatus::InBlock(block_hash) => match confirmations {
+            Some(n) => Step::Confirm(block_hash, n),
+            None => Step::Wait,
+        },
+        TransactionStatus::Finalized(block_hash) => Step::Done(block_hash),
+        TransactionStatus::Invalid => Step::Failed("invalid"),
+        TransactionStatus::Usurped(_) => Step::Failed("usurped"),
+        TransactionStatus::Dropped => Step::Failed("dropped"),
+        TransactionStatus::Retracted(_) => Step::Failed("retracted"),
+        TransactionStatus::FinalityTimeout(_) => {
+            Step::Failed("finality timeout")
+        },
+    }
+}
+
+impl TransactionStatus {
+    /// The status name, as logged.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionStatus::Future => "future",
+            TransactionStatus::Ready => "ready",
+            TransactionStatus::Broadcast(_) => "broadcast",
+            TransactionStatus::InBlock(_) => "in_block",
+            TransactionStatus::Retracted(_) => "retracted",
+            TransactionStatus::FinalityTimeout(_) => "finality_timeout",
+            TransactionStatus::Finalized(_) => "finalized",
+            TransactionStatus::Usurped(_) => "usurped",
+            TransactionStatus::Dropped => "dropped",
+            TransactionStatus::Invalid => "invalid",
+        }
+    }
+
+    /// The block hash carried by the status, if any.
+    pub fn block_hash(&self) -> Option<Hash> {
+        match *self {
+            TransactionStatus::InBlock(h)
+            | TransactionStatus::Retracted(h)
+            | TransactionStatus::FinalityTimeout(h)
+            | TransactionStatus::Finalized(h)
+            | TransactionStatus::Usurped(h) => Some(h),
+            _ => None,
+        }
+    }
+}
+
+/// Polls the finalized head until `block_hash` is an ancestor of it,
+/// with at least `confirmations` finalized blocks (including itself).
+async fn wait_for_confirmations(
+    client: &Client<WebbRuntime>,
+    block_hash: Hash,
+    confirmations: u32,
+) -> anyhow::Result<()> {
+    let number = client
+        .header(Some(block_hash))
+        .await?
+        .context("transaction block not found")?
+        .number;
+    loop {
+        let finalized_hash = client.finalized_head().await?;
+        let finalized = client
+            .header(Some(finalized_hash))
+            .await?
+            .context("finalized block not found")?
+            .number;
+        if finalized >= number {
+            let canonical = client.block_hash(Some(number.into())).await?;
+            if canonical != Some(block_hash) {
+                anyhow::bail!("transaction block #{} got retracted", number);
+            }
+            let confirmed = finalized - number + 1;
+            log::debug!(
+                "block #{} has {}/{} confirmations",
+                number,
+                confirmed,
+                confirmations
+            );
+            if confirmed >= confirmations {
+                return Ok(());
+            }
+        }
+        async_std::task::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Estimates the fee of the `call` signed by `signer` using the node
+/// `payment_queryInfo` RPC, in base units.
+///
+/// The fee doesn't depend on who signs the call, so a throwaway signer could
+/// be used to estimate the fee without asking for a password.
+pub async fn estimate_fee<C>(
+    client: &Client<WebbRuntime>,
+    rpc_client: &RpcClient,
+    call: C,
+    signer: &(dyn Signer<WebbRuntime> + Send + Sync),
+) -> anyhow::Result<u128>
+where
+    C: Call<WebbRuntime> + Send + Sync,
+{
+    let xt = client.create_signed(call, signer).await?;
+    let encoded = format!("0x{}", hex::encode(xt.encode()));
+    let info: serde_json::Value = rpc_client
+        .request("payment_queryInfo", Params::Array(vec![encoded.into()]))
+        .await?;
+    // depending on the node version, the fee is either a number or a string.
+    let fee = match &info["partialFee"] {
+        serde_json::Value::Number(n) => n.as_u64().map(u128::from),
+        serde_json::Value::String(s) => s.parse().ok(),
+        _ => None,
+    };
+    fee.with_context(|| format!("unexpected fee info: {}", info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finalized_or_confirmed_transactions_are_done() {
+        let hash = Hash::repeat_byte(1);
+        let in_block = TransactionStatus::InBlock(hash);
+        assert_eq!(next_step(&in_block, None), Step::Wait);
+        assert_eq!(next_step(&in_block, Some(2)), Step::Confirm(hash, 2));
+        let finalized = TransactionStatus::Finalized(hash);
+        assert_eq!(next_step(&finalized, None), Step::Done(hash));
+        assert_eq!(next_step(&TransactionStatus::Ready, None), Step::Wait);
+        assert_eq!(
+            next_step(&TransactionStatus::Dropped, Some(2)),
+            Step::Failed("dropped")
+        );
+        assert_eq!(
+            next_step(&TransactionStatus::Retracted(hash), None),
+            Step::Failed("retracted")
+        );
+        assert_eq!(in_block.block_hash(), Some(hash));
+        assert_eq!(TransactionStatus::Invalid.block_hash(), None);
+    }
+}
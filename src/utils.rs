@@ -1,34 +1,98 @@
+use std::convert::TryFrom;
+use std::future::Future;
 use std::io::Write;
+use std::time::Duration;
 
 use anyhow::{Context, Result};
-use bip39::{Language, Mnemonic};
+use bip39::Mnemonic;
 use console::style;
+use futures::StreamExt;
 use secrecy::{ExposeSecret, SecretString};
 use sha2::Digest;
+use subxt::sp_core::crypto::{AccountId32, Ss58AddressFormat, Ss58Codec};
+use webb_cli::keystore::parse_mnemonic;
 
 /// Parse a sercret string, returning a displayable error.
 pub fn secret_string_from_str(s: &str) -> Result<SecretString> {
     std::str::FromStr::from_str(s).context("read secret string")
 }
 
+/// Opens the file at `path` with the `options`, readable only by the owner,
+/// for writing secrets to it.
+pub fn open_private(
+    options: std::fs::OpenOptions,
+    path: &std::path::Path,
+) -> std::io::Result<std::fs::File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let mut options = options;
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // the mode only applies to new files, so make sure an existing file
+        // is restricted too, before writing the secrets to it.
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        options.open(path)
+    }
+}
+
+/// The word counts of the bip39 mnemonic phrases.
+const MNEMONIC_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+/// Asks for a mnemonic phrase until a valid one is entered, the words could
+/// be pasted all on one line or over several lines, separated by any
+/// whitespace.
 pub fn ask_for_phrase(prompt: &str) -> Result<Mnemonic> {
     let mut term = console::Term::stdout();
     loop {
         writeln!(term, "{}", style(prompt).bold().yellow())?;
-        let mut words = Vec::with_capacity(12);
-        while words.len() < 12 {
-            let line = term.read_line()?;
-            for word in line.split(' ') {
-                words.push(word.trim().to_string());
-            }
+        let lines = std::iter::from_fn(|| Some(term.read_line()));
+        match read_phrase(lines)? {
+            Ok(mnemonic) => return Ok(mnemonic),
+            Err(e) => writeln!(term, "{}", e)?,
         }
-        if let Ok(mnemonic) =
-            Mnemonic::from_phrase(&words.join(" "), Language::English)
-        {
-            return Ok(mnemonic);
+    }
+}
+
+/// Reads the words of a mnemonic phrase from the `lines`, a phrase on a
+/// single line is taken as soon as it is valid, but once it spans several
+/// lines it ends with a blank line (or the end of the input) unless it has
+/// 24 words, since a longer phrase may start with a valid shorter one.
+///
+/// the outer error is from reading the lines, the inner one is why the
+/// words are not a valid phrase.
+fn read_phrase(
+    lines: impl IntoIterator<Item = std::io::Result<String>>,
+) -> std::io::Result<Result<Mnemonic, String>> {
+    let mut words = Vec::with_capacity(24);
+    let mut read_lines = 0;
+    for line in lines {
+        let line = line?;
+        let before = words.len();
+        words.extend(line.split_whitespace().map(ToOwned::to_owned));
+        if words.len() == before {
+            break;
+        }
+        read_lines += 1;
+        let done = words.len() >= *MNEMONIC_WORD_COUNTS.last().unwrap();
+        let pasted =
+            read_lines == 1 && MNEMONIC_WORD_COUNTS.contains(&words.len());
+        if !done && !pasted {
+            continue;
+        }
+        let phrase = words.join(" ");
+        match parse_mnemonic(&phrase) {
+            Ok(mnemonic) => return Ok(Ok(mnemonic)),
+            Err(e) if done => return Ok(Err(e.to_string())),
+            Err(_) => continue,
         }
-        writeln!(term, "Invalid mnemonic")?;
     }
+    // a blank line, or we ran out of lines.
+    Ok(parse_mnemonic(&words.join(" ")).map_err(|e| e.to_string()))
 }
 
 pub fn sha256(s: SecretString) -> Vec<u8> {
@@ -36,3 +100,283 @@ pub fn sha256(s: SecretString) -> Vec<u8> {
     hasher.update(s.expose_secret());
     hasher.finalize().to_vec()
 }
+
+/// Formats a raw on-chain balance (in base units) into a human readable
+/// amount using the token `decimals`, i.e `1500000000000` with 12 decimals
+/// becomes `1.5 EDG`.
+pub fn format_balance(raw: u128, decimals: u8, symbol: &str) -> String {
+    let base = match 10u128.checked_pow(u32::from(decimals)) {
+        Some(base) => base,
+        None => return format!("{} {}", raw, symbol),
+    };
+    let whole = raw / base;
+    let fraction = raw % base;
+    if fraction == 0 {
+        return format!("{} {}", whole, symbol);
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{} {}", whole, fraction.trim_end_matches('0'), symbol)
+}
+
+/// The largest ss58 address format, the prefix only has 14 bits.
+pub const MAX_SS58_FORMAT: u16 = 16_383;
+
+/// Parses a `--ss58-format` value.
+pub fn ss58_format_from_str(s: &str) -> Result<u16> {
+    let format: u32 = s.parse().context("the ss58 format is a number")?;
+    anyhow::ensure!(
+        format <= u32::from(MAX_SS58_FORMAT),
+        "the ss58 format must be at most {}",
+        MAX_SS58_FORMAT
+    );
+    Ok(format as u16)
+}
+
+/// Re-encodes the ss58 `address` (in any format) with the `format` prefix.
+pub fn reformat_address(address: &str, format: u16) -> Result<String> {
+    let (account, _) = AccountId32::from_ss58check_with_version(address)
+        .map_err(|e| anyhow::anyhow!("bad address {}: {:?}", address, e))?;
+    let format = Ss58AddressFormat::try_from(format)
+        .map_err(|_| anyhow::anyhow!("unknown ss58 format {}", format))?;
+    Ok(account.to_ss58check_with_version(format))
+}
+
+/// Runs the `queries` concurrently, with at most `limit` of them in-flight
+/// at once, and returns their results in the same order.
+///
+/// this is what the commands that issue many RPC requests should use, with
+/// the `--rpc-concurrency` limit, so they don't overwhelm the node.
+pub fn fetch_concurrently<I, F>(
+    limit: usize,
+    queries: I,
+) -> impl Future<Output = Vec<F::Output>>
+where
+    I: IntoIterator<Item = F>,
+    F: Future,
+{
+    // collected upfront, so the returned future doesn't hold the iterator
+    // (and its closures) across the await points.
+    let queries: Vec<_> = queries.into_iter().collect();
+    futures::stream::iter(queries)
+        .buffered(limit.max(1))
+        .collect()
+}
+
+/// Runs `op` until it succeeds, at most `attempts` times, waiting `delay`
+/// before the first retry and doubling it before each of the next ones.
+///
+/// `op` gets the attempt number (starting at 1), and the last error is
+/// returned if all of the attempts failed.
+pub async fn retry_with_backoff<T, F, Fut>(
+    attempts: u32,
+    mut delay: Duration,
+    mut op: F,
+) -> Result<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op(attempt).await {
+            Ok(v) => return Ok(v),
+            Err(e) if attempt < attempts => {
+                log::warn!(
+                    "attempt {}/{} failed: {:#}, retrying in {:?}",
+                    attempt,
+                    attempts,
+                    e,
+                    delay
+                );
+                async_std::task::sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use bip39::Language;
+
+    use super::*;
+
+    fn lines(input: &[&str]) -> Vec<std::io::Result<String>> {
+        input.iter().map(|line| Ok(line.to_string())).collect()
+    }
+
+    #[test]
+    fn reads_12_and_24_words_phrases() {
+        let twelve =
+            Mnemonic::new(bip39::MnemonicType::Words12, Language::English);
+        let read = read_phrase(lines(&[twelve.phrase()])).unwrap().unwrap();
+        assert_eq!(read.phrase(), twelve.phrase());
+
+        // none of its 12 to 21 words prefixes is a valid phrase.
+        let long =
+            Mnemonic::from_entropy(&[0u8; 32], Language::English).unwrap();
+        // one word per line, with trailing whitespace.
+        let words: Vec<_> = long
+            .phrase()
+            .split(' ')
+            .map(|w| format!("{}  \t", w))
+            .collect();
+        let words: Vec<_> = words.iter().map(String::as_str).collect();
+        let read = read_phrase(lines(&words)).unwrap().unwrap();
+        assert_eq!(read.phrase(), long.phrase());
+
+        let padded = format!("  {}   ", long.phrase());
+        let read = read_phrase(lines(&[&padded])).unwrap().unwrap();
+        assert_eq!(read.phrase(), long.phrase());
+    }
+
+    #[test]
+    fn keeps_reading_a_phrase_that_starts_with_a_shorter_one() {
+        // a 24 words phrase whose first 12 words are a valid phrase too.
+        let long = (0..=u8::MAX)
+            .map(|i| {
+                let mut entropy = [7u8; 32];
+                entropy[0] = i;
+                Mnemonic::from_entropy(&entropy, Language::English).unwrap()
+            })
+            .find(|m| {
+                let words: Vec<_> = m.phrase().split(' ').take(12).collect();
+                parse_mnemonic(&words.join(" ")).is_ok()
+            })
+            .unwrap();
+        let words: Vec<_> = long.phrase().split(' ').collect();
+        let read = read_phrase(lines(&words)).unwrap().unwrap();
+        assert_eq!(read.phrase(), long.phrase());
+
+        let first = words[..6].join(" ");
+        let second = words[6..].join(" ");
+        let read = read_phrase(lines(&[&first, &second])).unwrap().unwrap();
+        assert_eq!(read.phrase(), long.phrase());
+
+        // the shorter phrase ends with a blank line.
+        let mut short = words[..12].to_vec();
+        short.extend(&["", "ignored"]);
+        let read = read_phrase(lines(&short)).unwrap().unwrap();
+        assert_eq!(read.phrase(), words[..12].join(" "));
+    }
+
+    #[test]
+    fn reads_a_pasted_phrase() {
+        let phrase =
+            Mnemonic::new(bip39::MnemonicType::Words12, Language::English);
+        let words: Vec<_> = phrase.phrase().split(' ').collect();
+        let line = words.join(" ");
+        let read = read_phrase(lines(&[&line])).unwrap().unwrap();
+        assert_eq!(read.phrase(), phrase.phrase());
+
+        // tabs, repeated spaces and a line break in the middle.
+        let first = format!("\t{}   {}\t", words[..5].join("  "), words[5]);
+        let second = format!(" {}  \r", words[6..].join(" \t "));
+        let read = read_phrase(lines(&[&first, &second])).unwrap().unwrap();
+        assert_eq!(read.phrase(), phrase.phrase());
+    }
+
+    #[test]
+    fn rejects_invalid_word_counts() {
+        let phrase =
+            Mnemonic::new(bip39::MnemonicType::Words12, Language::English);
+        let words: Vec<_> = phrase.phrase().split(' ').take(10).collect();
+        let err = read_phrase(lines(&[&words.join(" "), ""]))
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("got 10"), "{}", err);
+        // running out of lines is the same as a blank line.
+        let err = read_phrase(lines(&["abandon abandon"]))
+            .unwrap()
+            .unwrap_err();
+        assert!(err.contains("got 2"), "{}", err);
+    }
+
+    #[test]
+    fn same_key_under_two_ss58_formats() {
+        let account = AccountId32::from([7u8; 32]);
+        let generic = account.to_ss58check();
+        let edgeware = reformat_address(&generic, 7).unwrap();
+        let kusama = reformat_address(&generic, 2).unwrap();
+        assert_ne!(edgeware, kusama);
+        assert_ne!(edgeware, generic);
+        // any of them can be turned back into the others.
+        assert_eq!(reformat_address(&kusama, 42).unwrap(), generic);
+        assert_eq!(reformat_address(&kusama, 7).unwrap(), edgeware);
+        // the two bytes prefixes too.
+        let custom = reformat_address(&generic, 1000).unwrap();
+        assert_eq!(reformat_address(&custom, 42).unwrap(), generic);
+        assert!(reformat_address("not an address", 42).is_err());
+    }
+
+    #[test]
+    fn ss58_format_must_fit_the_prefix() {
+        assert_eq!(ss58_format_from_str("42").unwrap(), 42);
+        assert_eq!(ss58_format_from_str("16383").unwrap(), 16_383);
+        assert!(ss58_format_from_str("16384").is_err());
+        assert!(ss58_format_from_str("99999").is_err());
+        assert!(ss58_format_from_str("edgeware").is_err());
+    }
+
+    #[test]
+    fn format_whole_balance() {
+        assert_eq!(format_balance(1_000_000_000_000, 12, "EDG"), "1 EDG");
+        assert_eq!(format_balance(0, 12, "EDG"), "0 EDG");
+    }
+
+    #[test]
+    fn format_fractional_balance() {
+        assert_eq!(format_balance(1_500_000_000_000, 12, "EDG"), "1.5 EDG");
+        assert_eq!(format_balance(1, 12, "EDG"), "0.000000000001 EDG");
+        assert_eq!(format_balance(12345, 0, "Unit"), "12345 Unit");
+    }
+
+    #[test]
+    fn fetch_concurrently_is_bounded_and_ordered() {
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let queries = (0..20).map(|i| {
+            let in_flight = &in_flight;
+            let max_in_flight = &max_in_flight;
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                async_std::task::yield_now().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                i
+            }
+        });
+        let results = async_std::task::block_on(fetch_concurrently(3, queries));
+        assert_eq!(results, (0..20).collect::<Vec<_>>());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn retries_until_the_last_attempt() {
+        let calls = AtomicUsize::new(0);
+        let result: Result<()> = async_std::task::block_on(retry_with_backoff(
+            4,
+            Duration::from_millis(1),
+            |_| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { anyhow::bail!("nope") }
+            },
+        ));
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        let result = async_std::task::block_on(retry_with_backoff(
+            4,
+            Duration::from_millis(1),
+            |attempt| async move {
+                anyhow::ensure!(attempt == 2, "not yet");
+                Ok(attempt)
+            },
+        ));
+        assert_eq!(result.unwrap(), 2);
+    }
+}